@@ -0,0 +1,99 @@
+use nalgebra_glm::Vec3;
+
+use crate::ray::Ray;
+
+// Caja alineada a los ejes
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    // Prueba de las placas: si el rayo atraviesa la caja dentro de su intervalo [t_min, t_max]
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        let mut t_near = ray.t_min;
+        let mut t_far = ray.t_max;
+        for axis in 0..3 {
+            let inverse = 1.0 / ray.direction[axis];
+            let t1 = (self.min[axis] - ray.origin[axis]) * inverse;
+            let t2 = (self.max[axis] - ray.origin[axis]) * inverse;
+            t_near = t_near.max(t1.min(t2));
+            t_far = t_far.min(t1.max(t2));
+        }
+        t_near <= t_far
+    }
+
+    // Caja que contiene a todas las de la lista, o None si está vacía
+    pub fn enclosing(boxes: impl IntoIterator<Item = Aabb>) -> Option<Aabb> {
+        boxes.into_iter().reduce(|a, b| a.union(&b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray::new(Vec3::new(origin[0], origin[1], origin[2]), Vec3::new(direction[0], direction[1], direction[2]))
+    }
+
+    #[test]
+    fn ray_through_the_box_hits() {
+        assert!(unit_box().intersects(&ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0])));
+        assert!(unit_box().intersects(&ray([3.0, 3.0, 3.0], [-1.0, -1.0, -1.0])));
+        // Desde adentro, hacia cualquier lado
+        assert!(unit_box().intersects(&ray([0.0, 0.0, 0.0], [0.3, -0.4, 0.5])));
+    }
+
+    #[test]
+    fn ray_beside_or_away_misses() {
+        assert!(!unit_box().intersects(&ray([2.0, 0.0, 5.0], [0.0, 0.0, -1.0])));
+        assert!(!unit_box().intersects(&ray([0.0, 0.0, 5.0], [0.0, 0.0, 1.0])));
+        assert!(!unit_box().intersects(&ray([3.0, 0.0, 3.0], [-1.0, 0.0, 1.0])));
+    }
+
+    // La caja está entre t = 4 y t = 6 del rayo
+    #[test]
+    fn respects_the_ray_interval() {
+        let mut ray = ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        ray.t_max = 3.0;
+        assert!(!unit_box().intersects(&ray));
+        ray.t_max = 4.5;
+        assert!(unit_box().intersects(&ray));
+        ray.t_min = 7.0;
+        ray.t_max = f32::INFINITY;
+        assert!(!unit_box().intersects(&ray));
+    }
+
+    // Paralelo a un eje, la placa de ese eje da ±infinito y solo importa si el origen está entre sus caras
+    #[test]
+    fn axis_parallel_rays() {
+        assert!(unit_box().intersects(&ray([0.5, 0.5, 5.0], [0.0, 0.0, -1.0])));
+        assert!(!unit_box().intersects(&ray([1.5, 0.5, 5.0], [0.0, 0.0, -1.0])));
+        assert!(!unit_box().intersects(&ray([0.5, -1.5, 5.0], [0.0, 0.0, -1.0])));
+    }
+}
@@ -0,0 +1,176 @@
+// background.rs
+
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use image::{DynamicImage, Rgb32FImage};
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+use crate::color_management::srgb_to_linear;
+
+// Lo que ven los rayos que no golpean nada (primarios y secundarios por igual)
+#[derive(Clone)]
+pub enum Background {
+    Solid(Color),
+    // Degradado vertical: `horizon` hacia abajo y en el horizonte, `zenith` mirando hacia arriba
+    Gradient { horizon: Color, zenith: Color },
+    // Mapa de entorno equirectangular (longitud en X, latitud en Y). Se carga una sola vez y
+    // las copias de los ajustes la comparten. Se guarda en flotantes en la escala 0-255 de
+    // los colores: un HDR/EXR puede pasarse de 255 (el sol)
+    Image(Arc<Rgb32FImage>),
+    // Cubo de seis caras en el orden +X, -X, +Y, -Y, +Z, -Z
+    Cubemap(Arc<[Rgb32FImage; 6]>),
+}
+
+// Nombres de las caras de un cubemap dentro de su carpeta, con cualquier extensión
+const CUBEMAP_FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::from_srgb(135.0, 206.0, 235.0))
+    }
+}
+
+impl fmt::Debug for Background {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Background::Solid(color) => write!(f, "Solid({:?})", color),
+            Background::Gradient { horizon, zenith } => write!(f, "Gradient({:?}, {:?})", horizon, zenith),
+            Background::Image(image) => write!(f, "Image({}x{})", image.width(), image.height()),
+            Background::Cubemap(faces) => write!(f, "Cubemap({}x{})", faces[0].width(), faces[0].height()),
+        }
+    }
+}
+
+fn parse_color(text: &str) -> Result<Color, Box<dyn Error>> {
+    Color::parse(text).ok_or_else(|| format!("se esperaba R,G,B: {}", text).into())
+}
+
+impl Background {
+    // "R,G,B" es un color sólido, "R,G,B:R,G,B" un degradado del horizonte al cenit
+    // y cualquier otra cosa la ruta de una imagen de entorno o de la carpeta de un cubemap
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        if text.split([',', ':']).all(|value| value.trim().parse::<f32>().is_ok()) {
+            return match text.split_once(':') {
+                Some((horizon, zenith)) => Ok(Background::Gradient { horizon: parse_color(horizon)?, zenith: parse_color(zenith)? }),
+                None => Ok(Background::Solid(parse_color(text)?)),
+            };
+        }
+        if Path::new(text).is_dir() {
+            return Background::load_cubemap(text);
+        }
+        Background::load_image(text)
+    }
+
+    pub fn load_image(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Background::Image(Arc::new(load_linear(Path::new(path))?)))
+    }
+
+    // Carpeta con px, nx, py, ny, pz y nz (.png, .jpg, .hdr, .exr...), todas del mismo tamaño
+    pub fn load_cubemap(directory: &str) -> Result<Self, Box<dyn Error>> {
+        let entries = std::fs::read_dir(directory).map_err(|error| format!("{}: {}", directory, error))?;
+        let mut files: Vec<_> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+        files.sort();
+        let mut faces = Vec::with_capacity(6);
+        for name in CUBEMAP_FACES {
+            let path = files
+                .iter()
+                .find(|path| path.file_stem().is_some_and(|stem| stem.eq_ignore_ascii_case(name)))
+                .ok_or_else(|| format!("falta la cara {} del cubemap en {}", name, directory))?;
+            faces.push(load_linear(path)?);
+        }
+        if faces.iter().any(|face| face.dimensions() != faces[0].dimensions()) {
+            return Err(format!("las caras del cubemap en {} no son del mismo tamaño", directory).into());
+        }
+        let faces: [Rgb32FImage; 6] = faces.try_into().map_err(|_| "se esperaban seis caras")?;
+        Ok(Background::Cubemap(Arc::new(faces)))
+    }
+
+    pub fn color(&self, direction: &Vec3) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { horizon, zenith } => {
+                let height = direction.normalize().y.max(0.0);
+                horizon.blend(*zenith, height)
+            }
+            Background::Image(image) => {
+                let direction = direction.normalize();
+                let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+                let v = 0.5 - direction.y.clamp(-1.0, 1.0).asin() / PI;
+                texel(image, u, v)
+            }
+            Background::Cubemap(faces) => {
+                // Eje dominante: elige la cara y las otras dos componentes dan la posición en ella.
+                // Las caras siguen la convención de OpenGL, que es zurda; se invierte X para que
+                // no se vean espejadas en esta escena diestra (lo mismo que hace three.js)
+                let [x, y, z] = [-direction.x, direction.y, direction.z];
+                let (face, major, u, v) = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+                    if x > 0.0 { (0, x, -z, -y) } else { (1, -x, z, -y) }
+                } else if y.abs() >= z.abs() {
+                    if y > 0.0 { (2, y, x, z) } else { (3, -y, x, -z) }
+                } else if z > 0.0 {
+                    (4, z, x, -y)
+                } else {
+                    (5, -z, -x, -y)
+                };
+                if major <= 0.0 {
+                    return Color::black();
+                }
+                texel(&faces[face], 0.5 * (u / major + 1.0), 0.5 * (v / major + 1.0))
+            }
+        }
+    }
+}
+
+// Imagen en flotantes lineales con la escala 0-255 de los colores. Las de 8 y 16 bits
+// vienen en sRGB y se linealizan; las HDR/EXR ya traen radiancia lineal y conservan lo que
+// pasa de 1.0
+fn load_linear(path: &Path) -> Result<Rgb32FImage, Box<dyn Error>> {
+    let image = image::open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let is_float = matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+    let mut image = image.to_rgb32f();
+    image.pixels_mut().for_each(|pixel| {
+        pixel.0 = pixel.0.map(|value| if is_float { value.max(0.0) } else { srgb_to_linear(value) } * 255.0)
+    });
+    Ok(image)
+}
+
+// Pixel más cercano a (u, v) en [0, 1]
+fn texel(image: &Rgb32FImage, u: f32, v: f32) -> Color {
+    let x = ((u * image.width() as f32) as u32).min(image.width() - 1);
+    let y = ((v * image.height() as f32) as u32).min(image.height() - 1);
+    let [r, g, b] = image.get_pixel(x, y).0;
+    Color::new(r, g, b)
+}
+
+// Fondo en un archivo de escena, por ejemplo
+// background: Some(Gradient(horizon: (200, 220, 240), zenith: (60, 110, 200)))
+// o background: Some(Cubemap("cielo")), una carpeta junto a la escena
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum BackgroundDescription {
+    Solid([f32; 3]),
+    Gradient { horizon: [f32; 3], zenith: [f32; 3] },
+    Image(String),
+    Cubemap(String),
+}
+
+impl BackgroundDescription {
+    // Las rutas son relativas a `dir`, la carpeta de la escena
+    pub fn build(&self, dir: &Path) -> Result<Background, Box<dyn Error>> {
+        let color = |[r, g, b]: [f32; 3]| Color::from_srgb(r, g, b);
+        Ok(match self {
+            BackgroundDescription::Solid(rgb) => Background::Solid(color(*rgb)),
+            BackgroundDescription::Gradient { horizon, zenith } => {
+                Background::Gradient { horizon: color(*horizon), zenith: color(*zenith) }
+            }
+            BackgroundDescription::Image(path) => Background::load_image(&dir.join(path).to_string_lossy())?,
+            BackgroundDescription::Cubemap(directory) => Background::load_cubemap(&dir.join(directory).to_string_lossy())?,
+        })
+    }
+}
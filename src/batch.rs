@@ -0,0 +1,230 @@
+// batch.rs
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Command;
+
+use image::{imageops, RgbImage};
+use rayon::prelude::*;
+
+use crate::export::{OutputFormat, framebuffer_to_image};
+use crate::font;
+use crate::framebuffer::Framebuffer;
+
+// Tamaño de cada miniatura de la hoja de contactos
+const TILE_WIDTH: u32 = 320;
+const TILE_HEIGHT: u32 = 240;
+
+// Opciones del render que no llevan valor; todas las demás se llevan el argumento que sigue,
+// aunque termine en .ron
+const FLAGS: [&str; 14] = [
+    "--auto-exposure",
+    "--auto-frame",
+    "--debug-nan",
+    "--floor",
+    "--guides",
+    "--half-res",
+    "--headless",
+    "--lens-flare",
+    "--light-shafts",
+    "--profile-passes",
+    "--shading-cache",
+    "--taa",
+    "--tag-color-space",
+    "--terminal",
+];
+
+// Una escena del lote con las opciones que solo valen para ella
+pub struct BatchScene {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+}
+
+// `batch escenas/*.ron --out renders/`: cada escena se renderiza en su propio proceso, con las
+// mismas opciones que un render suelto, y al final se arma una hoja con todas
+pub struct Batch {
+    pub scenes: Vec<BatchScene>,
+    // Opciones de todas las escenas
+    pub common: Vec<String>,
+    pub out: PathBuf,
+    // Procesos a la vez
+    pub jobs: usize,
+}
+
+impl Batch {
+    // `args` son los que siguen a `batch`. Las opciones antes de la primera escena valen para
+    // todas y las que siguen a una escena solo para ella, por ejemplo
+    // `batch --samples 4 casa.ron patio.ron --set camera.fov=30 --out renders/`. Las escenas
+    // son los argumentos sueltos, los que no son una opción ni el valor de una
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut batch = Batch { scenes: Vec::new(), common: Vec::new(), out: PathBuf::from("renders"), jobs: 1 };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let options = match batch.scenes.last_mut() {
+                Some(scene) => &mut scene.args,
+                None => &mut batch.common,
+            };
+            match arg.as_str() {
+                "--out" => batch.out = PathBuf::from(args.next().ok_or("falta la carpeta de --out")?),
+                "--jobs" => {
+                    let jobs = args.next().ok_or("falta el número de --jobs")?;
+                    batch.jobs = jobs
+                        .parse()
+                        .ok()
+                        .filter(|&jobs| jobs >= 1)
+                        .ok_or_else(|| format!("--jobs debe ser un entero mayor que cero: {}", jobs))?;
+                }
+                "--scene" | "--output" => return Err(format!("{} lo pone el lote para cada escena", arg)),
+                flag if FLAGS.contains(&flag) => options.push(arg.clone()),
+                option if option.starts_with('-') => {
+                    options.push(arg.clone());
+                    options.extend(args.next().cloned());
+                }
+                path => batch.scenes.push(BatchScene { path: PathBuf::from(path), args: Vec::new() }),
+            }
+        }
+        if batch.scenes.is_empty() {
+            return Err("uso: batch <escena.ron>... [--out carpeta] [--jobs N]".to_string());
+        }
+        Ok(batch)
+    }
+
+    // 01_casa.png, 02_patio.png...: el número evita choques entre escenas de igual nombre. La
+    // extensión es la del --format de la escena o del lote, como la elegiría el render suelto
+    fn output(&self, index: usize) -> PathBuf {
+        let scene = &self.scenes[index];
+        let stem = scene.path.file_stem().unwrap_or_default().to_string_lossy();
+        let format = [&scene.args, &self.common]
+            .into_iter()
+            .find_map(|args| args.windows(2).rev().find(|pair| pair[0] == "--format"))
+            .and_then(|pair| OutputFormat::from_name(&pair[1]))
+            .unwrap_or(OutputFormat::Png);
+        self.out.join(format!("{:02}_{}.{}", index + 1, stem, format.extension()))
+    }
+}
+
+// Renderiza todas las escenas y guarda la hoja de contactos en `out/hoja.png`. Devuelve cuántas
+// fallaron; las que fallan, o cuya imagen no se puede leer, quedan en la hoja como una
+// miniatura vacía
+pub fn run(batch: &Batch) -> Result<usize, Box<dyn Error>> {
+    std::fs::create_dir_all(&batch.out)?;
+    let executable = std::env::current_exe()?;
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(batch.jobs).build()?;
+    let results: Vec<bool> = pool.install(|| {
+        batch
+            .scenes
+            .par_iter()
+            .enumerate()
+            .map(|(index, scene)| {
+                let status = Command::new(&executable)
+                    .arg("--scene")
+                    .arg(&scene.path)
+                    .arg("--output")
+                    .arg(batch.output(index))
+                    .arg("--headless")
+                    .args(&batch.common)
+                    .args(&scene.args)
+                    .status();
+                match status {
+                    Ok(status) if status.success() => true,
+                    Ok(_) => false,
+                    Err(error) => {
+                        eprintln!("{}: {}", scene.path.display(), error);
+                        false
+                    }
+                }
+            })
+            .collect()
+    });
+
+    let outputs: Vec<(String, Option<PathBuf>)> = results
+        .iter()
+        .enumerate()
+        .map(|(index, &ok)| {
+            let name = batch.scenes[index].path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            (name, ok.then(|| batch.output(index)))
+        })
+        .collect();
+    contact_sheet(&outputs).save(batch.out.join("hoja.png"))?;
+    Ok(results.iter().filter(|&&ok| !ok).count())
+}
+
+// Las imágenes reducidas a miniaturas en una cuadrícula, cada una con el nombre de su escena
+fn contact_sheet(outputs: &[(String, Option<PathBuf>)]) -> RgbImage {
+    let columns = (outputs.len() as f32).sqrt().ceil().max(1.0) as u32;
+    let rows = (outputs.len() as u32).div_ceil(columns);
+    let mut sheet = RgbImage::new(columns * TILE_WIDTH, rows * TILE_HEIGHT);
+    for (index, (name, path)) in outputs.iter().enumerate() {
+        let mut tile = Framebuffer::new(TILE_WIDTH as usize, TILE_HEIGHT as usize);
+        let image = path.as_ref().and_then(|path| match image::open(path) {
+            Ok(image) => Some(image.to_rgb8()),
+            Err(error) => {
+                eprintln!("aviso: {} queda fuera de la hoja: {}", path.display(), error);
+                None
+            }
+        });
+        let label = match image {
+            Some(image) => {
+                thumbnail(&image, &mut tile);
+                name.clone()
+            }
+            None => format!("{} ERROR", name),
+        };
+        font::draw_label(&mut tile, 4, 4, &label);
+        let (x, y) = (index as u32 % columns * TILE_WIDTH, index as u32 / columns * TILE_HEIGHT);
+        imageops::replace(&mut sheet, &framebuffer_to_image(&tile), x as i64, y as i64);
+    }
+    sheet
+}
+
+// Reduce `image` a la miniatura conservando la proporción y la centra
+fn thumbnail(image: &RgbImage, tile: &mut Framebuffer) {
+    let scale = (TILE_WIDTH as f32 / image.width() as f32).min(TILE_HEIGHT as f32 / image.height() as f32);
+    let width = ((image.width() as f32 * scale) as u32).clamp(1, TILE_WIDTH);
+    let height = ((image.height() as f32 * scale) as u32).clamp(1, TILE_HEIGHT);
+    let resized = imageops::resize(image, width, height, imageops::FilterType::Triangle);
+    let (left, top) = ((TILE_WIDTH - width) / 2, (TILE_HEIGHT - height) / 2);
+    for (x, y, pixel) in resized.enumerate_pixels() {
+        let [r, g, b] = pixel.0;
+        let index = (top + y) as usize * tile.width + (left + x) as usize;
+        tile.buffer[index] = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    // Un .ron que es el valor de una opción no es una escena del lote
+    #[test]
+    fn scenes_are_the_positional_arguments() {
+        let batch = Batch::parse(&args("--headless --samples 4 casa.ron --taa patio --set fondo=cielo.ron --jobs 2")).unwrap();
+        let paths: Vec<_> = batch.scenes.iter().map(|scene| scene.path.clone()).collect();
+        assert_eq!(paths, [PathBuf::from("casa.ron"), PathBuf::from("patio")]);
+        assert_eq!(batch.common, args("--headless --samples 4"));
+        assert_eq!(batch.scenes[0].args, args("--taa"));
+        assert_eq!(batch.scenes[1].args, args("--set fondo=cielo.ron"));
+        assert_eq!(batch.jobs, 2);
+    }
+
+    #[test]
+    fn outputs_use_the_export_extension() {
+        let batch = Batch::parse(&args("--format bmp casa.ron patio.ron --format webp jardin.ron --format png16")).unwrap();
+        let outputs: Vec<_> = (0..3).map(|index| batch.output(index)).collect();
+        let out = PathBuf::from("renders");
+        assert_eq!(outputs, [out.join("01_casa.bmp"), out.join("02_patio.webp"), out.join("03_jardin.png")]);
+    }
+
+    // Una imagen que no se puede leer queda como miniatura vacía en vez de perder la hoja
+    #[test]
+    fn unreadable_images_leave_an_empty_tile() {
+        let missing = std::env::temp_dir().join(format!("cubito-batch-{}.png", std::process::id()));
+        let outputs = [("casa.ron".to_string(), Some(missing)), ("patio.ron".to_string(), None)];
+        let sheet = contact_sheet(&outputs);
+        assert_eq!(sheet.dimensions(), (2 * TILE_WIDTH, TILE_HEIGHT));
+    }
+}
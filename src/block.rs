@@ -0,0 +1,247 @@
+// block.rs
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::aabb::Aabb;
+use crate::cube::Face;
+use crate::material::Material;
+use crate::obj_export::{self, ObjTransform};
+use crate::object::{Object, SceneObject};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Forma de un bloque que no ocupa toda la celda
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum BlockShape {
+    // Media altura
+    Slab,
+    // Media altura más un cuarto de bloque detrás, hacia `facing`
+    Stairs,
+    // Bloque entero acostado según `axis`: las caras Top y Bottom de `Material::faces` van en
+    // las puntas y las de los costados, con la veta a lo largo del eje
+    Log,
+}
+
+// Eje a lo largo del que está un tronco
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub enum Axis {
+    X,
+    // Parado, como un cubo
+    #[default]
+    Y,
+    Z,
+}
+
+impl Axis {
+    // Giro (una permutación cíclica de las coordenadas) que lleva el eje a Y, donde el
+    // tronco está parado y las caras se texturizan como en un cubo
+    fn to_local(self, v: &Vec3) -> Vec3 {
+        match self {
+            Axis::X => Vec3::new(v.z, v.x, v.y),
+            Axis::Y => *v,
+            Axis::Z => Vec3::new(v.y, v.z, v.x),
+        }
+    }
+
+    fn to_world(self, v: &Vec3) -> Vec3 {
+        match self {
+            Axis::X => Vec3::new(v.y, v.z, v.x),
+            Axis::Y => *v,
+            Axis::Z => Vec3::new(v.z, v.x, v.y),
+        }
+    }
+}
+
+// Hacia dónde sube la escalera
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub enum Facing {
+    // -Z
+    #[default]
+    North,
+    // +X
+    East,
+    // +Z
+    South,
+    // -X
+    West,
+}
+
+// Bloque con forma y orientación que ocupa una celda de lado `size`. Se arma con
+// cajas alineadas a los ejes, y las UV se calculan sobre la celda completa para
+// que la textura continúe la de los cubos vecinos
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub center: Vec3,
+    pub size: f32,
+    pub shape: BlockShape,
+    pub facing: Facing,
+    // Losa pegada al techo de la celda o escalera invertida
+    pub upside_down: bool,
+    // Solo en los troncos
+    pub axis: Axis,
+    pub material: Arc<Material>,
+}
+
+impl Block {
+    // Cajas que forman el bloque, en coordenadas del mundo
+    pub fn parts(&self) -> Vec<Aabb> {
+        let half = self.size / 2.0;
+        if self.shape == BlockShape::Log {
+            let corner = Vec3::new(half, half, half);
+            return vec![Aabb::new(self.center - corner, self.center + corner)];
+        }
+        let (low, high) = if self.upside_down { (0.0, half) } else { (-half, 0.0) };
+        let mut parts = vec![Aabb::new(Vec3::new(-half, low, -half), Vec3::new(half, high, half))];
+
+        if self.shape == BlockShape::Stairs {
+            let (low, high) = if self.upside_down { (-half, 0.0) } else { (0.0, half) };
+            let (min_xz, max_xz) = match self.facing {
+                Facing::North => ((-half, -half), (half, 0.0)),
+                Facing::East => ((0.0, -half), (half, half)),
+                Facing::South => ((-half, 0.0), (half, half)),
+                Facing::West => ((-half, -half), (0.0, half)),
+            };
+            parts.push(Aabb::new(Vec3::new(min_xz.0, low, min_xz.1), Vec3::new(max_xz.0, high, max_xz.1)));
+        }
+
+        parts
+            .into_iter()
+            .map(|part| Aabb::new(part.min + self.center, part.max + self.center))
+            .collect()
+    }
+}
+
+// Intersección de un rayo con una caja: distancia, normal hacia afuera de la cara golpeada
+// y si el rayo empezó dentro (la cara es la de salida)
+fn intersect_box(ray: &Ray, part: &Aabb) -> Option<(f32, Vec3, bool)> {
+    let mut t_near = f32::NEG_INFINITY;
+    let mut t_far = f32::INFINITY;
+    let (mut near_axis, mut far_axis) = (0, 0);
+    for axis in 0..3 {
+        let inverse = 1.0 / ray.direction[axis];
+        let t1 = (part.min[axis] - ray.origin[axis]) * inverse;
+        let t2 = (part.max[axis] - ray.origin[axis]) * inverse;
+        if t1.min(t2) > t_near {
+            t_near = t1.min(t2);
+            near_axis = axis;
+        }
+        if t1.max(t2) < t_far {
+            t_far = t1.max(t2);
+            far_axis = axis;
+        }
+    }
+    if t_near > t_far {
+        return None;
+    }
+
+    // Entrada si está dentro del intervalo; si no, la salida (rayo que empieza dentro)
+    let inside = !ray.contains(t_near);
+    let (t, axis, sign) = if inside {
+        (t_far, far_axis, ray.direction[far_axis].signum())
+    } else {
+        (t_near, near_axis, -ray.direction[near_axis].signum())
+    };
+    if !ray.contains(t) {
+        return None;
+    }
+    let mut normal = Vec3::zeros();
+    normal[axis] = sign;
+    Some((t, normal, inside))
+}
+
+impl RayIntersect for Block {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let hit = self
+            .parts()
+            .iter()
+            .filter_map(|part| intersect_box(ray, part))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((t, normal, inside)) = hit else {
+            return Intersect::empty();
+        };
+
+        let point = ray.at(t);
+        let half_size = self.size / 2.0;
+        // Los troncos se texturizan parados; los demás bloques tienen `axis` Y
+        let local_point = self.axis.to_local(&(point - self.center));
+        let local_normal = self.axis.to_local(&normal);
+
+        // Mismo mapeo que `Cube::ray_intersect`, medido sobre la celda completa
+        let (uv, tangent, bitangent) = if local_normal.x != 0.0 {
+            (
+                ((local_point.z + half_size) / self.size, (local_point.y + half_size) / self.size),
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            )
+        } else if local_normal.y != 0.0 {
+            (
+                ((local_point.x + half_size) / self.size, (local_point.z + half_size) / self.size),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            )
+        } else {
+            (
+                ((local_point.x + half_size) / self.size, (local_point.y + half_size) / self.size),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            )
+        };
+
+        let intersect = Intersect::new(point, normal, t, self.material.clone(), Some(uv))
+            .with_tangents(self.axis.to_world(&tangent), self.axis.to_world(&bitangent))
+            .with_uv_scale(self.size)
+            .on_face(Face::from_normal(&local_normal));
+        if inside { intersect.seen_from_inside() } else { intersect }
+    }
+}
+
+impl SceneObject for Block {
+    fn kind(&self) -> &'static str {
+        match self.shape {
+            BlockShape::Slab => "slab",
+            BlockShape::Stairs => "stairs",
+            BlockShape::Log => "log",
+        }
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.center = center;
+    }
+
+    fn size(&self) -> f32 {
+        self.size
+    }
+
+    fn set_size(&mut self, size: f32) {
+        self.size = size;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    // Si otro objeto comparte el material, este pasa a tener su propia copia
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.material)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Aabb::enclosing(self.parts())
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(self.clone())
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        obj_export::write_block(obj, self, vertex_offset, transform)
+    }
+}
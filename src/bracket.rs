@@ -0,0 +1,46 @@
+// bracket.rs
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::color_management::OutputTransform;
+use crate::export::{self, OutputFormat};
+use crate::framebuffer::Framebuffer;
+use crate::settings::RenderSettings;
+use crate::tonemap;
+
+// Pasos de exposición (stops) por defecto: cada uno duplica o reduce a la mitad la luz
+pub const DEFAULT_STOPS: [f32; 5] = [-2.0, -1.0, 0.0, 1.0, 2.0];
+
+// Lista de stops separados por comas, por ejemplo "-3,0,3"
+pub fn parse_stops(text: &str) -> Option<Vec<f32>> {
+    text.split(',').map(|value| value.trim().parse().ok()).collect()
+}
+
+// Guarda el cuadro ya renderizado a varias exposiciones (`ev-2.png`, `ev+0.png`...) y el
+// cuadro lineal completo en `hdr.exr`. No vuelve a renderizar: todo sale del buffer HDR
+pub fn save_brackets(
+    framebuffer: &Framebuffer,
+    settings: &RenderSettings,
+    stops: &[f32],
+    dir: &Path,
+    color_space: Option<OutputTransform>,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let mut bracket = Framebuffer::new(framebuffer.width, framebuffer.height);
+    bracket.hdr = framebuffer.hdr.clone();
+
+    let mut paths = Vec::with_capacity(stops.len() + 1);
+    for stop in stops {
+        bracket.exposure = framebuffer.exposure * stop.exp2();
+        tonemap::resolve(&mut bracket, settings);
+        let path = dir.join(format!("ev{:+}.png", stop));
+        export::save_framebuffer(&bracket, &path, OutputFormat::Png, settings, color_space)?;
+        paths.push(path);
+    }
+
+    let path = dir.join("hdr.exr");
+    export::save_hdr_exr(framebuffer, &path)?;
+    paths.push(path);
+    Ok(paths)
+}
@@ -0,0 +1,118 @@
+// buffer_pool.rs
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::color::Color;
+
+// Elementos por encima de los cuales un búfer no se guarda para el cuadro siguiente
+const MAX_KEPT_CAPACITY: usize = 1 << 12;
+// Búferes libres por hilo; vaciar un caché grande no los acumula sin límite
+const MAX_FREE_BUFFERS: usize = 64;
+
+// Cuadro en curso; cada hilo lo compara con el último que vio para soltar lo que sobra
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+// Búferes libres de un hilo. Los rayos toman uno y lo devuelven vacío en lugar de pedir y
+// liberar memoria cada vez: después de los primeros pixeles ya no se reserva nada
+struct Pool<T> {
+    frame: u64,
+    free: Vec<Vec<T>>,
+}
+
+impl<T> Pool<T> {
+    const fn new() -> Self {
+        Pool { frame: 0, free: Vec::new() }
+    }
+
+    fn take(&mut self) -> Vec<T> {
+        let frame = FRAME.load(Ordering::Relaxed);
+        if self.frame != frame {
+            // Un cuadro con mucha profundidad no deja reservado su pico para siempre
+            self.frame = frame;
+            self.free.retain(|buffer| buffer.capacity() <= MAX_KEPT_CAPACITY);
+        }
+        self.free.pop().unwrap_or_default()
+    }
+
+    fn give(&mut self, mut buffer: Vec<T>) {
+        if self.free.len() < MAX_FREE_BUFFERS {
+            buffer.clear();
+            self.free.push(buffer);
+        }
+    }
+}
+
+thread_local! {
+    static STACKS: RefCell<Pool<usize>> = const { RefCell::new(Pool::new()) };
+    static LIGHTS: RefCell<Pool<(usize, Color)>> = const { RefCell::new(Pool::new()) };
+}
+
+// Al empezar cada cuadro
+pub fn next_frame() {
+    FRAME.fetch_add(1, Ordering::Relaxed);
+}
+
+// Pila vacía para recorrer el BVH; se puede anidar (cada llamada toma la suya)
+pub fn with_stack<R>(f: impl FnOnce(&mut Vec<usize>) -> R) -> R {
+    let mut stack = STACKS.with(|pool| pool.borrow_mut().take());
+    let result = f(&mut stack);
+    STACKS.with(|pool| pool.borrow_mut().give(stack));
+    result
+}
+
+// Lista vacía para las luces de un punto; `return_lights` la devuelve al hilo que la suelte
+pub fn take_lights() -> Vec<(usize, Color)> {
+    LIGHTS.with(|pool| pool.borrow_mut().take())
+}
+
+pub fn return_lights(lights: Vec<(usize, Color)>) {
+    // Al cerrar el hilo ya no hay dónde guardarla y se libera
+    let _ = LIGHTS.try_with(|pool| pool.borrow_mut().give(lights));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returned_buffers_come_back_empty_with_their_capacity() {
+        let mut pool = Pool::new();
+        let mut buffer: Vec<usize> = pool.take();
+        buffer.extend(0..100);
+        let capacity = buffer.capacity();
+        pool.give(buffer);
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+    }
+
+    #[test]
+    fn large_buffers_are_dropped_on_the_next_frame() {
+        let mut pool: Pool<usize> = Pool::new();
+        drop(pool.take());
+        pool.give(Vec::with_capacity(MAX_KEPT_CAPACITY * 2));
+        pool.give(Vec::with_capacity(16));
+        next_frame();
+        assert!(pool.take().capacity() <= MAX_KEPT_CAPACITY);
+        assert!(pool.free.is_empty());
+    }
+
+    #[test]
+    fn keeps_a_bounded_number_of_buffers() {
+        let mut pool: Pool<usize> = Pool::new();
+        for _ in 0..MAX_FREE_BUFFERS + 10 {
+            pool.give(Vec::with_capacity(4));
+        }
+        assert_eq!(pool.free.len(), MAX_FREE_BUFFERS);
+    }
+
+    #[test]
+    fn nested_stacks_are_separate() {
+        with_stack(|outer| {
+            outer.push(1);
+            with_stack(|inner| assert!(inner.is_empty()));
+            assert_eq!(outer, &[1]);
+        });
+    }
+}
@@ -0,0 +1,305 @@
+// bvh.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+use crate::buffer_pool;
+use crate::cube;
+use crate::kernels::Kernel;
+use crate::object::Object;
+use crate::ray::Ray;
+use crate::ray_intersect::Intersect;
+
+// Objetos por hoja a partir de los cuales ya no se divide
+const MAX_LEAF_OBJECTS: usize = 2;
+
+// Un nodo es hoja si `count > 0` (objetos indices[start..start + count]);
+// si no, sus hijos son nodes[left] y nodes[left + 1]
+struct Node {
+    bounds: Aabb,
+    start: usize,
+    count: usize,
+    left: usize,
+}
+
+impl Node {
+    fn placeholder() -> Self {
+        Node { bounds: Aabb::new(Vec3::zeros(), Vec3::zeros()), start: 0, count: 0, left: 0 }
+    }
+}
+
+// Los cubos comunes de las hojas en arreglos paralelos a `indices` (estructura de arreglos):
+// probar uno lee cuatro floats seguidos en lugar de ir al objeto, y el impacto con su material
+// se arma una sola vez, para el más cercano. Un lado negativo marca lo que no es un cubo común
+#[derive(Default)]
+struct CubeArrays {
+    center_x: Vec<f32>,
+    center_y: Vec<f32>,
+    center_z: Vec<f32>,
+    size: Vec<f32>,
+}
+
+impl CubeArrays {
+    fn push(&mut self, cube: Option<(Vec3, f32)>) {
+        let (center, size) = cube.unwrap_or((Vec3::zeros(), -1.0));
+        self.center_x.push(center.x);
+        self.center_y.push(center.y);
+        self.center_z.push(center.z);
+        self.size.push(size);
+    }
+
+    fn center(&self, slot: usize) -> Vec3 {
+        Vec3::new(self.center_x[slot], self.center_y[slot], self.center_z[slot])
+    }
+}
+
+// Jerarquía de cajas sobre los objetos de la escena para no probar cada rayo contra
+// todos. Los objetos sin caja (planos) quedan fuera del árbol y se prueban siempre
+pub struct Bvh<'a> {
+    objects: &'a [Object],
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+    cubes: CubeArrays,
+    kernel: Kernel,
+}
+
+impl<'a> Bvh<'a> {
+    pub fn build(objects: &'a [Object]) -> Self {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            match object.bounds() {
+                Some(bounds) => bounded.push((index, bounds)),
+                None => unbounded.push(index),
+            }
+        }
+
+        let mut bvh = Bvh {
+            objects,
+            nodes: Vec::new(),
+            indices: Vec::new(),
+            unbounded,
+            cubes: CubeArrays::default(),
+            kernel: Kernel::Portable,
+        };
+        if !bounded.is_empty() {
+            bvh.nodes.push(Node::placeholder());
+            bvh.build_node(0, &mut bounded);
+        }
+        for &index in &bvh.indices {
+            bvh.cubes.push(objects[index].solid_cube());
+        }
+        bvh
+    }
+
+    pub fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    // Llena nodes[slot] con la caja de `items`; si son muchos, divide por la mediana de
+    // los centros a lo largo del eje más largo y construye los dos hijos, que van juntos
+    fn build_node(&mut self, slot: usize, items: &mut [(usize, Aabb)]) {
+        let bounds = Aabb::enclosing(items.iter().map(|(_, bounds)| *bounds)).expect("nodo sin objetos");
+
+        if items.len() <= MAX_LEAF_OBJECTS {
+            self.nodes[slot] = Node { bounds, start: self.indices.len(), count: items.len(), left: 0 };
+            self.indices.extend(items.iter().map(|(index, _)| *index));
+            return;
+        }
+
+        let centers = Aabb::enclosing(items.iter().map(|(_, bounds)| Aabb::new(bounds.center(), bounds.center())))
+            .expect("nodo sin objetos");
+        let extent = centers.size();
+        let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |(_, a), (_, b)| a.center()[axis].total_cmp(&b.center()[axis]));
+        let (left_items, right_items) = items.split_at_mut(middle);
+
+        let left = self.nodes.len();
+        self.nodes.push(Node::placeholder());
+        self.nodes.push(Node::placeholder());
+        self.nodes[slot] = Node { bounds, start: 0, count: 0, left };
+        self.build_node(left, left_items);
+        self.build_node(left + 1, right_items);
+    }
+
+    // Impacto más cercano; cada impacto acorta el t_max del rayo y poda las cajas más lejanas
+    pub fn intersect(&self, ray: &Ray) -> Intersect {
+        self.traverse(ray, false)
+    }
+
+    // Primer impacto que se encuentre, no necesariamente el más cercano (para sombras)
+    pub fn any_hit(&self, ray: &Ray) -> Intersect {
+        self.traverse(ray, true)
+    }
+
+    fn traverse(&self, ray: &Ray, stop_at_first: bool) -> Intersect {
+        let mut intersect = Intersect::empty();
+        let mut ray = *ray;
+
+        let mut hit_index = None;
+        // El último impacto fue un cubo de `cubes` y falta armarlo
+        let mut pending_cube = false;
+
+        for &index in &self.unbounded {
+            let mut hit = self.objects[index].ray_intersect(&ray);
+            if hit.is_intersecting {
+                hit.object = Some(index);
+                ray.t_max = hit.distance;
+                intersect = hit;
+                if stop_at_first {
+                    return intersect;
+                }
+                hit_index = Some(index);
+            }
+        }
+
+        buffer_pool::with_stack(|stack| {
+            // En la pila solo entran nodos cuya caja ya cortó el rayo; los hijos se prueban juntos
+            if self.nodes.first().is_some_and(|root| root.bounds.intersects(&ray)) {
+                stack.push(0);
+            }
+            while let Some(node_index) = stack.pop() {
+                let node = &self.nodes[node_index];
+                if node.count == 0 {
+                    let (left, right) =
+                        self.kernel.intersects_pair(&self.nodes[node.left].bounds, &self.nodes[node.left + 1].bounds, &ray);
+                    if left {
+                        stack.push(node.left);
+                    }
+                    if right {
+                        stack.push(node.left + 1);
+                    }
+                    continue;
+                }
+                for slot in node.start..node.start + node.count {
+                    let index = self.indices[slot];
+                    let size = self.cubes.size[slot];
+                    if size >= 0.0 {
+                        let Some((distance, _)) = cube::slab_distance(&self.cubes.center(slot), size, &ray) else { continue };
+                        ray.t_max = distance;
+                        if stop_at_first {
+                            let mut hit = self.objects[index].ray_intersect(&ray);
+                            hit.object = Some(index);
+                            return hit;
+                        }
+                        hit_index = Some(index);
+                        pending_cube = true;
+                        continue;
+                    }
+                    let mut hit = self.objects[index].ray_intersect(&ray);
+                    if hit.is_intersecting {
+                        hit.object = Some(index);
+                        ray.t_max = hit.distance;
+                        intersect = hit;
+                        if stop_at_first {
+                            return intersect;
+                        }
+                        hit_index = Some(index);
+                        pending_cube = false;
+                    }
+                }
+            }
+
+            // Con t_max en su distancia, el cubo da el mismo impacto que encontró la prueba
+            if pending_cube && let Some(index) = hit_index {
+                intersect = self.objects[index].ray_intersect(&ray);
+                intersect.object = Some(index);
+            }
+            if let Some(index) = hit_index {
+                intersect.instance = self.objects[index].center();
+            }
+            intersect.time = ray.time;
+            intersect
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::plane::Plane;
+    use crate::scene_intersect;
+    use crate::sphere::Sphere;
+
+    fn random_vec3(rng: &mut StdRng, extent: f32) -> Vec3 {
+        Vec3::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))
+    }
+
+    // Cubos y esferas sueltos y un piso, que queda fuera del árbol
+    fn random_scene(rng: &mut StdRng) -> Vec<Object> {
+        let material = Arc::new(Material::new(Color::new(200.0, 200.0, 200.0), 10.0, [0.9, 0.1]));
+        let mut objects: Vec<Object> = (0..200)
+            .map(|index| -> Object {
+                let center = random_vec3(rng, 20.0);
+                if index % 2 == 0 {
+                    Box::new(Cube { center, size: rng.gen_range(0.1..2.0), material: material.clone() })
+                } else {
+                    Box::new(Sphere { center, radius: rng.gen_range(0.1..1.0), material: material.clone() })
+                }
+            })
+            .collect();
+        objects.push(Box::new(Plane::new(Vec3::new(0.0, -25.0, 0.0), Vec3::y(), 1.0, material)));
+        objects
+    }
+
+    fn kernels() -> impl Iterator<Item = Kernel> {
+        [Kernel::Portable, Kernel::Avx2, Kernel::Neon].into_iter().filter(|kernel| kernel.is_supported())
+    }
+
+    // El árbol encuentra el mismo impacto más cercano que probar todos los objetos
+    #[test]
+    fn closest_hit_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(533);
+        let objects = random_scene(&mut rng);
+        for kernel in kernels() {
+            let bvh = Bvh::build(&objects).with_kernel(kernel);
+            let mut hits = 0;
+            for _ in 0..2000 {
+                let ray = Ray::new(random_vec3(&mut rng, 30.0), random_vec3(&mut rng, 1.0).normalize());
+                let (expected, found) = (scene_intersect(&ray, &objects), bvh.intersect(&ray));
+                assert_eq!(found.is_intersecting, expected.is_intersecting, "{:?} con {}", ray, kernel);
+                if expected.is_intersecting {
+                    hits += 1;
+                    assert_eq!(found.object, expected.object, "{:?} con {}", ray, kernel);
+                    assert!((found.distance - expected.distance).abs() <= 1e-4 * expected.distance);
+                }
+            }
+            assert!(hits > 200, "muy pocos impactos para probar algo: {}", hits);
+        }
+    }
+
+    // Con un t_max, `any_hit` encuentra algo exactamente cuando hay un impacto antes
+    #[test]
+    fn any_hit_agrees_on_occlusion() {
+        let mut rng = StdRng::seed_from_u64(534);
+        let objects = random_scene(&mut rng);
+        for kernel in kernels() {
+            let bvh = Bvh::build(&objects).with_kernel(kernel);
+            for _ in 0..2000 {
+                let mut ray = Ray::new(random_vec3(&mut rng, 30.0), random_vec3(&mut rng, 1.0).normalize());
+                ray.t_max = rng.gen_range(1.0..40.0);
+                let expected = scene_intersect(&ray, &objects).is_intersecting;
+                assert_eq!(bvh.any_hit(&ray).is_intersecting, expected, "{:?} con {}", ray, kernel);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_scene_has_no_hits() {
+        let objects: Vec<Object> = Vec::new();
+        let bvh = Bvh::build(&objects);
+        assert!(!bvh.intersect(&Ray::new(Vec3::zeros(), Vec3::z())).is_intersecting);
+    }
+}
@@ -1,10 +1,21 @@
 use nalgebra_glm::Vec3;
+use crate::aabb::Aabb;
+use crate::ray::Ray;
 use std::f32::consts::PI;
 
+// Campo de visión vertical por omisión
+pub const FIELD_OF_VIEW: f32 = PI / 3.0;
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub position: Vec3, // Camera position in world space
     pub center: Vec3,   // Point the camera is looking at
     pub up: Vec3,      // Up vector
+    pub fov: f32,      // Campo de visión vertical, en radianes
+    // Diámetro de la lente; 0 = estenopeica, todo enfocado
+    pub aperture: f32,
+    // Distancia al plano nítido; None = enfoca el punto que mira la cámara
+    pub focus_distance: Option<f32>,
 }
 
 impl Camera {
@@ -13,6 +24,9 @@ impl Camera {
             position,
             center,
             up,
+            fov: FIELD_OF_VIEW,
+            aperture: 0.0,
+            focus_distance: None,
         }
     }
 
@@ -29,6 +43,83 @@ impl Camera {
         rotated.normalize()
     }
 
+    // Dirección del rayo primario que pasa por el pixel (x, y) de una imagen de width x height
+    pub fn primary_ray_direction(&self, x: f32, y: f32, width: f32, height: f32) -> Vec3 {
+        let aspect_ratio = width / height;
+        let perspective_scale = (self.fov * 0.5).tan();
+
+        let screen_x = (2.0 * x) / width - 1.0;
+        let screen_y = -(2.0 * y) / height + 1.0;
+
+        let screen_x = screen_x * aspect_ratio * perspective_scale;
+        let screen_y = screen_y * perspective_scale;
+
+        let ray_direction = Vec3::new(screen_x, screen_y, -1.0).normalize();
+        self.basis_change(&ray_direction)
+    }
+
+    // Ángulo que cubre un pixel (en el centro de la imagen) con `height` pixeles de alto
+    pub fn pixel_spread(&self, height: f32) -> f32 {
+        2.0 * (self.fov * 0.5).tan() / height
+    }
+
+    // Rayo primario de lente delgada: sale de un punto del disco de la lente (`lens` en [0, 1)²)
+    // hacia el punto del plano de foco que corresponde al pixel, así que solo ese plano queda nítido
+    pub fn lens_ray(&self, x: f32, y: f32, width: f32, height: f32, lens: (f32, f32)) -> Ray {
+        let direction = self.primary_ray_direction(x, y, width, height);
+        let spread = self.pixel_spread(height);
+        if self.aperture <= 0.0 {
+            return Ray::new(self.position, direction).with_spread(spread);
+        }
+
+        let forward = (self.center - self.position).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+        let focus_distance = self.focus_distance.unwrap_or_else(|| (self.center - self.position).magnitude());
+        let focus_point = self.position + direction * (focus_distance / direction.dot(&forward));
+
+        let (radius, angle) = (self.aperture * 0.5 * lens.0.sqrt(), 2.0 * PI * lens.1);
+        let origin = self.position + right * (radius * angle.cos()) + up * (radius * angle.sin());
+        Ray::new(origin, (focus_point - origin).normalize()).with_spread(spread)
+    }
+
+    // Inversa de primary_ray_direction: posición en pixeles de un punto del mundo,
+    // o None si queda detrás de la cámara
+    pub fn project(&self, point: &Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+        let forward = (self.center - self.position).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let relative = point - self.position;
+        let depth = relative.dot(&forward);
+        if depth <= 0.0 {
+            return None;
+        }
+
+        let aspect_ratio = width / height;
+        let perspective_scale = (self.fov * 0.5).tan();
+        let screen_x = relative.dot(&right) / depth / (aspect_ratio * perspective_scale);
+        let screen_y = relative.dot(&up) / depth / perspective_scale;
+
+        Some(((screen_x + 1.0) * width / 2.0, (1.0 - screen_y) * height / 2.0))
+    }
+
+    // Mueve la cámara, manteniendo la dirección de vista, para que toda la caja
+    // quepa en pantalla; la órbita queda centrada en la caja
+    pub fn frame_bounds(&mut self, bounds: &Aabb, aspect_ratio: f32) {
+        let forward = (self.center - self.position).normalize();
+        let radius = (bounds.size().magnitude() * 0.5).max(1e-3);
+
+        // El ángulo más estrecho entre el vertical y el horizontal
+        let half_vertical = self.fov * 0.5;
+        let half_horizontal = (half_vertical.tan() * aspect_ratio).atan();
+        let half_fov = half_vertical.min(half_horizontal);
+
+        let distance = radius / half_fov.sin();
+        self.center = bounds.center();
+        self.position = self.center - forward * distance;
+    }
+
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
         // Calculate the vector from the center to the eye (radius vector) and measure the distance
         let radius_vector = self.position - self.center;
@@ -1,3 +1,7 @@
+use crate::color_management::{linear_to_srgb, srgb_to_linear};
+
+// Radiancia lineal en la escala 0-255 (puede pasarse de 255 donde se suman luces); la
+// curva de sRGB se aplica una sola vez, en `tonemap::resolve`
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f32,
@@ -10,16 +14,63 @@ impl Color {
         Self { r, g, b }
     }
 
-    pub fn to_hex(&self) -> u32 {
+    // Color escrito como en un selector de colores (escenas, materiales, línea de comandos):
+    // sRGB en 0-255, pasado a lineal para que la luz se sume y se mezcle bien
+    pub fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        let linear = |value: f32| srgb_to_linear(value.max(0.0) / 255.0) * 255.0;
+        Color::new(linear(r), linear(g), linear(b))
+    }
+
+    // Inversa de `from_srgb`, para mostrar el color como se escribe
+    pub fn to_srgb(self) -> [f32; 3] {
+        [self.r, self.g, self.b].map(|value| linear_to_srgb(value.max(0.0) / 255.0) * 255.0)
+    }
+
+    pub fn to_hex(self) -> u32 {
         ((self.r.clamp(0.0, 255.0) as u32) << 16)
             | ((self.g.clamp(0.0, 255.0) as u32) << 8)
             | (self.b.clamp(0.0, 255.0) as u32)
     }
 
+    // Como `to_hex`, pero suma `threshold` (0 a 1) antes de truncar: en promedio redondea al valor exacto
+    pub fn to_hex_dithered(self, threshold: f32) -> u32 {
+        let channel = |value: f32| (value + threshold).clamp(0.0, 255.0) as u32;
+        (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b)
+    }
+
+    pub fn from_hex(hex: u32) -> Self {
+        Color::new(
+            ((hex >> 16) & 0xFF) as f32,
+            ((hex >> 8) & 0xFF) as f32,
+            (hex & 0xFF) as f32,
+        )
+    }
+
+    // "R,G,B" en sRGB 0-255, como en la línea de comandos
+    pub fn parse(text: &str) -> Option<Self> {
+        let values = text
+            .split(',')
+            .map(|value| value.trim().parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match values.as_slice() {
+            [r, g, b] => Some(Color::from_srgb(*r, *g, *b)),
+            _ => None,
+        }
+    }
+
     pub fn black() -> Self {
         Color::new(0.0, 0.0, 0.0)
     }
 
+    // Multiplica canal por canal; un tinte blanco (255, 255, 255) no cambia el color
+    pub fn tinted(self, tint: Color) -> Color {
+        Color {
+            r: self.r * tint.r / 255.0,
+            g: self.g * tint.g / 255.0,
+            b: self.b * tint.b / 255.0,
+        }
+    }
+
     pub fn blend(self, other: Color, factor: f32) -> Color {
         let f = factor.clamp(0.0, 1.0);
         Color {
@@ -0,0 +1,85 @@
+// color_management.rs
+
+use crate::color::Color;
+
+// sRGB lineal -> Display P3 lineal (ambos con blanco D65)
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.8225, 0.1774, 0.0000],
+    [0.0332, 0.9669, 0.0000],
+    [0.0171, 0.0724, 0.9108],
+];
+
+// Transformación de salida: espacio de color y curva con que se codifican los pixeles.
+// Los colores del render son sRGB lineal (0-255); las demás opciones los llevan a una
+// pantalla de gama amplia o a video
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputTransform {
+    Srgb,
+    // Primarios P3 con blanco D65 y la curva de sRGB
+    DisplayP3,
+    // Primarios de Rec.709 (los de sRGB) con la curva de codificación de BT.709
+    Rec709,
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 }
+}
+
+// OETF de BT.709: la curva que corresponde a la característica de transferencia 1 de cICP
+fn linear_to_rec709(value: f32) -> f32 {
+    if value < 0.018 { value * 4.5 } else { 1.099 * value.powf(0.45) - 0.099 }
+}
+
+impl OutputTransform {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "srgb" => Some(OutputTransform::Srgb),
+            "p3" | "display-p3" => Some(OutputTransform::DisplayP3),
+            "rec709" | "bt1886" => Some(OutputTransform::Rec709),
+            _ => None,
+        }
+    }
+
+    // Color lineal (0-255, ya en el rango de pantalla) a valores codificados en 0-255
+    pub fn apply(self, color: Color) -> Color {
+        let linear = [color.r, color.g, color.b].map(|value| (value / 255.0).clamp(0.0, 1.0));
+        let [r, g, b] = match self {
+            OutputTransform::Srgb => linear.map(linear_to_srgb),
+            OutputTransform::DisplayP3 => SRGB_TO_P3
+                .map(|row| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+                .map(|value| linear_to_srgb(value.clamp(0.0, 1.0))),
+            OutputTransform::Rec709 => linear.map(linear_to_rec709),
+        };
+        Color::new(r * 255.0, g * 255.0, b * 255.0)
+    }
+
+    // Códigos de ITU-T H.273 para el chunk cICP: primarios, curva, matriz (0 = RGB), rango completo
+    pub fn cicp(self) -> [u8; 4] {
+        match self {
+            OutputTransform::Srgb => [1, 13, 0, 1],
+            OutputTransform::DisplayP3 => [12, 13, 0, 1],
+            OutputTransform::Rec709 => [1, 1, 0, 1],
+        }
+    }
+
+    // Blanco y primarios (x, y) para el chunk cHRM de los lectores que no entienden cICP
+    pub fn chromaticities(self) -> [(f32, f32); 4] {
+        let white = (0.3127, 0.3290);
+        match self {
+            OutputTransform::DisplayP3 => [white, (0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            _ => [white, (0.640, 0.330), (0.300, 0.600), (0.150, 0.060)],
+        }
+    }
+
+    // Exponente de codificación aproximado para el chunk gAMA
+    pub fn gamma(self) -> f32 {
+        match self {
+            OutputTransform::Rec709 => 0.45,
+            _ => 1.0 / 2.2,
+        }
+    }
+}
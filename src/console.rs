@@ -0,0 +1,395 @@
+// console.rs
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+use minifb::{InputCallback, Key, KeyRepeat, Window};
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::object::Object;
+use crate::group::Group;
+use crate::ies::IesProfile;
+use crate::light::{AreaShape, Light, LightKind};
+use crate::obj_export;
+use crate::placement;
+use crate::scene_graph::SceneGraph;
+use crate::settings::MAX_RAY_DEPTH;
+
+// Recibe los caracteres tecleados desde minifb
+struct ConsoleInput {
+    pending: Rc<RefCell<Vec<char>>>,
+}
+
+impl InputCallback for ConsoleInput {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(c) = char::from_u32(uni_char) {
+            self.pending.borrow_mut().push(c);
+        }
+    }
+}
+
+pub struct Console {
+    pub open: bool,
+    line: String,
+    pending: Rc<RefCell<Vec<char>>>,
+}
+
+impl Console {
+    pub fn new(window: &mut Window) -> Self {
+        let pending = Rc::new(RefCell::new(Vec::new()));
+        window.set_input_callback(Box::new(ConsoleInput { pending: pending.clone() }));
+        Console {
+            open: false,
+            line: String::new(),
+            pending,
+        }
+    }
+
+    // Procesa el teclado de este cuadro y devuelve el comando si se presionó Enter
+    pub fn update(&mut self, window: &Window) -> Option<String> {
+        let typed: Vec<char> = self.pending.borrow_mut().drain(..).collect();
+
+        if window.is_key_pressed(Key::Backquote, KeyRepeat::No) {
+            self.open = !self.open;
+            self.line.clear();
+            return None;
+        }
+        if !self.open {
+            return None;
+        }
+
+        for c in typed {
+            if c != '`' && c != '~' && !c.is_control() {
+                self.line.push(c);
+            }
+        }
+        if window.is_key_pressed(Key::Backspace, KeyRepeat::Yes) {
+            self.line.pop();
+        }
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            let command = self.line.trim().to_string();
+            self.line.clear();
+            if !command.is_empty() {
+                return Some(command);
+            }
+        }
+        None
+    }
+
+    // Abre la consola con la línea ya empezada, para que solo falte escribir el valor
+    pub fn open_with(&mut self, line: &str) {
+        self.open = true;
+        self.line = line.to_string();
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("> {}_", self.line)
+    }
+}
+
+fn parse_f32(value: &str) -> Result<f32, String> {
+    value.parse().map_err(|_| format!("valor inválido: {}", value))
+}
+
+fn parse_vec3(values: &[&str]) -> Result<Vec3, String> {
+    match values {
+        [x, y, z] => Ok(Vec3::new(parse_f32(x)?, parse_f32(y)?, parse_f32(z)?)),
+        _ => Err("se esperaban 3 valores".to_string()),
+    }
+}
+
+fn parse_color(values: &[&str]) -> Result<Color, String> {
+    let v = parse_vec3(values)?;
+    Ok(Color::from_srgb(v.x, v.y, v.z))
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    value.parse().map_err(|_| "se esperaba true o false".to_string())
+}
+
+fn parse_single<'a>(values: &[&'a str]) -> Result<&'a str, String> {
+    match values {
+        [value] => Ok(value),
+        _ => Err("se esperaba 1 valor".to_string()),
+    }
+}
+
+// Objeto escrito como `object.N`
+fn parse_object(target: &str, len: usize) -> Result<usize, String> {
+    match target.split_once('.') {
+        Some(("object", index)) => parse_index(index, len),
+        _ => Err(format!("se esperaba object.N: {}", target)),
+    }
+}
+
+// Objetos por índice o por nombre de grupo, por ejemplo `0 3 rojos`
+fn parse_members(values: &[&str], len: usize, groups: &[Group]) -> Result<Vec<usize>, String> {
+    let mut members = Vec::new();
+    for value in values {
+        match groups.iter().find(|group| group.name == *value) {
+            Some(group) => members.extend(&group.members),
+            None => members.push(parse_index(value, len)?),
+        }
+    }
+    Ok(members)
+}
+
+fn parse_index(index: &str, len: usize) -> Result<usize, String> {
+    let i: usize = index.parse().map_err(|_| format!("índice inválido: {}", index))?;
+    if i >= len {
+        return Err(format!("índice fuera de rango: {} (hay {})", i, len));
+    }
+    Ok(i)
+}
+
+// Ejecuta un comando como `set light.0.intensity 2.0` sobre la escena
+pub fn execute(
+    command: &str,
+    objects: &mut Vec<Object>,
+    lights: &mut [Light],
+    groups: &mut Vec<Group>,
+    graph: &mut SceneGraph,
+) -> Result<String, String> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    match words.as_slice() {
+        ["help"] => Ok(
+            "set light.N.{intensity,position,color,casts_shadows,shadow_only,ies,attenuation,radius,panel,direction,cone,include,exclude} | set object.N.{center,size} | \
+             set material.N.{diffuse,specular,albedo,crystal,tint,opacity,variation,emissive,reflectivity,ior,pbr,metallic,roughness,max_depth} | group NAME N... | \
+             set group.NAME.{tint,material} [none] | set node.NAME.{translation,rotation,scale} | snap object.N [CELDA] | attach object.N object.M {+x,-x,+y,-y,+z,-z} | \
+             array object.N CANTIDAD DX DY DZ | export RUTA.obj"
+                .to_string(),
+        ),
+        ["snap", target, cell @ ..] => {
+            let index = parse_object(target, objects.len())?;
+            let cell_size = match cell {
+                [] => 1.0,
+                [cell] => parse_f32(cell)?,
+                _ => return Err("se esperaba a lo más 1 tamaño de celda".to_string()),
+            };
+            if cell_size <= 0.0 {
+                return Err("el tamaño de celda debe ser positivo".to_string());
+            }
+            placement::snap_to_grid(objects[index].as_mut(), cell_size);
+            let center = objects[index].center();
+            Ok(format!("{} en {:.3} {:.3} {:.3}", target, center.x, center.y, center.z))
+        }
+        ["attach", target, anchor, face] => {
+            let index = parse_object(target, objects.len())?;
+            let anchor_index = parse_object(anchor, objects.len())?;
+            if index == anchor_index {
+                return Err("un objeto no se puede apoyar sobre sí mismo".to_string());
+            }
+            let face = placement::parse_face(face).ok_or_else(|| format!("cara inválida: {}", face))?;
+            let anchor_bounds = objects[anchor_index]
+                .bounds()
+                .ok_or_else(|| format!("{} no tiene caras donde apoyarse", anchor))?;
+            placement::attach_to_face(objects[index].as_mut(), &anchor_bounds, &face);
+            let center = objects[index].center();
+            Ok(format!("{} en {:.3} {:.3} {:.3}", target, center.x, center.y, center.z))
+        }
+        ["array", target, count, step @ ..] => {
+            let index = parse_object(target, objects.len())?;
+            let count: usize = count.parse().map_err(|_| format!("cantidad inválida: {}", count))?;
+            let step = parse_vec3(step)?;
+            let copies = placement::array(objects[index].as_ref(), count, &step);
+            let first = objects.len();
+            objects.extend(copies);
+            Ok(format!("{} copias de {} (object.{} a object.{})", count, target, first, objects.len() - 1))
+        }
+        ["group", name, members @ ..] => {
+            let members = members
+                .iter()
+                .map(|index| parse_index(index, objects.len()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let count = members.len();
+            match groups.iter_mut().find(|group| group.name == *name) {
+                // Los que salen del grupo vuelven a su material y los que entran toman el del grupo
+                Some(group) => {
+                    group.members = members;
+                    group.apply(objects);
+                }
+                None => groups.push(Group::new(name, members)),
+            }
+            Ok(format!("grupo {} con {} objetos", name, count))
+        }
+        // La escena como está ahora, con lo editado en la consola
+        ["export", path] => {
+            obj_export::export_obj(objects, Path::new(path)).map_err(|error| format!("{}: {}", path, error))?;
+            Ok(format!("{} objetos exportados a {}", objects.len(), path))
+        }
+        ["set", path, values @ ..] => {
+            set(path, values, objects, lights, groups, graph)?;
+            Ok(format!("{} = {}", path, values.join(" ")))
+        }
+        _ => Err(format!("comando desconocido: {}", command)),
+    }
+}
+
+fn set(
+    path: &str,
+    values: &[&str],
+    objects: &mut [Object],
+    lights: &mut [Light],
+    groups: &mut [Group],
+    graph: &mut SceneGraph,
+) -> Result<(), String> {
+    let parts: Vec<&str> = path.split('.').collect();
+    match parts.as_slice() {
+        ["light", index, field] => {
+            let light = &mut lights[parse_index(index, lights.len())?];
+            match *field {
+                // Enlace de luz: `all` vuelve a iluminar todo
+                "include" => {
+                    light.links.include = match values {
+                        ["all"] => None,
+                        _ => Some(parse_members(values, objects.len(), groups)?),
+                    }
+                }
+                "exclude" => {
+                    light.links.exclude = match values {
+                        ["none"] => Vec::new(),
+                        _ => parse_members(values, objects.len(), groups)?,
+                    }
+                }
+                "intensity" => light.intensity = parse_f32(parse_single(values)?)?,
+                "position" => light.position = parse_vec3(values)?,
+                "color" => light.color = parse_color(values)?,
+                "casts_shadows" => light.casts_shadows = parse_bool(parse_single(values)?)?,
+                "shadow_only" => light.shadow_only = parse_bool(parse_single(values)?)?,
+                // Radio de una luz esférica de área; 0 la vuelve puntual
+                "radius" => {
+                    let radius = parse_f32(parse_single(values)?)?;
+                    light.area = (radius > 0.0).then_some(AreaShape::Sphere { radius });
+                }
+                "panel" => match values {
+                    [width, depth] => {
+                        light.area = Some(AreaShape::Rectangle { width: parse_f32(width)?, depth: parse_f32(depth)? })
+                    }
+                    _ => return Err("se esperaban 2 valores".to_string()),
+                },
+                "attenuation" => light.attenuation = parse_f32(parse_single(values)?)?.max(0.0),
+                // Dirección de un foco, o vuelve direccional a una luz puntual; none la vuelve puntual
+                "direction" => {
+                    light.kind = match (values, light.kind) {
+                        (["none"], _) => LightKind::Point,
+                        (_, kind) => {
+                            let direction = parse_vec3(values)?;
+                            if direction.norm() == 0.0 {
+                                return Err("la dirección no puede ser 0 0 0".to_string());
+                            }
+                            match kind {
+                                LightKind::Spot { inner, outer, .. } => LightKind::Spot { direction, inner, outer },
+                                _ => LightKind::Directional { direction },
+                            }
+                        }
+                    }
+                }
+                // Medios ángulos interior y exterior del foco, en grados; none lo vuelve puntual
+                "cone" => {
+                    light.kind = match (values, light.kind) {
+                        (["none"], _) => LightKind::Point,
+                        ([inner, outer], kind) => {
+                            let direction = match kind {
+                                LightKind::Directional { direction } | LightKind::Spot { direction, .. } => direction,
+                                LightKind::Point => Vec3::new(0.0, -1.0, 0.0),
+                            };
+                            let (inner, outer) = (parse_f32(inner)?.to_radians(), parse_f32(outer)?.to_radians());
+                            LightKind::Spot { direction, inner, outer }
+                        }
+                        _ => return Err("se esperaban 2 valores o none".to_string()),
+                    }
+                }
+                "ies" => {
+                    let path = parse_single(values)?;
+                    light.profile = match path {
+                        "none" => None,
+                        _ => Some(IesProfile::load(Path::new(path)).map_err(|error| error.to_string())?),
+                    };
+                }
+                _ => return Err(format!("propiedad de luz desconocida: {}", field)),
+            }
+        }
+        ["object", index, field] => {
+            let object = &mut objects[parse_index(index, objects.len())?];
+            match *field {
+                "center" => object.set_center(parse_vec3(values)?),
+                "size" => object.set_size(parse_f32(parse_single(values)?)?),
+                _ => return Err(format!("propiedad de objeto desconocida: {}", field)),
+            }
+        }
+        ["material", index, field] => {
+            let material = objects[parse_index(index, objects.len())?].material_mut();
+            match *field {
+                "diffuse" => material.diffuse = parse_color(values)?,
+                "specular" => material.specular = parse_f32(parse_single(values)?)?,
+                "albedo" => match values {
+                    [diffuse, specular] => material.albedo = [parse_f32(diffuse)?, parse_f32(specular)?],
+                    _ => return Err("se esperaban 2 valores".to_string()),
+                },
+                "crystal" => material.is_crystal = parse_bool(parse_single(values)?)?,
+                "reflectivity" => material.reflectivity = parse_f32(parse_single(values)?)?.clamp(0.0, 1.0),
+                "tint" => material.tint = parse_color(values)?,
+                "emissive" => material.emissive = parse_color(values)?,
+                "variation" => material.variation = parse_f32(parse_single(values)?)?.max(0.0),
+                "opacity" => material.opacity = parse_f32(parse_single(values)?)?.clamp(0.0, 1.0),
+                "ior" => material.ior = parse_f32(parse_single(values)?)?.max(1.0),
+                // none vuelve a los rebotes de los ajustes
+                "max_depth" => {
+                    material.max_depth = match parse_single(values)? {
+                        "none" => None,
+                        depth => Some(depth.parse::<u32>().map_err(|_| format!("valor inválido: {}", depth))?.min(MAX_RAY_DEPTH)),
+                    };
+                }
+                // Cambia entre Phong y el modelo físico; metallic y roughness lo activan
+                "pbr" => material.pbr = parse_bool(parse_single(values)?)?.then(|| material.pbr.unwrap_or_default()),
+                "metallic" => material.pbr.get_or_insert_default().metallic = parse_f32(parse_single(values)?)?.clamp(0.0, 1.0),
+                "roughness" => material.pbr.get_or_insert_default().roughness = parse_f32(parse_single(values)?)?.clamp(0.0, 1.0),
+                _ => return Err(format!("propiedad de material desconocida: {}", field)),
+            }
+        }
+        ["group", name, field] => {
+            let group = groups
+                .iter_mut()
+                .find(|group| group.name == *name)
+                .ok_or_else(|| format!("grupo desconocido: {}", name))?;
+            match *field {
+                // `none` quita el cambio y devuelve a cada miembro su material original
+                "tint" if values == ["none"] => group.tint = None,
+                "material" if values == ["none"] => group.material = None,
+                "tint" => group.tint = Some(parse_color(values)?),
+                // Usa el material del objeto N para todo el grupo
+                "material" => {
+                    let source = parse_index(parse_single(values)?, objects.len())?;
+                    group.material = Some(objects[source].material().clone());
+                }
+                _ => return Err(format!("propiedad de grupo desconocida: {}", field)),
+            }
+            group.apply(objects);
+        }
+        // Mueve, gira (grados alrededor de X, Y y Z) o escala el nodo con todos sus hijos
+        ["node", name, field] => {
+            let index = graph.find(name).ok_or_else(|| format!("nodo desconocido: {}", name))?;
+            let node = &mut graph.nodes[index];
+            match *field {
+                "translation" => node.translation = parse_vec3(values)?,
+                "rotation" => {
+                    let rotation = parse_vec3(values)?;
+                    node.rotation = [rotation.x, rotation.y, rotation.z];
+                }
+                "scale" => {
+                    let scale = parse_vec3(values)?;
+                    if scale.min() <= 0.0 {
+                        return Err("la escala debe ser positiva".to_string());
+                    }
+                    node.scale = scale;
+                }
+                _ => return Err(format!("propiedad de nodo desconocida: {}", field)),
+            }
+            graph.flatten(objects);
+        }
+        _ => return Err(format!("ruta desconocida: {}", path)),
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// contact_sheet.rs
+
+use std::f32::consts::PI;
+
+use image::{imageops, RgbImage};
+
+use crate::camera::Camera;
+use crate::object::Object;
+use crate::export::framebuffer_to_image;
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::render;
+use crate::settings::RenderSettings;
+
+// Renderiza la escena desde `angles` ángulos alrededor del centro y los acomoda en una cuadrícula
+pub fn render_contact_sheet(
+    objects: &[Object],
+    lights: &[Light],
+    camera: &Camera,
+    angles: usize,
+    tile_width: usize,
+    tile_height: usize,
+    settings: &RenderSettings,
+) -> RgbImage {
+    let angles = angles.max(1);
+    let columns = (angles as f32).sqrt().ceil() as usize;
+    let rows = angles.div_ceil(columns);
+
+    let mut sheet = RgbImage::new((columns * tile_width) as u32, (rows * tile_height) as u32);
+    let mut tile = Framebuffer::new(tile_width, tile_height);
+    let mut tile_camera = camera.clone();
+    let yaw_step = 2.0 * PI / angles as f32;
+
+    for i in 0..angles {
+        render(&mut tile, objects, &tile_camera, lights, settings);
+
+        let degrees = (i as f32 * yaw_step).to_degrees().round();
+        font::draw_label(&mut tile, 4, 4, &format!("#{} YAW {}", i + 1, degrees));
+
+        let x = (i % columns) * tile_width;
+        let y = (i / columns) * tile_height;
+        imageops::replace(&mut sheet, &framebuffer_to_image(&tile), x as i64, y as i64);
+
+        tile_camera.orbit(yaw_step, 0.0);
+    }
+
+    sheet
+}
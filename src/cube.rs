@@ -1,45 +1,112 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
 use nalgebra_glm::Vec3;
 use crate::ray_intersect::{RayIntersect, Intersect};
 use crate::material::Material;
+use crate::object::{Object, SceneObject};
+use crate::obj_export::{self, ObjTransform};
+use crate::ray::Ray;
+
+// Cara de un cubo o de un bloque según su normal hacia afuera, con los puntos
+// cardinales de los bloques (norte = -Z)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    // +X
+    East,
+    // -X
+    West,
+    // +Y
+    Top,
+    // -Y
+    Bottom,
+    // +Z
+    South,
+    // -Z
+    North,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::East, Face::West, Face::Top, Face::Bottom, Face::South, Face::North];
+
+    // La normal tiene que estar alineada a un eje
+    pub fn from_normal(normal: &Vec3) -> Self {
+        match (normal.x, normal.y, normal.z) {
+            (x, _, _) if x > 0.5 => Face::East,
+            (x, _, _) if x < -0.5 => Face::West,
+            (_, y, _) if y > 0.5 => Face::Top,
+            (_, y, _) if y < -0.5 => Face::Bottom,
+            (_, _, z) if z > 0.0 => Face::South,
+            _ => Face::North,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    // Pasa la UV de `ray_intersect` a la de una imagen vista desde afuera y derecha: en los
+    // costados arriba es +Y y en la tapa arriba es el norte
+    pub fn upright_uv(self, (u, v): (f32, f32)) -> (f32, f32) {
+        match self {
+            Face::South | Face::West => (u, 1.0 - v),
+            Face::North | Face::East => (1.0 - u, 1.0 - v),
+            Face::Top => (u, v),
+            Face::Bottom => (1.0 - u, v),
+        }
+    }
+}
 
+#[derive(Debug, Clone)]
 pub struct Cube {
     pub center: Vec3,
     pub size: f32,
-    pub material: Material,
+    pub material: Arc<Material>,
 }
 
-impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        let half_size = self.size / 2.0;
-        let min = self.center - Vec3::new(half_size, half_size, half_size);
-        let max = self.center + Vec3::new(half_size, half_size, half_size);
-
-        let inv_dir = Vec3::new(
-            1.0 / ray_direction.x,
-            1.0 / ray_direction.y,
-            1.0 / ray_direction.z,
-        );
-
-        let t1 = (min.x - ray_origin.x) * inv_dir.x;
-        let t2 = (max.x - ray_origin.x) * inv_dir.x;
-        let t3 = (min.y - ray_origin.y) * inv_dir.y;
-        let t4 = (max.y - ray_origin.y) * inv_dir.y;
-        let t5 = (min.z - ray_origin.z) * inv_dir.z;
-        let t6 = (max.z - ray_origin.z) * inv_dir.z;
-
-        let tmin = t1.min(t2).max(t3.min(t4)).max(t5.min(t6));
-        let tmax = t1.max(t2).min(t3.max(t4)).min(t5.max(t6));
-
-        if tmax < 0.0 || tmin > tmax {
-            return Intersect::empty();
-        }
+// Distancia a la que el rayo entra en el cubo, o sale si empieza adentro (true), dentro del
+// intervalo del rayo. Es todo lo que necesita el BVH para elegir el más cercano
+pub fn slab_distance(center: &Vec3, size: f32, ray: &Ray) -> Option<(f32, bool)> {
+    let ray_origin = &ray.origin;
+    let ray_direction = &ray.direction;
+    let half_size = size / 2.0;
+    let min = center - Vec3::new(half_size, half_size, half_size);
+    let max = center + Vec3::new(half_size, half_size, half_size);
+
+    let inv_dir = Vec3::new(
+        1.0 / ray_direction.x,
+        1.0 / ray_direction.y,
+        1.0 / ray_direction.z,
+    );
+
+    let t1 = (min.x - ray_origin.x) * inv_dir.x;
+    let t2 = (max.x - ray_origin.x) * inv_dir.x;
+    let t3 = (min.y - ray_origin.y) * inv_dir.y;
+    let t4 = (max.y - ray_origin.y) * inv_dir.y;
+    let t5 = (min.z - ray_origin.z) * inv_dir.z;
+    let t6 = (max.z - ray_origin.z) * inv_dir.z;
 
-        let t = if tmin > 0.0 { tmin } else { tmax };
-        if t <= 0.0 {
+    let tmin = t1.min(t2).max(t3.min(t4)).max(t5.min(t6));
+    let tmax = t1.max(t2).min(t3.max(t4)).min(t5.max(t6));
+
+    if tmin > tmax {
+        return None;
+    }
+
+    // Entrada si está dentro del intervalo; si no, la salida (rayo que empieza dentro)
+    let inside = !ray.contains(tmin);
+    let t = if inside { tmax } else { tmin };
+    ray.contains(t).then_some((t, inside))
+}
+
+impl RayIntersect for Cube {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let Some((t, inside)) = slab_distance(&self.center, self.size, ray) else {
             return Intersect::empty();
-        }
+        };
+        let half_size = self.size / 2.0;
 
-        let point = ray_origin + ray_direction * t;
+        let point = ray.at(t);
         let local_point = point - self.center;
 
         // Determinar la normal basada en la cara más cercana
@@ -55,25 +122,81 @@ impl RayIntersect for Cube {
             Vec3::new(0.0, 0.0, local_point.z.signum())
         };
 
-        // Calcular coordenadas UV según la cara golpeada
+        // Calcular coordenadas UV según la cara golpeada, con la tangente y
+        // bitangente (direcciones en que crecen u y v)
         let mut uv = None;
+        let mut tangent = Vec3::zeros();
+        let mut bitangent = Vec3::zeros();
         if normal.x != 0.0 {
             // ±X → mapear YZ
             let u = (local_point.z + half_size) / self.size;
             let v = (local_point.y + half_size) / self.size;
             uv = Some((u, v));
+            tangent = Vec3::new(0.0, 0.0, 1.0);
+            bitangent = Vec3::new(0.0, 1.0, 0.0);
         } else if normal.y != 0.0 {
             // ±Y → mapear XZ
             let u = (local_point.x + half_size) / self.size;
             let v = (local_point.z + half_size) / self.size;
             uv = Some((u, v));
+            tangent = Vec3::new(1.0, 0.0, 0.0);
+            bitangent = Vec3::new(0.0, 0.0, 1.0);
         } else if normal.z != 0.0 {
             // ±Z → mapear XY
             let u = (local_point.x + half_size) / self.size;
             let v = (local_point.y + half_size) / self.size;
             uv = Some((u, v));
+            tangent = Vec3::new(1.0, 0.0, 0.0);
+            bitangent = Vec3::new(0.0, 1.0, 0.0);
         }
 
-        Intersect::new(point, normal, t, self.material.clone(), uv)
+        let intersect = Intersect::new(point, normal, t, self.material.clone(), uv)
+            .with_tangents(tangent, bitangent)
+            .with_uv_scale(self.size)
+            .on_face(Face::from_normal(&normal));
+        if inside { intersect.seen_from_inside() } else { intersect }
+    }
+}
+
+impl SceneObject for Cube {
+    fn kind(&self) -> &'static str {
+        "cube"
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.center = center;
+    }
+
+    fn size(&self) -> f32 {
+        self.size
+    }
+
+    fn set_size(&mut self, size: f32) {
+        self.size = size;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    // Si otro objeto comparte el material, este pasa a tener su propia copia
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.material)
+    }
+
+    fn solid_cube(&self) -> Option<(Vec3, f32)> {
+        Some((self.center, self.size))
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(self.clone())
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        obj_export::write_cube(obj, self, vertex_offset, transform)
     }
 }
@@ -0,0 +1,173 @@
+// demos.rs
+
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::light::{AreaShape, Light};
+use crate::material::Material;
+use crate::object::Object;
+use crate::pbr::Pbr;
+use crate::plane::Plane;
+use crate::procedural::{Pattern, PatternKind, perlin};
+use crate::scene::Scene;
+use crate::scene_graph::SceneGraph;
+use crate::sphere::Sphere;
+use crate::texture::Texture;
+
+// Escenas de `Cube demo NOMBRE`
+pub const NAMES: [&str; 4] = ["cornell", "glass", "island", "mirrors"];
+
+// Lado de la isla en bloques
+const ISLAND_SIZE: i32 = 24;
+// Altura del agua de la isla, en bloques
+const WATER_LEVEL: i32 = 1;
+
+// Escena de demostración armada en código; None si no hay una con ese nombre. La semilla
+// cambia los colores y las posiciones al azar (y el terreno de la isla)
+pub fn build(name: &str, seed: u64) -> Option<Scene> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scene = match name {
+        "cornell" => cornell(),
+        "glass" => glass(&mut rng),
+        "island" => island(&mut rng),
+        "mirrors" => mirrors(&mut rng),
+        _ => return None,
+    };
+    Some(scene)
+}
+
+fn matte(r: f32, g: f32, b: f32) -> Material {
+    Material::new(Color::from_srgb(r, g, b), 5.0, [0.9, 0.05])
+}
+
+fn checker_floor() -> Object {
+    let mut material = matte(255.0, 255.0, 255.0);
+    material.texture = Some(Texture::Procedural(Pattern {
+        kind: PatternKind::Checker,
+        scale: 1.0,
+        colors: [[220.0, 220.0, 220.0], [70.0, 70.0, 70.0]],
+        world: true,
+        octaves: 1,
+    }.linearized()));
+    Box::new(Plane::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), 1.0, material))
+}
+
+fn camera(eye: [f32; 3], center: [f32; 3]) -> Option<Camera> {
+    Some(Camera::new(Vec3::new(eye[0], eye[1], eye[2]), Vec3::new(center[0], center[1], center[2]), Vec3::new(0.0, 1.0, 0.0)))
+}
+
+// Caja de Cornell: paredes roja y verde, un panel de luz en el techo y dos cajas blancas
+fn cornell() -> Scene {
+    let wall = |point: [f32; 3], normal: [f32; 3], material: Arc<Material>| -> Object {
+        Box::new(Plane::new(Vec3::new(point[0], point[1], point[2]), Vec3::new(normal[0], normal[1], normal[2]), 1.0, material))
+    };
+    let white = Arc::new(matte(220.0, 220.0, 220.0));
+    let objects: Vec<Object> = vec![
+        wall([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], white.clone()),
+        wall([0.0, 2.0, 0.0], [0.0, -1.0, 0.0], white.clone()),
+        wall([0.0, 0.0, -1.0], [0.0, 0.0, 1.0], white.clone()),
+        wall([-1.0, 0.0, 0.0], [1.0, 0.0, 0.0], matte(190.0, 30.0, 30.0).into()),
+        wall([1.0, 0.0, 0.0], [-1.0, 0.0, 0.0], matte(40.0, 170.0, 50.0).into()),
+        // La caja alta son dos cubos apilados
+        Box::new(Cube { center: Vec3::new(-0.35, 0.3, -0.35), size: 0.6, material: white.clone() }),
+        Box::new(Cube { center: Vec3::new(-0.35, 0.9, -0.35), size: 0.6, material: white.clone() }),
+        Box::new(Cube { center: Vec3::new(0.4, 0.25, 0.3), size: 0.5, material: white }),
+    ];
+    let mut light = Light::new(Vec3::new(0.0, 1.95, 0.0), Color::from_srgb(255.0, 240.0, 220.0), 1.0);
+    light.area = Some(AreaShape::Rectangle { width: 0.5, depth: 0.5 });
+    Scene { objects, lights: vec![light], camera: camera([0.0, 1.0, 3.4], [0.0, 1.0, 0.0]), background: None, max_depth: None, graph: SceneGraph::default(), portals: Vec::new(), groups: Vec::new() }
+}
+
+// Cristales, translúcidos de colores y dieléctricos pulidos en fila sobre un tablero
+fn glass(rng: &mut StdRng) -> Scene {
+    let mut objects = vec![checker_floor()];
+    for index in 0..5 {
+        let x = index as f32 * 1.2 - 2.4;
+        let tint = Color::from_srgb(rng.gen_range(60.0..255.0), rng.gen_range(60.0..255.0), rng.gen_range(60.0..255.0));
+        let material = match index {
+            0 | 4 => Material::crystal(tint, 80.0, [0.1, 0.8]),
+            2 => Material { pbr: Some(Pbr { metallic: 0.0, roughness: 0.05 }), ..Material::new(tint, 5.0, [0.9, 0.05]) },
+            _ => Material { opacity: rng.gen_range(0.2..0.5), ..Material::new(tint, 80.0, [0.6, 0.4]) },
+        };
+        if index % 2 == 0 {
+            objects.push(Box::new(Sphere { center: Vec3::new(x, 0.5, 0.0), radius: 0.5, material: material.into() }));
+        } else {
+            objects.push(Box::new(Cube { center: Vec3::new(x, 0.45, 0.0), size: 0.9, material: material.into() }));
+        }
+    }
+    let lights = vec![
+        Light::new(Vec3::new(3.0, 5.0, 4.0), Color::from_srgb(255.0, 255.0, 255.0), 0.9),
+        Light::new(Vec3::new(-4.0, 3.0, -2.0), Color::from_srgb(150.0, 180.0, 255.0), 0.4),
+    ];
+    Scene { objects, lights, camera: camera([0.0, 1.6, 5.0], [0.0, 0.4, 0.0]), background: None, max_depth: None, graph: SceneGraph::default(), portals: Vec::new(), groups: Vec::new() }
+}
+
+// Isla de bloques sobre el mar: alturas de ruido de Perlin que bajan hacia los bordes
+fn island(rng: &mut StdRng) -> Scene {
+    let offset = Vec3::new(rng.gen_range(0.0..100.0), 0.0, rng.gen_range(0.0..100.0));
+    let block = |r: f32, g: f32, b: f32| Material { variation: 0.08, ..matte(r, g, b) };
+    // Cada capa es un solo material compartido por todos sus bloques
+    let (grass, dirt, sand, stone) = (
+        Arc::new(block(90.0, 160.0, 60.0)),
+        Arc::new(block(130.0, 90.0, 55.0)),
+        Arc::new(block(220.0, 200.0, 140.0)),
+        Arc::new(block(130.0, 130.0, 135.0)),
+    );
+
+    let half = ISLAND_SIZE as f32 / 2.0;
+    let mut objects: Vec<Object> = Vec::new();
+    for x in 0..ISLAND_SIZE {
+        for z in 0..ISLAND_SIZE {
+            let (px, pz) = (x as f32 - half + 0.5, z as f32 - half + 0.5);
+            let noise = perlin(&(Vec3::new(px, 0.0, pz) * 0.15 + offset)) + 0.5 * perlin(&(Vec3::new(px, 0.0, pz) * 0.3 + offset));
+            let falloff = 1.0 - (px * px + pz * pz).sqrt() / half;
+            let height = ((noise + falloff) * 7.0).floor() as i32;
+            for y in 0..height.max(0) {
+                let material = if height <= WATER_LEVEL + 1 {
+                    &sand
+                } else if y == height - 1 {
+                    &grass
+                } else if y >= height - 3 {
+                    &dirt
+                } else {
+                    &stone
+                };
+                objects.push(Box::new(Cube { center: Vec3::new(px, y as f32 + 0.5, pz), size: 1.0, material: material.clone() }));
+            }
+        }
+    }
+    let water = Material { opacity: 0.55, reflectivity: 0.2, ..Material::new(Color::from_srgb(40.0, 110.0, 170.0), 120.0, [0.6, 0.5]) };
+    objects.push(Box::new(Plane::new(Vec3::new(0.0, WATER_LEVEL as f32 + 0.4, 0.0), Vec3::new(0.0, 1.0, 0.0), 4.0, water)));
+    objects.push(Box::new(Plane::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), 1.0, block(200.0, 185.0, 130.0))));
+
+    let sun = Light::new(Vec3::new(30.0, 40.0, 20.0), Color::from_srgb(255.0, 240.0, 210.0), 1.0);
+    Scene { objects, lights: vec![sun], camera: camera([20.0, 16.0, 22.0], [0.0, 2.0, 0.0]), background: None, max_depth: None, graph: SceneGraph::default(), portals: Vec::new(), groups: Vec::new() }
+}
+
+// Dos espejos enfrentados con objetos entre ellos; con --max-depth alto el pasillo se repite
+fn mirrors(rng: &mut StdRng) -> Scene {
+    let mirror = Material { reflectivity: 0.9, ..Material::new(Color::from_srgb(200.0, 210.0, 220.0), 200.0, [0.3, 0.6]) };
+    let mut objects = vec![
+        checker_floor(),
+        Box::new(Plane::new(Vec3::new(-2.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 1.0, mirror.clone())) as Object,
+        Box::new(Plane::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 1.0, mirror)),
+    ];
+    for _ in 0..4 {
+        let diffuse = Color::from_srgb(rng.gen_range(40.0..255.0), rng.gen_range(40.0..255.0), rng.gen_range(40.0..255.0));
+        let center = Vec3::new(rng.gen_range(-1.3..1.3), 0.0, rng.gen_range(-2.0..1.0));
+        let material = Material::new(diffuse, 60.0, [0.8, 0.3]);
+        if rng.gen_bool(0.5) {
+            objects.push(Box::new(Sphere { center: center + Vec3::new(0.0, 0.35, 0.0), radius: 0.35, material: material.into() }));
+        } else {
+            objects.push(Box::new(Cube { center: center + Vec3::new(0.0, 0.3, 0.0), size: 0.6, material: material.into() }));
+        }
+    }
+    let light = Light::new(Vec3::new(0.0, 4.0, 2.0), Color::from_srgb(255.0, 255.0, 255.0), 1.0);
+    Scene { objects, lights: vec![light], camera: camera([-1.0, 1.3, 4.5], [0.3, 0.6, -1.0]), background: None, max_depth: None, graph: SceneGraph::default(), portals: Vec::new(), groups: Vec::new() }
+}
@@ -0,0 +1,47 @@
+// depth_heatmap.rs
+
+use std::cell::Cell;
+
+use crate::cast_ray;
+use crate::color::Color;
+use crate::frame_graph::FrameContext;
+use crate::integrator::Integrator;
+use crate::ray::Ray;
+use crate::restir::PixelRng;
+
+// Color de cada cantidad de rebotes, de 0 en adelante: azul oscuro, azul, cian, verde,
+// amarillo, naranja, rojo y blanco desde 7
+const PALETTE: [Color; 8] = [
+    Color { r: 20.0, g: 20.0, b: 60.0 },
+    Color { r: 40.0, g: 90.0, b: 220.0 },
+    Color { r: 30.0, g: 190.0, b: 200.0 },
+    Color { r: 60.0, g: 200.0, b: 70.0 },
+    Color { r: 230.0, g: 220.0, b: 50.0 },
+    Color { r: 240.0, g: 140.0, b: 30.0 },
+    Color { r: 220.0, g: 40.0, b: 30.0 },
+    Color { r: 255.0, g: 255.0, b: 255.0 },
+];
+
+thread_local! {
+    // Rebote más profundo del rayo de cámara que está trazando este hilo
+    static DEEPEST: Cell<u32> = const { Cell::new(0) };
+}
+
+// Cada rayo trazado avisa su profundidad
+pub fn record(depth: u32) {
+    DEEPEST.with(|deepest| deepest.set(deepest.get().max(depth)));
+}
+
+// Mapa de rebotes: traza como Whitted y pinta cada pixel según el rebote más profundo que
+// usó, para ver dónde se gasta la profundidad y ajustar `max_depth` de los materiales
+pub struct DepthHeatmap;
+
+impl Integrator for DepthHeatmap {
+    fn radiance(&self, ray: &Ray, context: &FrameContext, _rng: &mut PixelRng) -> Color {
+        DEEPEST.with(|deepest| deepest.set(0));
+        cast_ray(ray, context.scene, context.light_grid, context.settings, 0);
+        let deepest = DEEPEST.with(Cell::get) as usize;
+        let Color { r, g, b } = PALETTE[deepest.min(PALETTE.len() - 1)];
+        Color::from_srgb(r, g, b)
+    }
+}
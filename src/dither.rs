@@ -0,0 +1,68 @@
+// dither.rs
+
+use crate::color::Color;
+use crate::settings::RenderSettings;
+
+// Matriz de Bayer 8x8: umbrales de 0 a 63 repartidos para que no formen bloques
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+// Tramado al pasar el color a 8 bits por canal: en vez de truncar siempre hacia abajo,
+// cada pixel redondea con un umbral distinto y los degradados suaves (el cielo) no forman bandas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dither {
+    // Matriz de Bayer, patrón regular
+    Ordered,
+    // Ruido de gradiente entrelazado (Jimenez 2014): sin patrón visible y de energía en alta frecuencia
+    Noise,
+}
+
+impl Dither {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ordered" | "bayer" => Some(Dither::Ordered),
+            "noise" | "blue-noise" => Some(Dither::Noise),
+            _ => None,
+        }
+    }
+
+    // Umbral en [0, 1) para el pixel (x, y)
+    pub fn threshold(self, x: usize, y: usize) -> f32 {
+        match self {
+            Dither::Ordered => (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0,
+            Dither::Noise => (52.982_918 * (0.067_110_56 * x as f32 + 0.005_837_15 * y as f32).fract()).fract(),
+        }
+    }
+}
+
+// Color lineal ya expuesto a valores codificados del espacio de salida (0-255), sin cuantizar
+fn encode(color: Color, settings: &RenderSettings) -> Color {
+    let color = match settings.tone_map {
+        Some(tone_map) => tone_map.apply(color),
+        None => color,
+    };
+    settings.output_transform.apply(color)
+}
+
+// Color del pixel (x, y) en el espacio de salida, empaquetado en u32 con tramado si se pidió
+pub fn quantize(color: Color, x: usize, y: usize, settings: &RenderSettings) -> u32 {
+    let color = encode(color, settings);
+    match settings.dither {
+        Some(dither) => color.to_hex_dithered(dither.threshold(x, y)),
+        None => color.to_hex(),
+    }
+}
+
+// Como `quantize` pero a 16 bits por canal; con 65536 niveles no hace falta tramado
+pub fn quantize_16(color: Color, settings: &RenderSettings) -> [u16; 3] {
+    let color = encode(color, settings);
+    [color.r, color.g, color.b].map(|value| (value.clamp(0.0, 255.0) / 255.0 * 65535.0).round() as u16)
+}
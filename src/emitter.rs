@@ -0,0 +1,79 @@
+// emitter.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::object::Object;
+use crate::ray_intersect::Intersect;
+use crate::settings::RenderSettings;
+use crate::secondary_ray;
+
+// Objeto con material emisivo, muestreado como luz por su esfera envolvente. `emissive`
+// es radiancia en la escala 0-255 de los colores: la misma que el fondo
+pub struct Emitter {
+    pub object: usize,
+    pub center: Vec3,
+    pub radius: f32,
+    pub emissive: Color,
+}
+
+// Los emisivos sin caja (planos infinitos) no se pueden muestrear; su luz solo llega
+// cuando un camino los golpea
+pub fn collect(objects: &[Object]) -> Vec<Emitter> {
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| object.material().emissive != Color::black())
+        .filter_map(|(index, object)| {
+            let bounds = object.bounds()?;
+            Some(Emitter {
+                object: index,
+                center: (bounds.min + bounds.max) * 0.5,
+                radius: (bounds.max - bounds.min).norm() * 0.5,
+                emissive: object.material().emissive,
+            })
+        })
+        .collect()
+}
+
+impl Emitter {
+    // Dirección al centro y coseno del cono que cubre la esfera envolvente vista desde
+    // `point`; None desde adentro de la esfera
+    pub fn cone(&self, point: &Vec3) -> Option<(Vec3, f32)> {
+        let to_center = self.center - point;
+        let distance = to_center.norm();
+        if distance <= self.radius {
+            return None;
+        }
+        Some((to_center / distance, (1.0 - (self.radius / distance).powi(2)).max(0.0).sqrt()))
+    }
+
+    // Luz que devuelve un difuso blanco cuando el emisor llena el cono y llega con `cosine`
+    // contra la normal: radiancia por ángulo sólido 2π(1 - cos_max) por el coseno, entre
+    // π del difuso lambertiano. Un cielo emisivo de 255 que cubre el hemisferio da 255,
+    // igual que una luz blanca de intensidad 1 de frente
+    pub fn reflected(&self, cosine: f32, cos_max: f32) -> Color {
+        self.emissive * (cosine * 2.0 * (1.0 - cos_max))
+    }
+}
+
+// Luz directa de los emisivos en Whitted: un rayo de sombra al centro de cada uno en lugar
+// de muestrear el cono como el trazado de caminos, con la misma escala. Es la que recibe un
+// difuso blanco; falta teñirla con el albedo
+pub fn direct_light(intersect: &Intersect, emitters: &[Emitter], scene: &Bvh, settings: &RenderSettings) -> Color {
+    let (point, normal) = (intersect.point, intersect.normal);
+    let mut light = Color::black();
+    for emitter in emitters.iter().filter(|emitter| intersect.object != Some(emitter.object)) {
+        let Some((axis, cos_max)) = emitter.cone(&point) else { continue };
+        let cosine = normal.dot(&axis);
+        if cosine <= 0.0 {
+            continue;
+        }
+        let hit = scene.intersect(&secondary_ray(intersect, &axis, f32::INFINITY, settings));
+        if hit.is_intersecting && !hit.inside && hit.object == Some(emitter.object) {
+            light = light + emitter.reflected(cosine, cos_max);
+        }
+    }
+    light
+}
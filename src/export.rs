@@ -0,0 +1,228 @@
+// export.rs
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use std::path::Path;
+
+use exr::meta::attribute::Chromaticities;
+use exr::prelude::{Image, SpecificChannels, Vec2, WritableImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+use image::{Delay, DynamicImage, Frame, ImageBuffer, ImageEncoder, ImageFormat, ImageResult, Rgb, RgbImage};
+
+use crate::color_management::OutputTransform;
+use crate::framebuffer::Framebuffer;
+use crate::settings::RenderSettings;
+use crate::tonemap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Png16,
+    Ppm,
+    Bmp,
+    Tga,
+    WebP,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "png16" => Some(OutputFormat::Png16),
+            "ppm" => Some(OutputFormat::Ppm),
+            "bmp" => Some(OutputFormat::Bmp),
+            "tga" => Some(OutputFormat::Tga),
+            "webp" => Some(OutputFormat::WebP),
+            _ => None,
+        }
+    }
+
+    // Extensión de los archivos en este formato
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png | OutputFormat::Png16 => "png",
+            OutputFormat::Ppm => "ppm",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tga => "tga",
+            OutputFormat::WebP => "webp",
+        }
+    }
+
+    // Elige el formato a partir de la extensión del archivo
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(OutputFormat::from_name)
+    }
+}
+
+pub fn framebuffer_to_image(framebuffer: &Framebuffer) -> RgbImage {
+    ImageBuffer::from_fn(framebuffer.width as u32, framebuffer.height as u32, |x, y| {
+        let pixel = framebuffer.buffer[y as usize * framebuffer.width + x as usize];
+        Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+    })
+}
+
+// Con `color_space`, los PNG llevan los chunks cICP, cHRM y gAMA (o sRGB) del espacio
+// de color de salida; los demás formatos se guardan sin etiquetar. `settings` (mapeo de
+// tonos y espacio de salida) solo hace falta para el PNG de 16 bits, que sale del cuadro HDR
+pub fn save_framebuffer(
+    framebuffer: &Framebuffer,
+    path: &Path,
+    format: OutputFormat,
+    settings: &RenderSettings,
+    color_space: Option<OutputTransform>,
+) -> Result<(), Box<dyn Error>> {
+    let image = framebuffer_to_image(framebuffer);
+
+    match (format, color_space) {
+        (OutputFormat::Png, Some(color_space)) => {
+            save_tagged_png(path, image.width(), image.height(), image.as_raw(), png::BitDepth::Eight, color_space)?
+        }
+        (OutputFormat::Png, None) => image.save_with_format(path, ImageFormat::Png)?,
+        (OutputFormat::Png16, color_space) => {
+            let (width, height) = (framebuffer.width as u32, framebuffer.height as u32);
+            let wide: ImageBuffer<Rgb<u16>, Vec<u16>> = ImageBuffer::from_raw(width, height, tonemap::resolve_16(framebuffer, settings))
+                .ok_or("el cuadro HDR no coincide con el tamaño de la imagen")?;
+            match color_space {
+                Some(color_space) => {
+                    let bytes: Vec<u8> = wide.as_raw().iter().flat_map(|value| value.to_be_bytes()).collect();
+                    save_tagged_png(path, wide.width(), wide.height(), &bytes, png::BitDepth::Sixteen, color_space)?
+                }
+                None => wide.save_with_format(path, ImageFormat::Png)?,
+            }
+        }
+        (OutputFormat::Ppm, _) => {
+            let writer = BufWriter::new(File::create(path)?);
+            PnmEncoder::new(writer)
+                .with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary))
+                .write_image(image.as_raw(), image.width(), image.height(), image::ExtendedColorType::Rgb8)?
+        }
+        (OutputFormat::Bmp, _) => image.save_with_format(path, ImageFormat::Bmp)?,
+        (OutputFormat::Tga, _) => image.save_with_format(path, ImageFormat::Tga)?,
+        // El codificador WebP de `image` siempre es sin pérdida
+        (OutputFormat::WebP, _) => image.save_with_format(path, ImageFormat::WebP)?,
+    }
+    Ok(())
+}
+
+fn save_tagged_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    data: &[u8],
+    depth: png::BitDepth,
+    color_space: OutputTransform,
+) -> Result<(), Box<dyn Error>> {
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(depth);
+    if color_space == OutputTransform::Srgb {
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    } else {
+        let [white, red, green, blue] = color_space.chromaticities();
+        encoder.set_source_chromaticities(png::SourceChromaticities::new(white, red, green, blue));
+        encoder.set_source_gamma(png::ScaledFloat::new(color_space.gamma()));
+    }
+
+    let mut png_writer = encoder.write_header()?;
+    // cICP tiene prioridad sobre los demás chunks en los lectores que lo soportan
+    png_writer.write_chunk(png::chunk::cICP, &color_space.cicp())?;
+    png_writer.write_image_data(data)?;
+    png_writer.finish()?;
+    Ok(())
+}
+
+const ANIMATION_FRAME_DELAY_MS: u32 = 40;
+
+// Guarda una secuencia de cuadros como GIF animado, o como APNG si la ruta termina en .png/.apng
+pub fn save_animation(frames: &[RgbImage], path: &Path) -> Result<(), Box<dyn Error>> {
+    let is_apng = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png") || ext.eq_ignore_ascii_case("apng"));
+
+    let writer = BufWriter::new(File::create(path)?);
+    if is_apng {
+        save_apng(frames, writer)
+    } else {
+        save_gif(frames, writer)
+    }
+}
+
+fn save_gif(frames: &[RgbImage], writer: BufWriter<File>) -> Result<(), Box<dyn Error>> {
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames.iter().map(|frame| {
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).to_rgba8();
+        Frame::from_parts(rgba, 0, 0, Delay::from_numer_denom_ms(ANIMATION_FRAME_DELAY_MS, 1))
+    }))?;
+    Ok(())
+}
+
+fn save_apng(frames: &[RgbImage], writer: BufWriter<File>) -> Result<(), Box<dyn Error>> {
+    let (width, height) = frames.first().map(|frame| frame.dimensions()).unwrap_or((0, 0));
+
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(ANIMATION_FRAME_DELAY_MS as u16, 1000)?;
+
+    let mut png_writer = encoder.write_header()?;
+    for frame in frames {
+        png_writer.write_image_data(frame.as_raw())?;
+    }
+    png_writer.finish()?;
+    Ok(())
+}
+
+// EXR en punto flotante con la radiancia lineal del cuadro (1.0 = 255), con la exposición
+// aplicada pero sin mapeo de tonos ni recorte: sirve como imagen fuente HDR. Lleva los
+// primarios de sRGB, en los que está el HDR sea cual sea la transformación de salida
+pub fn save_hdr_exr(framebuffer: &Framebuffer, path: &Path) -> Result<(), Box<dyn Error>> {
+    let scale = framebuffer.exposure / 255.0;
+    let pixels = SpecificChannels::rgb(|Vec2(x, y): Vec2<usize>| {
+        let color = framebuffer.hdr[y * framebuffer.width + x];
+        (color.r * scale, color.g * scale, color.b * scale)
+    });
+    let mut image = Image::from_channels((framebuffer.width, framebuffer.height), pixels);
+    let [white, red, green, blue] = OutputTransform::Srgb.chromaticities().map(|(x, y)| Vec2(x, y));
+    image.attributes.chromaticities = Some(Chromaticities { red, green, blue, white });
+    image.write().to_file(path)?;
+    Ok(())
+}
+
+pub fn encode_png(framebuffer: &Framebuffer) -> ImageResult<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    framebuffer_to_image(framebuffer).write_to(&mut bytes, ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::color::Color;
+
+    // Un degradado suave guardado a 16 bits conserva más niveles de los 256 que caben en 8
+    #[test]
+    fn png16_keeps_more_than_256_levels() {
+        let mut framebuffer = Framebuffer::new(2048, 1);
+        for (x, pixel) in framebuffer.hdr.iter_mut().enumerate() {
+            let value = x as f32 / 2047.0 * 255.0;
+            *pixel = Color::new(value, value, value);
+        }
+        let path = std::env::temp_dir().join(format!("cubito-png16-{}.png", std::process::id()));
+        let saved = save_framebuffer(&framebuffer, &path, OutputFormat::Png16, &RenderSettings::default(), None);
+        let image = saved.and_then(|()| Ok(image::open(&path)?.into_rgb16()));
+        std::fs::remove_file(&path).ok();
+
+        let levels: HashSet<u16> = image.unwrap().pixels().map(|pixel| pixel[0]).collect();
+        assert!(levels.len() > 256, "solo {} niveles", levels.len());
+    }
+}
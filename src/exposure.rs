@@ -0,0 +1,49 @@
+// exposure.rs
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Luminancia lineal promedio (0..1) a la que se intenta llevar la imagen: el gris medio,
+// que ya codificado en sRGB queda cerca de la mitad de la escala
+const TARGET_LUMINANCE: f32 = 0.18;
+// Fracción del camino hacia la exposición deseada que se recorre por cuadro
+const ADAPTATION_RATE: f32 = 0.1;
+const MIN_EXPOSURE: f32 = 1.0 / 16.0;
+const MAX_EXPOSURE: f32 = 16.0;
+
+pub struct AutoExposure {
+    pub enabled: bool,
+    pub exposure: f32,
+}
+
+// Del color lineal antes de la exposición, en 0..1 (puede pasarse de 1 donde se suman luces)
+fn luminance(color: &Color) -> f32 {
+    (0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b) / 255.0
+}
+
+impl AutoExposure {
+    pub fn new(enabled: bool) -> Self {
+        AutoExposure { enabled, exposure: 1.0 }
+    }
+
+    // Mide el cuadro recién dibujado y ajusta suavemente la exposición del siguiente
+    pub fn update(&mut self, framebuffer: &Framebuffer) {
+        if !self.enabled || framebuffer.hdr.is_empty() {
+            return;
+        }
+
+        // Promedio logarítmico del HDR, menos sensible a unos pocos pixeles muy brillantes. Se
+        // mide antes de la exposición y del recorte a 8 bits: lo saturado también cuenta
+        let log_sum: f32 = framebuffer.hdr.iter()
+            .map(|color| (luminance(color).max(0.0) + 1e-4).ln())
+            .filter(|value| value.is_finite())
+            .sum();
+        let scene_luminance = (log_sum / framebuffer.hdr.len() as f32).exp().max(1e-4);
+        let desired = (TARGET_LUMINANCE / scene_luminance).clamp(MIN_EXPOSURE, MAX_EXPOSURE);
+
+        // Adaptar en escala logarítmica (en "pasos" de exposición)
+        let current_stops = self.exposure.log2();
+        let desired_stops = desired.log2();
+        self.exposure = (current_stops + (desired_stops - current_stops) * ADAPTATION_RATE).exp2();
+    }
+}
@@ -0,0 +1,65 @@
+// eyedropper.rs
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::material::Material;
+use crate::object::Object;
+use crate::ray::Ray;
+
+// Lo que hay bajo el cursor: el color ya renderizado del pixel y el material del objeto
+pub struct Sample {
+    pub color: u32,
+    pub material: Material,
+}
+
+pub fn pick(x: f32, y: f32, camera: &Camera, objects: &[Object], framebuffer: &Framebuffer) -> Option<Sample> {
+    let (px, py) = (x as usize, y as usize);
+    if px >= framebuffer.width || py >= framebuffer.height {
+        return None;
+    }
+
+    let direction = camera.primary_ray_direction(x, y, framebuffer.width as f32, framebuffer.height as f32);
+    let intersect = crate::scene_intersect(&Ray::new(camera.position, direction), objects);
+    if !intersect.is_intersecting {
+        return None;
+    }
+
+    Some(Sample {
+        color: framebuffer.buffer[py * framebuffer.width + px],
+        material: Material::clone(&intersect.material),
+    })
+}
+
+// Parámetros del color y del material en una línea, para copiarlos a la consola o a un .ron
+pub fn describe(sample: &Sample) -> String {
+    let material = &sample.material;
+    // En sRGB, como se escribe en la consola y en los .ron
+    let diffuse = material.diffuse.to_srgb();
+    let mut text = format!(
+        "color #{:06X}  material {}  diffuse ({:.0}, {:.0}, {:.0})  specular {:.0}  albedo ({:.2}, {:.2})",
+        sample.color,
+        material.name.as_deref().unwrap_or("sin nombre"),
+        diffuse[0],
+        diffuse[1],
+        diffuse[2],
+        material.specular,
+        material.albedo[0],
+        material.albedo[1],
+    );
+    if material.is_crystal {
+        text.push_str(&format!("  crystal  ior {:.2}", material.ior));
+    }
+    if material.reflectivity > 0.0 {
+        text.push_str(&format!("  reflectivity {:.2}", material.reflectivity));
+    }
+    if let Some(depth) = material.max_depth {
+        text.push_str(&format!("  max_depth {}", depth));
+    }
+    if let Some(pbr) = &material.pbr {
+        text.push_str(&format!("  metallic {:.2}  roughness {:.2}  ior {:.2}", pbr.metallic, pbr.roughness, material.ior));
+    }
+    if let Some(path) = &material.texture_path {
+        text.push_str(&format!("  texture {}", path));
+    }
+    text
+}
@@ -0,0 +1,123 @@
+// flare.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::object::Object;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::ray::Ray;
+
+// Destellos secundarios sobre la línea luz-centro de la pantalla:
+// posición (1 = la luz, -1 = reflejada al otro lado) y radio relativo
+const GHOSTS: [(f32, f32); 3] = [(0.4, 0.18), (-0.2, 0.3), (-0.7, 0.12)];
+
+#[derive(Debug, Clone, Copy)]
+pub struct LensFlare {
+    // Radio del destello en pixeles
+    pub size: f32,
+    pub intensity: f32,
+    // Número de rayos de la estrella
+    pub rays: u32,
+}
+
+impl Default for LensFlare {
+    fn default() -> Self {
+        LensFlare { size: 40.0, intensity: 1.0, rays: 6 }
+    }
+}
+
+impl LensFlare {
+    // Aporte del destello a un pixel a distancia (dx, dy) del centro de la luz, de 0 a 1
+    fn starburst(&self, dx: f32, dy: f32) -> f32 {
+        let distance = (dx * dx + dy * dy).sqrt() / self.size;
+        if distance >= 1.0 {
+            return 0.0;
+        }
+        let falloff = 1.0 - distance;
+        let glow = (-distance * 8.0).exp();
+        let angle = dy.atan2(dx);
+        let spikes = (angle * self.rays as f32 / 2.0).cos().abs().powi(40);
+        glow + spikes * falloff * falloff * 0.6
+    }
+}
+
+fn add_to_pixel(framebuffer: &mut Framebuffer, x: i32, y: i32, color: Color) {
+    if x < 0 || y < 0 || x as usize >= framebuffer.width || y as usize >= framebuffer.height {
+        return;
+    }
+    let index = y as usize * framebuffer.width + x as usize;
+    let current = framebuffer.buffer[index];
+    framebuffer.buffer[index] = (Color::from_hex(current) + color).to_hex();
+}
+
+// Un disco suave y translúcido para los destellos secundarios
+fn draw_ghost(framebuffer: &mut Framebuffer, cx: f32, cy: f32, radius: f32, color: Color) {
+    let r = radius.ceil() as i32;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius;
+            if distance < 1.0 {
+                let amount = 0.15 * (1.0 - distance * distance);
+                add_to_pixel(framebuffer, cx as i32 + dx, cy as i32 + dy, color * amount);
+            }
+        }
+    }
+}
+
+// Compone un destello en la posición de pantalla de cada luz visible desde la cámara;
+// una luz tapada por algún objeto no produce destello
+pub fn draw_lens_flares(
+    framebuffer: &mut Framebuffer,
+    camera: &Camera,
+    objects: &[Object],
+    lights: &[Light],
+    flare: &LensFlare,
+) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    for light in lights {
+        if light.intensity <= 0.0 || light.shadow_only {
+            continue;
+        }
+        let Some((sx, sy)) = camera.project(&light.position, width, height) else { continue };
+        if sx < 0.0 || sy < 0.0 || sx >= width || sy >= height {
+            continue;
+        }
+
+        let to_light: Vec3 = light.position - camera.position;
+        let distance = to_light.norm();
+        let ray = Ray::with_interval(camera.position, to_light / distance, 0.0, distance);
+        if crate::scene_intersect(&ray, objects).is_intersecting {
+            continue;
+        }
+
+        // El destello se suma a los pixeles ya codificados, así que va con el color de la luz en sRGB
+        let [lr, lg, lb] = light.color.to_srgb();
+        let light_color = Color::new(lr, lg, lb);
+        let color = light_color * (flare.intensity * light.intensity.min(1.0));
+        let r = flare.size.ceil() as i32;
+        let (cx, cy) = (sx.round() as i32, sy.round() as i32);
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let amount = flare.starburst(dx as f32, dy as f32);
+                if amount > 0.0 {
+                    add_to_pixel(framebuffer, cx + dx, cy + dy, color * amount);
+                }
+            }
+        }
+
+        let (center_x, center_y) = (width / 2.0, height / 2.0);
+        for (position, radius) in GHOSTS {
+            let gx = center_x + (sx - center_x) * position;
+            let gy = center_y + (sy - center_y) * position;
+            let hue = 0.5 + 0.5 * (position * PI).cos();
+            let tint = Color::new(255.0 * hue, 200.0, 255.0 * (1.0 - hue) + 80.0).tinted(light_color);
+            draw_ghost(framebuffer, gx, gy, flare.size * radius, tint * (flare.intensity * light.intensity.min(1.0)));
+        }
+    }
+}
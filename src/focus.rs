@@ -0,0 +1,47 @@
+// focus.rs
+
+use crate::camera::Camera;
+use crate::object::Object;
+use crate::ray::Ray;
+
+// Fracción del camino hacia el foco elegido que se recorre por cuadro
+const TRANSITION_RATE: f32 = 0.2;
+// Diferencia relativa con la que se da por llegado
+const SETTLED: f32 = 1e-3;
+
+// Enfoque con clic: con profundidad de campo, un clic izquierdo elige el punto a enfocar y
+// la distancia de foco se acerca a la suya en unos cuadros en lugar de saltar
+#[derive(Default)]
+pub struct FocusPicker {
+    target: Option<f32>,
+}
+
+impl FocusPicker {
+    // Lanza un rayo por el pixel (x, y) y toma como objetivo la distancia del impacto medida a
+    // lo largo de la vista, que es como la usa `Camera::lens_ray`. Devuelve esa distancia
+    pub fn click(&mut self, x: f32, y: f32, camera: &Camera, objects: &[Object], width: f32, height: f32) -> Option<f32> {
+        let direction = camera.primary_ray_direction(x, y, width, height);
+        let intersect = crate::scene_intersect(&Ray::new(camera.position, direction), objects);
+        if !intersect.is_intersecting {
+            return None;
+        }
+        let forward = (camera.center - camera.position).normalize();
+        let distance = intersect.distance * direction.dot(&forward);
+        self.target = Some(distance);
+        Some(distance)
+    }
+
+    // Avanza la transición un cuadro; false cuando ya no hay nada que mover
+    pub fn update(&mut self, camera: &mut Camera) -> bool {
+        let Some(target) = self.target else { return false };
+        let current = camera.focus_distance.unwrap_or_else(|| (camera.center - camera.position).magnitude());
+        let next = current + (target - current) * TRANSITION_RATE;
+        if (target - next).abs() <= SETTLED * target {
+            camera.focus_distance = Some(target);
+            self.target = None;
+        } else {
+            camera.focus_distance = Some(next);
+        }
+        true
+    }
+}
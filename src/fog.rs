@@ -0,0 +1,72 @@
+// fog.rs
+
+use std::f32::consts::PI;
+
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::light::Light;
+use crate::ray::Ray;
+use crate::restir::PixelRng;
+
+// El cielo cuenta como si estuviera a esta distancia: con niebla densa desaparece
+// y con niebla leve se sigue viendo
+const SKY_DISTANCE: f32 = 50.0;
+// Pasos con que se recorre el rayo para los haces de luz
+const SHAFT_STEPS: u32 = 32;
+
+// Medio homogéneo: la luz que viaja una distancia d se atenúa por exp(-densidad·d)
+// y lo que se pierde se reemplaza por el color de la niebla
+#[derive(Debug, Clone, Copy)]
+pub struct Fog {
+    pub density: f32,
+    pub color: Color,
+    // Haces de luz: la niebla también brilla donde la alcanzan las luces puntuales
+    pub light_shafts: bool,
+}
+
+impl Fog {
+    pub fn new(density: f32) -> Self {
+        Fog { density: density.max(0.0), color: Color::from_srgb(200.0, 210.0, 220.0), light_shafts: false }
+    }
+
+    // Fracción de la luz que atraviesa `distance` de niebla; el infinito es el cielo
+    pub fn transmittance(&self, distance: f32) -> f32 {
+        (-self.density * distance.min(SKY_DISTANCE)).exp()
+    }
+
+    // Color que llega a la cámara desde algo de color `color` a `distance` por el rayo
+    pub fn apply(&self, color: Color, ray: &Ray, distance: f32, scene: &Bvh, lights: &[Light]) -> Color {
+        let transmittance = self.transmittance(distance);
+        let mut fogged = color.blend(self.color, 1.0 - transmittance);
+        if self.light_shafts {
+            fogged = fogged + self.in_scattering(ray, distance.min(SKY_DISTANCE), scene, lights);
+        }
+        fogged
+    }
+
+    // Luz de las luces puntuales que la niebla desvía hacia la cámara a lo largo del rayo,
+    // solo donde la luz no está tapada; de ahí salen los haces entre los objetos
+    fn in_scattering(&self, ray: &Ray, distance: f32, scene: &Bvh, lights: &[Light]) -> Color {
+        let step = distance / SHAFT_STEPS as f32;
+        // Un desplazamiento al azar por rayo cambia las bandas de los pasos por ruido fino
+        let bits = ray.direction.map(|value| value.to_bits());
+        let jitter = PixelRng::new(bits.x as usize, bits.y as usize ^ bits.z as usize, 0).next();
+
+        let mut scattered = Color::black();
+        for index in 0..SHAFT_STEPS {
+            let t = (index as f32 + jitter) * step;
+            let point = ray.at(t);
+            // Dispersión isótropa: cada punto manda hacia la cámara 1/4π de la luz que recibe
+            let attenuation = (-self.density * t).exp() * self.density * step / (4.0 * PI);
+            for light in lights.iter().filter(|light| !light.shadow_only) {
+                let (light_dir, light_distance) = (light.direction_from(&point), light.distance_from(&point));
+                let shadow_ray = Ray::with_interval(point, light_dir, 0.0, light_distance).at_time(ray.time);
+                if light.casts_shadows && scene.any_hit(&shadow_ray).is_intersecting {
+                    continue;
+                }
+                scattered = scattered + light.color * (light.intensity_towards(&point) * attenuation);
+            }
+        }
+        scattered
+    }
+}
@@ -0,0 +1,243 @@
+// frame_graph.rs
+
+use std::time::{Duration, Instant};
+
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::flare;
+use crate::framebuffer::Framebuffer;
+use crate::half_res;
+use crate::integrator::{self, Integrator};
+use crate::light::Light;
+use crate::light_grid::LightGrid;
+use crate::object::Object;
+use crate::settings::{IntegratorKind, RenderSettings};
+use crate::{restir, taa, tonemap};
+
+// Buffers del framebuffer que leen y escriben las pasadas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    // Color lineal en flotantes (`framebuffer.hdr`)
+    Hdr,
+    // Pixeles de 8 bits que se muestran o se guardan (`framebuffer.buffer`)
+    Display,
+}
+
+// Lo que comparten todas las pasadas de un cuadro
+pub struct FrameContext<'a> {
+    pub objects: &'a [Object],
+    pub camera: &'a Camera,
+    pub lights: &'a [Light],
+    pub settings: &'a RenderSettings,
+    pub scene: &'a Bvh<'a>,
+    pub light_grid: &'a LightGrid<'a>,
+    pub integrator: &'a dyn Integrator,
+}
+
+// Un paso del cuadro. Las entradas y salidas declaradas deciden dónde se inserta una pasada
+// nueva; una pasada que no aplica con los ajustes actuales simplemente no hace nada
+pub trait RenderPass {
+    fn name(&self) -> &'static str;
+    fn inputs(&self) -> &'static [Resource];
+    fn outputs(&self) -> &'static [Resource];
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext);
+}
+
+// Rayos de cámara con el integrador elegido
+struct TracePass;
+
+impl RenderPass for TracePass {
+    fn name(&self) -> &'static str {
+        "trace"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        // Los reservorios eligen luces por pixel entre cuadros y no encajan en un integrador por rayo
+        let FrameContext { camera, settings, scene, light_grid, .. } = context;
+        match settings.light_samples {
+            Some(candidates) if settings.integrator == IntegratorKind::Whitted => {
+                restir::render(framebuffer, scene, camera, light_grid, settings, candidates)
+            }
+            _ => integrator::render(framebuffer, context),
+        }
+    }
+}
+
+// Reflejos y oclusión ambiental a media resolución, sumados al cuadro trazado
+struct HalfResPass;
+
+impl RenderPass for HalfResPass {
+    fn name(&self) -> &'static str {
+        "half_res"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        if half_res::is_enabled(context.settings) {
+            half_res::composite(framebuffer, context);
+        }
+    }
+}
+
+// Antialiasing temporal; solo con los integradores que no acumulan, que son los que desplazan los rayos
+struct TaaPass;
+
+impl RenderPass for TaaPass {
+    fn name(&self) -> &'static str {
+        "taa"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        let settings = context.settings;
+        let restir = settings.light_samples.is_some() && settings.integrator == IntegratorKind::Whitted;
+        if settings.taa && !context.integrator.accumulates() && !restir {
+            taa::accumulate(framebuffer, context.scene, context.camera);
+        }
+    }
+}
+
+// Exposición, mapeo de tonos y tramado a 8 bits
+struct ResolvePass;
+
+impl RenderPass for ResolvePass {
+    fn name(&self) -> &'static str {
+        "resolve"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[Resource::Hdr]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Display]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        tonemap::resolve(framebuffer, context.settings);
+    }
+}
+
+struct GuidesPass;
+
+impl RenderPass for GuidesPass {
+    fn name(&self) -> &'static str {
+        "guides"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[Resource::Display]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Display]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        if let Some(guides) = &context.settings.guides {
+            guides.draw(framebuffer, context.camera, context.objects);
+        }
+    }
+}
+
+struct FlarePass;
+
+impl RenderPass for FlarePass {
+    fn name(&self) -> &'static str {
+        "flare"
+    }
+    fn inputs(&self) -> &'static [Resource] {
+        &[Resource::Display]
+    }
+    fn outputs(&self) -> &'static [Resource] {
+        &[Resource::Display]
+    }
+    fn run(&self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        if let Some(lens_flare) = &context.settings.lens_flare {
+            flare::draw_lens_flares(framebuffer, context.camera, context.objects, context.lights, lens_flare);
+        }
+    }
+}
+
+struct Node {
+    pass: Box<dyn RenderPass>,
+    enabled: bool,
+}
+
+// Lista ordenada de pasadas del cuadro. Cada una se puede apagar y se mide su tiempo
+pub struct FrameGraph {
+    nodes: Vec<Node>,
+    // Tiempo de cada pasada habilitada en el último cuadro
+    timings: Vec<(&'static str, Duration)>,
+}
+
+impl Default for FrameGraph {
+    // trace -> half_res -> taa -> resolve -> guides -> flare
+    fn default() -> Self {
+        let mut graph = FrameGraph { nodes: Vec::new(), timings: Vec::new() };
+        graph.add(Box::new(TracePass));
+        graph.add(Box::new(HalfResPass));
+        graph.add(Box::new(TaaPass));
+        graph.add(Box::new(ResolvePass));
+        graph.add(Box::new(GuidesPass));
+        graph.add(Box::new(FlarePass));
+        graph
+    }
+}
+
+impl FrameGraph {
+    // Agrega la pasada después de la última que escribe algo que ella lee, y antes de la
+    // primera que lee algo que ella escribe si esa viene antes (un efecto sobre Hdr queda
+    // antes de resolve aunque se agregue al final)
+    pub fn add(&mut self, pass: Box<dyn RenderPass>) {
+        let writes = |node: &Node, resources: &[Resource]| node.pass.outputs().iter().any(|resource| resources.contains(resource));
+        let after = self.nodes.iter().rposition(|node| writes(node, pass.inputs())).map_or(0, |index| index + 1);
+        let before = self.nodes[after..]
+            .iter()
+            .position(|node| node.pass.inputs().iter().any(|resource| pass.outputs().contains(resource)) && !writes(node, pass.outputs()))
+            .map_or(self.nodes.len(), |index| after + index);
+        self.nodes.insert(before, Node { pass, enabled: true });
+    }
+
+    // Prende o apaga una pasada por nombre; Err si no existe. Apagar una pasada deja sus
+    // salidas como estaban en el cuadro anterior
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let names = self.names();
+        let node = self
+            .nodes
+            .iter_mut()
+            .find(|node| node.pass.name() == name)
+            .ok_or_else(|| format!("pasada desconocida: {} (hay {})", name, names.join(", ")))?;
+        node.enabled = enabled;
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.nodes.iter().map(|node| node.pass.name()).collect()
+    }
+
+    pub fn execute(&mut self, framebuffer: &mut Framebuffer, context: &FrameContext) {
+        self.timings.clear();
+        for node in self.nodes.iter().filter(|node| node.enabled) {
+            let start = Instant::now();
+            node.pass.run(framebuffer, context);
+            self.timings.push((node.pass.name(), start.elapsed()));
+        }
+    }
+
+    // Tiempos del último cuadro, por ejemplo "trace 12.3 ms | resolve 0.4 ms"
+    pub fn report(&self) -> String {
+        self.timings
+            .iter()
+            .map(|(name, time)| format!("{} {:.1} ms", name, time.as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
@@ -1,9 +1,27 @@
 // framebuffer.rs
 
+use crate::color::Color;
+use crate::integrator::Accumulation;
+use crate::shading_cache::ShadingCache;
+use crate::restir::ReservoirHistory;
+use crate::taa::TaaHistory;
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
+    // Color lineal de cada pixel antes de la exposición y el mapeo de tonos; puede pasarse
+    // de 255 donde se suman luces. `tonemap::resolve` lo pasa a `buffer`
+    pub hdr: Vec<Color>,
+    pub exposure: f32,
+    // Muestras de luz del cuadro anterior, para el render con reservorios
+    pub reservoirs: ReservoirHistory,
+    // Suma de los cuadros del trazado de caminos
+    pub accumulation: Accumulation,
+    // Cuadros anteriores del antialiasing temporal
+    pub taa: TaaHistory,
+    // Sombras y oclusión por texel, con --shading-cache
+    pub shading_cache: ShadingCache,
     background_color: u32,
     current_color: u32,
 }
@@ -14,6 +32,12 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            hdr: vec![Color::black(); width * height],
+            exposure: 1.0,
+            reservoirs: ReservoirHistory::default(),
+            accumulation: Accumulation::default(),
+            shading_cache: ShadingCache::default(),
+            taa: TaaHistory::default(),
             background_color: 0x000000,
             current_color: 0xFFFFFF,
         }
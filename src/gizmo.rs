@@ -0,0 +1,135 @@
+// gizmo.rs
+
+use nalgebra_glm::{Vec3, rotate_vec3};
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+
+const GIZMO_RADIUS: i32 = 3;
+const SELECTION_RADIUS: i32 = 6;
+
+fn plot(framebuffer: &mut Framebuffer, x: i32, y: i32) {
+    if x >= 0 && y >= 0 {
+        framebuffer.point(x as usize, y as usize);
+    }
+}
+
+// Dibuja un rombo del color de cada luz en su posición en pantalla;
+// la luz seleccionada se enmarca con un cuadro blanco
+pub fn draw_light_gizmos(framebuffer: &mut Framebuffer, camera: &Camera, lights: &[Light], selected: Option<usize>) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    for (i, light) in lights.iter().enumerate() {
+        let Some((sx, sy)) = camera.project(&light.position, width, height) else { continue };
+        let (cx, cy) = (sx.round() as i32, sy.round() as i32);
+
+        // Contorno negro para que el rombo se vea sobre fondos claros
+        framebuffer.set_current_color(0x000000);
+        for dy in -(GIZMO_RADIUS + 1)..=(GIZMO_RADIUS + 1) {
+            for dx in -(GIZMO_RADIUS + 1)..=(GIZMO_RADIUS + 1) {
+                if dx.abs() + dy.abs() == GIZMO_RADIUS + 1 {
+                    plot(framebuffer, cx + dx, cy + dy);
+                }
+            }
+        }
+
+        framebuffer.set_current_color(light.color.to_hex());
+        for dy in -GIZMO_RADIUS..=GIZMO_RADIUS {
+            for dx in -GIZMO_RADIUS..=GIZMO_RADIUS {
+                if dx.abs() + dy.abs() <= GIZMO_RADIUS {
+                    plot(framebuffer, cx + dx, cy + dy);
+                }
+            }
+        }
+
+        if selected == Some(i) {
+            framebuffer.set_current_color(0xFFFFFF);
+            for d in -SELECTION_RADIUS..=SELECTION_RADIUS {
+                plot(framebuffer, cx + d, cy - SELECTION_RADIUS);
+                plot(framebuffer, cx + d, cy + SELECTION_RADIUS);
+                plot(framebuffer, cx - SELECTION_RADIUS, cy + d);
+                plot(framebuffer, cx + SELECTION_RADIUS, cy + d);
+            }
+        }
+    }
+}
+
+// La luz cuyo gizmo está más cerca del pixel (x, y), si está dentro del cuadro de selección
+pub fn pick_light(camera: &Camera, lights: &[Light], x: f32, y: f32, width: f32, height: f32) -> Option<usize> {
+    let reach = SELECTION_RADIUS as f32;
+    lights
+        .iter()
+        .enumerate()
+        .filter_map(|(i, light)| {
+            let (sx, sy) = camera.project(&light.position, width, height)?;
+            let (dx, dy) = ((sx - x).abs(), (sy - y).abs());
+            (dx <= reach && dy <= reach).then_some((i, dx * dx + dy * dy))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+// A dónde arrastrar una luz que está en `position` para que quede bajo el pixel (x, y): se
+// mueve en el plano paralelo a la pantalla que pasa por ella, así no se acerca ni se aleja
+pub fn drag_position(camera: &Camera, position: &Vec3, x: f32, y: f32, width: f32, height: f32) -> Option<Vec3> {
+    let forward = (camera.center - camera.position).normalize();
+    let direction = camera.primary_ray_direction(x, y, width, height);
+    let facing = direction.dot(&forward);
+    if facing <= 0.0 {
+        return None;
+    }
+    let distance = (position - camera.position).dot(&forward) / facing;
+    (distance > 0.0).then(|| camera.position + direction * distance)
+}
+
+// Gira la dirección de una luz direccional: `yaw` alrededor del eje vertical y `pitch`
+// hacia arriba o abajo, en radianes. Puede salir de la vertical pero no llegar a ella, donde
+// el giro hacia arriba o abajo no tiene un eje claro
+pub fn turn_direction(direction: &Vec3, yaw: f32, pitch: f32) -> Vec3 {
+    let direction = rotate_vec3(&direction.normalize(), yaw, &Vec3::y());
+    let side = direction.cross(&Vec3::y());
+    let side = if side.norm() < 1e-6 { Vec3::x() } else { side.normalize() };
+    let pitched = rotate_vec3(&direction, pitch, &side);
+    if pitched.y.abs() > 0.999 && pitched.y.abs() > direction.y.abs() { direction } else { pitched }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y())
+    }
+
+    #[test]
+    fn picks_the_light_under_the_cursor() {
+        let lights = [Light::new(Vec3::zeros(), Color::black(), 1.0), Light::new(Vec3::new(1.0, 0.0, 0.0), Color::black(), 1.0)];
+        let (x, y) = camera().project(&lights[1].position, 200.0, 100.0).unwrap();
+        assert_eq!(pick_light(&camera(), &lights, x + 2.0, y - 2.0, 200.0, 100.0), Some(1));
+        assert_eq!(pick_light(&camera(), &lights, 100.0, 50.0, 200.0, 100.0), Some(0));
+        assert_eq!(pick_light(&camera(), &lights, 10.0, 10.0, 200.0, 100.0), None);
+    }
+
+    // Arrastrar la luz al pixel donde se ve otro punto de su mismo plano la lleva a ese punto
+    #[test]
+    fn dragging_keeps_the_light_depth() {
+        let target = Vec3::new(0.7, -0.4, 1.0);
+        let (x, y) = camera().project(&target, 200.0, 100.0).unwrap();
+        let moved = drag_position(&camera(), &Vec3::new(0.0, 0.0, 1.0), x, y, 200.0, 100.0).unwrap();
+        assert!((moved - target).norm() < 1e-4, "{:?}", moved);
+    }
+
+    #[test]
+    fn turning_keeps_the_direction_off_the_vertical() {
+        let down = Vec3::new(1.0, -1.0, 0.0).normalize();
+        let turned = turn_direction(&down, std::f32::consts::FRAC_PI_2, 0.0);
+        assert!((turned - Vec3::new(0.0, -1.0, -1.0).normalize()).norm() < 1e-5, "{:?}", turned);
+        let steep = (0..200).fold(down, |direction, _| turn_direction(&direction, 0.0, 0.05));
+        assert!(steep.y.abs() < 1.0 && (steep.norm() - 1.0).abs() < 1e-4, "{:?}", steep);
+        // Una luz que apunta justo hacia abajo también se puede inclinar
+        assert!(turn_direction(&Vec3::new(0.0, -1.0, 0.0), 0.0, 0.1).y > -0.999);
+    }
+}
@@ -0,0 +1,749 @@
+// gltf.rs
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::f32::consts::FRAC_PI_4;
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::Engine;
+use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::light::{Light, LightKind};
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::object::Object;
+use crate::pbr::Pbr;
+use crate::texture::{Mipmap, Texture};
+
+// Cabecera de un .glb y tipos de sus bloques
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON: u32 = 0x4E4F534A;
+const GLB_BIN: u32 = 0x004E4942;
+
+// Extensiones que se entienden; un archivo que exige otra no se puede importar bien
+const SUPPORTED_EXTENSIONS: [&str; 1] = ["KHR_lights_punctual"];
+
+// Lo que se usa del JSON de glTF 2.0; el resto del archivo se ignora
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    #[serde(default)]
+    scene: Option<usize>,
+    #[serde(default)]
+    scenes: Vec<SceneNodes>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    meshes: Vec<MeshDescription>,
+    #[serde(default)]
+    materials: Vec<MaterialDescription>,
+    #[serde(default)]
+    textures: Vec<TextureDescription>,
+    #[serde(default)]
+    images: Vec<ImageDescription>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    extensions: DocumentExtensions,
+    #[serde(default)]
+    extensions_required: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SceneNodes {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    // Columna por columna; si no está se usan traslación, giro (cuaternión x, y, z, w) y escala
+    matrix: Option<[f32; 16]>,
+    translation: Option<[f32; 3]>,
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+    #[serde(default)]
+    extensions: NodeExtensions,
+}
+
+#[derive(Deserialize, Default)]
+struct NodeExtensions {
+    #[serde(rename = "KHR_lights_punctual")]
+    light: Option<NodeLight>,
+}
+
+#[derive(Deserialize)]
+struct NodeLight {
+    light: usize,
+}
+
+#[derive(Deserialize, Default)]
+struct DocumentExtensions {
+    #[serde(rename = "KHR_lights_punctual")]
+    lights: Option<Lights>,
+}
+
+#[derive(Deserialize)]
+struct Lights {
+    lights: Vec<LightDescription>,
+}
+
+#[derive(Deserialize)]
+struct LightDescription {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default = "default_white")]
+    color: [f32; 3],
+    #[serde(default = "default_one")]
+    intensity: f32,
+    spot: Option<Spot>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Spot {
+    #[serde(default)]
+    inner_cone_angle: f32,
+    #[serde(default = "default_outer_cone")]
+    outer_cone_angle: f32,
+}
+
+#[derive(Deserialize)]
+struct MeshDescription {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    // 4 = triángulos, 5 = tira, 6 = abanico; puntos y líneas no se importan
+    #[serde(default = "default_mode")]
+    mode: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MaterialDescription {
+    name: Option<String>,
+    #[serde(default)]
+    pbr_metallic_roughness: MetallicRoughness,
+    #[serde(default)]
+    emissive_factor: [f32; 3],
+    #[serde(default)]
+    alpha_mode: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetallicRoughness {
+    #[serde(default = "default_base_color")]
+    base_color_factor: [f32; 4],
+    #[serde(default = "default_one")]
+    metallic_factor: f32,
+    #[serde(default = "default_one")]
+    roughness_factor: f32,
+    base_color_texture: Option<TextureInfo>,
+}
+
+impl Default for MetallicRoughness {
+    fn default() -> Self {
+        MetallicRoughness { base_color_factor: default_base_color(), metallic_factor: 1.0, roughness_factor: 1.0, base_color_texture: None }
+    }
+}
+
+// Las mallas solo leen TEXCOORD_0, así que `texCoord` no se usa
+#[derive(Deserialize)]
+struct TextureInfo {
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct TextureDescription {
+    source: Option<usize>,
+}
+
+// Un archivo (o datos embebidos) o una vista de un buffer
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageDescription {
+    uri: Option<String>,
+    buffer_view: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Buffer {
+    uri: Option<String>,
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn default_white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_base_color() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_outer_cone() -> f32 {
+    FRAC_PI_4
+}
+
+fn default_mode() -> u32 {
+    4
+}
+
+// Objetos y luces de un archivo glTF, en el mundo de la escena que lo incluye
+pub struct Imported {
+    pub objects: Vec<Object>,
+    pub lights: Vec<Light>,
+}
+
+pub fn is_gltf(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("gltf") || ext.eq_ignore_ascii_case("glb"))
+}
+
+// Importa un .gltf (con sus .bin al lado o embebidos) o un .glb. Cada nodo aporta su
+// transformación a la de sus hijos y `root` lleva todo a la escena; los vértices de las mallas
+// quedan ya en el mundo. Cada malla es un objeto con una submalla por primitiva y su material
+// (con la textura de color base sobre TEXCOORD_0), sombreada con sus normales por vértice
+// salvo con `flat`; las luces son las de
+// KHR_lights_punctual, con su intensidad tal cual (candelas en las puntuales y los focos, que
+// se atenúan con el cuadrado de la distancia en metros, y lux en las direccionales)
+pub fn import(path: &Path, root: &Mat4, flat: bool) -> Result<Imported, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    let (json, binary) = if bytes.starts_with(GLB_MAGIC) { split_glb(&bytes)? } else { (bytes.as_slice(), None) };
+    let document: Document = serde_json::from_slice(json)?;
+    if let Some(extension) = document.extensions_required.iter().find(|name| !SUPPORTED_EXTENSIONS.contains(&name.as_str())) {
+        return Err(format!("la extensión {} no está soportada", extension).into());
+    }
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let buffers = document
+        .buffers
+        .iter()
+        .map(|buffer| match &buffer.uri {
+            Some(uri) => read_uri(uri, dir),
+            None => binary.map(<[u8]>::to_vec).ok_or_else(|| "un buffer sin uri fuera de un .glb".into()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let reader = Reader { document: &document, buffers, dir };
+
+    let images = (0..document.images.len()).map(|index| reader.image(index)).collect::<Result<Vec<_>, _>>()?;
+    let textures = document
+        .textures
+        .iter()
+        .map(|texture| texture.source.map(|source| images.get(source).cloned().ok_or_else(|| format!("la imagen {} no existe", source))).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    let materials = document.materials.iter().map(|material| build_material(material, &textures).map(Arc::new)).collect::<Result<Vec<_>, _>>()?;
+    // Sin material glTF usa blanco, completamente metálico y rugoso
+    let default_material = Arc::new(build_material(
+        &MaterialDescription { name: None, pbr_metallic_roughness: MetallicRoughness::default(), emissive_factor: [0.0; 3], alpha_mode: None },
+        &[],
+    )?);
+
+    let mut imported = Imported { objects: Vec::new(), lights: Vec::new() };
+    // Sin escenas se toman los nodos que no son hijos de otro
+    let roots: Vec<usize> = match document.scene.or((!document.scenes.is_empty()).then_some(0)) {
+        Some(scene) => document.scenes.get(scene).ok_or("la escena por defecto no existe")?.nodes.clone(),
+        None => (0..document.nodes.len())
+            .filter(|&index| !document.nodes.iter().any(|node| node.children.contains(&index)))
+            .collect(),
+    };
+    let mut pending: Vec<(usize, Mat4, usize)> = roots.into_iter().map(|index| (index, *root, 0)).collect();
+    while let Some((index, parent, depth)) = pending.pop() {
+        let node = document.nodes.get(index).ok_or_else(|| format!("el nodo {} no existe", index))?;
+        // Un ciclo de nodos no termina nunca
+        if depth > document.nodes.len() {
+            return Err("los nodos forman un ciclo".into());
+        }
+        let transform = parent * node_matrix(node);
+        if let Some(mesh) = node.mesh {
+            let mesh = document.meshes.get(mesh).ok_or_else(|| format!("la malla {} no existe", mesh))?;
+            let material = |index: Option<usize>| match index {
+                Some(index) => materials.get(index).cloned().ok_or_else(|| format!("el material {} no existe", index)),
+                None => Ok(default_material.clone()),
+            };
+            if let Some(mesh) = reader.mesh(mesh, &transform, material, flat)? {
+                imported.objects.push(Box::new(mesh));
+            }
+        }
+        if let Some(NodeLight { light }) = node.extensions.light {
+            let lights = document.extensions.lights.as_ref().map(|lights| lights.lights.as_slice()).unwrap_or_default();
+            let light = lights.get(light).ok_or_else(|| format!("la luz {} no existe", light))?;
+            imported.lights.push(build_light(light, &transform)?);
+        }
+        pending.extend(node.children.iter().map(|&child| (child, transform, depth + 1)));
+    }
+    Ok(imported)
+}
+
+// El JSON y el bloque binario de un .glb
+type Chunks<'a> = (&'a [u8], Option<&'a [u8]>);
+
+fn split_glb(bytes: &[u8]) -> Result<Chunks<'_>, Box<dyn Error>> {
+    let word = |at: usize| -> Result<u32, Box<dyn Error>> {
+        let word = bytes.get(at..at + 4).ok_or("el .glb está cortado")?;
+        Ok(u32::from_le_bytes(word.try_into()?))
+    };
+    if word(4)? != 2 {
+        return Err("solo se importa glTF 2.0".into());
+    }
+    let (mut json, mut binary) = (None, None);
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let (length, kind) = (word(at)? as usize, word(at + 4)?);
+        let chunk = bytes.get(at + 8..at + 8 + length).ok_or("el .glb está cortado")?;
+        match kind {
+            GLB_JSON => json = Some(chunk),
+            GLB_BIN => binary = Some(chunk),
+            _ => {}
+        }
+        at += 8 + length;
+    }
+    Ok((json.ok_or("el .glb no tiene JSON")?, binary))
+}
+
+// Un buffer embebido (data:...;base64,...) o un archivo junto al .gltf
+fn read_uri(uri: &str, dir: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data.split_once(";base64,").ok_or("solo se admiten datos embebidos en base64")?;
+        return Ok(base64::engine::general_purpose::STANDARD.decode(encoded)?);
+    }
+    std::fs::read(dir.join(uri)).map_err(|error| format!("{}: {}", uri, error).into())
+}
+
+fn node_matrix(node: &Node) -> Mat4 {
+    if let Some(matrix) = node.matrix {
+        return Mat4::from_column_slice(&matrix);
+    }
+    let [x, y, z, w] = node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    glm::translation(&Vec3::from(node.translation.unwrap_or_default()))
+        * glm::quat_to_mat4(&glm::quat(x, y, z, w))
+        * glm::scaling(&Vec3::from(node.scale.unwrap_or([1.0; 3])))
+}
+
+// Los colores de glTF son lineales entre 0 y 1
+fn linear_color([r, g, b]: [f32; 3]) -> Color {
+    Color::new(r * 255.0, g * 255.0, b * 255.0)
+}
+
+// `textures` tiene la imagen de cada textura del archivo, si la tiene
+fn build_material(description: &MaterialDescription, textures: &[Option<Arc<Mipmap>>]) -> Result<Material, Box<dyn Error>> {
+    let pbr = &description.pbr_metallic_roughness;
+    let [r, g, b, alpha] = pbr.base_color_factor;
+    let mut material = Material::new(linear_color([r, g, b]), 0.0, [1.0, 0.0]);
+    // El color base es la textura multiplicada por el factor
+    if let Some(TextureInfo { index }) = pbr.base_color_texture
+        && let Some(image) = textures.get(index).ok_or_else(|| format!("la textura {} no existe", index))?
+    {
+        material.texture = Some(Texture::Image(image.clone()));
+        material.tint = linear_color([r, g, b]);
+    }
+    material.name = description.name.clone();
+    material.pbr = Some(Pbr {
+        metallic: pbr.metallic_factor.clamp(0.0, 1.0),
+        roughness: pbr.roughness_factor.clamp(0.0, 1.0),
+    });
+    material.emissive = linear_color(description.emissive_factor);
+    // OPAQUE y MASK no dejan pasar luz
+    if description.alpha_mode.as_deref() == Some("BLEND") {
+        material.opacity = alpha.clamp(0.0, 1.0);
+    }
+    Ok(material)
+}
+
+// La luz mira hacia -Z de su nodo
+fn build_light(description: &LightDescription, transform: &Mat4) -> Result<Light, Box<dyn Error>> {
+    let position = (transform * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+    let direction = (glm::mat4_to_mat3(transform) * Vec3::new(0.0, 0.0, -1.0)).normalize();
+    let mut light = Light::new(position, linear_color(description.color), description.intensity);
+    light.kind = match description.kind.as_str() {
+        "directional" => LightKind::Directional { direction },
+        "point" => LightKind::Point,
+        "spot" => {
+            let spot = description.spot.as_ref().ok_or("un foco sin spot")?;
+            LightKind::Spot { direction, inner: spot.inner_cone_angle, outer: spot.outer_cone_angle }
+        }
+        kind => return Err(format!("tipo de luz desconocido: {}", kind).into()),
+    };
+    if !matches!(light.kind, LightKind::Directional { .. }) {
+        light.attenuation = 1.0;
+    }
+    Ok(light)
+}
+
+// Un componente de un accesor en f32, con sus bytes y si está normalizado
+type Decode = fn(&[u8], bool) -> f32;
+
+// Lee los accesores y las imágenes de los buffers ya cargados
+struct Reader<'a> {
+    document: &'a Document,
+    buffers: Vec<Vec<u8>>,
+    // Carpeta del archivo, para las imágenes que están al lado
+    dir: &'a Path,
+}
+
+impl Reader<'_> {
+    fn view(&self, index: usize) -> Result<&[u8], Box<dyn Error>> {
+        let view = self.document.buffer_views.get(index).ok_or_else(|| format!("la vista {} no existe", index))?;
+        let buffer = self.buffers.get(view.buffer).ok_or_else(|| format!("el buffer {} no existe", view.buffer))?;
+        Ok(buffer.get(view.byte_offset..view.byte_offset + view.byte_length).ok_or("una vista se sale de su buffer")?)
+    }
+
+    fn image(&self, index: usize) -> Result<Arc<Mipmap>, Box<dyn Error>> {
+        let image = &self.document.images[index];
+        let bytes = match (&image.uri, image.buffer_view) {
+            (Some(uri), _) => read_uri(uri, self.dir)?,
+            (None, Some(view)) => self.view(view)?.to_vec(),
+            (None, None) => return Err(format!("la imagen {} no tiene uri ni vista", index).into()),
+        };
+        let image = image::load_from_memory(&bytes).map_err(|error| format!("la imagen {}: {}", index, error))?;
+        Ok(Arc::new(Mipmap::new(&image)))
+    }
+
+    // Los valores del accesor en f32, `components` por elemento; los enteros normalizados
+    // pasan a 0-1 (o -1-1)
+    fn floats(&self, index: usize, components: usize) -> Result<Vec<f32>, Box<dyn Error>> {
+        let accessor = self.document.accessors.get(index).ok_or_else(|| format!("el accesor {} no existe", index))?;
+        let expected = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            3 => "VEC3",
+            _ => "VEC4",
+        };
+        if accessor.kind != expected {
+            return Err(format!("el accesor {} es {} y se esperaba {}", index, accessor.kind, expected).into());
+        }
+        if accessor.sparse.is_some() {
+            return Err(format!("el accesor {} es disperso, y eso no está soportado", index).into());
+        }
+        let (size, decode): (usize, Decode) = match accessor.component_type {
+            5120 => (1, |bytes, normalized| {
+                let value = bytes[0] as i8 as f32;
+                if normalized { (value / 127.0).max(-1.0) } else { value }
+            }),
+            5121 => (1, |bytes, normalized| bytes[0] as f32 / if normalized { 255.0 } else { 1.0 }),
+            5122 => (2, |bytes, normalized| {
+                let value = i16::from_le_bytes([bytes[0], bytes[1]]) as f32;
+                if normalized { (value / 32767.0).max(-1.0) } else { value }
+            }),
+            5123 => (2, |bytes, normalized| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / if normalized { 65535.0 } else { 1.0 }),
+            5125 => (4, |bytes, _| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32),
+            5126 => (4, |bytes, _| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            other => return Err(format!("tipo de componente desconocido: {}", other).into()),
+        };
+        // Un accesor sin vista es todo ceros
+        let Some(view) = accessor.buffer_view else { return Ok(vec![0.0; accessor.count * components]) };
+        let data = self.view(view)?;
+        let stride = self.document.buffer_views[view].byte_stride.unwrap_or(size * components);
+        let mut values = Vec::with_capacity(accessor.count * components);
+        for element in 0..accessor.count {
+            let start = accessor.byte_offset + element * stride;
+            let bytes = data.get(start..start + size * components).ok_or_else(|| format!("el accesor {} se sale de su vista", index))?;
+            values.extend(bytes.chunks_exact(size).map(|component| decode(component, accessor.normalized)));
+        }
+        Ok(values)
+    }
+
+    fn vectors(&self, index: usize, transform: impl Fn(Vec3) -> Vec3) -> Result<Vec<Vec3>, Box<dyn Error>> {
+        Ok(self.floats(index, 3)?.chunks_exact(3).map(|xyz| transform(Vec3::new(xyz[0], xyz[1], xyz[2]))).collect())
+    }
+
+    // Las primitivas de la malla como una sola malla en el mundo, con una submalla por
+    // primitiva; None si ninguna tiene triángulos. A las que no tienen normales o UV se les
+    // pone la normal de la cara y (0, 0)
+    fn mesh(
+        &self,
+        mesh: &MeshDescription,
+        transform: &Mat4,
+        material: impl Fn(Option<usize>) -> Result<Arc<Material>, String>,
+        flat: bool,
+    ) -> Result<Option<Mesh>, Box<dyn Error>> {
+        let linear = glm::mat4_to_mat3(transform);
+        // Las normales siguen perpendiculares con la inversa transpuesta
+        let inverse = linear.try_inverse().ok_or("un nodo aplasta su malla")?.transpose();
+        let (mut positions, mut normals, mut uvs) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut has_normals, mut has_uvs) = (false, false);
+        let mut submeshes = Vec::new();
+        for primitive in &mesh.primitives {
+            let &position = primitive.attributes.get("POSITION").ok_or("una primitiva sin POSITION")?;
+            let points = self.vectors(position, |point| (transform * point.push(1.0)).xyz())?;
+            let Some(mut triangles) = self.triangles(primitive, points.len())? else { continue };
+            // Una transformación espejada da vuelta las caras
+            if linear.determinant() < 0.0 {
+                triangles.iter_mut().for_each(|triangle| triangle.swap(1, 2));
+            }
+            if triangles.is_empty() {
+                continue;
+            }
+            let offset = positions.len() as u32;
+            triangles.iter_mut().flatten().for_each(|index| *index += offset);
+
+            match primitive.attributes.get("NORMAL") {
+                Some(&normal) => {
+                    let read = self.vectors(normal, |normal| inverse * normal)?;
+                    if read.len() != points.len() {
+                        return Err("NORMAL no tiene una normal por vértice".into());
+                    }
+                    normals.extend(read);
+                    has_normals = true;
+                }
+                None => normals.extend(std::iter::repeat_n(Vec3::zeros(), points.len())),
+            }
+            match primitive.attributes.get("TEXCOORD_0") {
+                Some(&uv) => {
+                    let read: Vec<(f32, f32)> = self.floats(uv, 2)?.chunks_exact(2).map(|uv| (uv[0], uv[1])).collect();
+                    if read.len() != points.len() {
+                        return Err("TEXCOORD_0 no tiene una coordenada por vértice".into());
+                    }
+                    uvs.extend(read);
+                    has_uvs = true;
+                }
+                None => uvs.extend(std::iter::repeat_n((0.0, 0.0), points.len())),
+            }
+            positions.extend(points);
+            submeshes.push((triangles, material(primitive.material)?));
+        }
+        if submeshes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut mesh = Mesh::new(positions, submeshes).ok_or("una primitiva usa un vértice que no existe")?;
+        mesh.flat = flat;
+        // Sin normales la malla se ve por caras, como pide glTF
+        if has_normals {
+            mesh = mesh.with_normals(normals).ok_or("NORMAL no tiene una normal por vértice")?;
+        }
+        if has_uvs {
+            mesh = mesh.with_uvs(uvs).ok_or("TEXCOORD_0 no tiene una coordenada por vértice")?;
+        }
+        Ok(Some(mesh))
+    }
+
+    // Los triángulos de la primitiva, antihorarios, con índices de sus `count` vértices; None
+    // si son puntos o líneas
+    fn triangles(&self, primitive: &Primitive, count: usize) -> Result<Option<Vec<[u32; 3]>>, Box<dyn Error>> {
+        let indices: Vec<u32> = match primitive.indices {
+            Some(indices) => self.floats(indices, 1)?.into_iter().map(|index| index as u32).collect(),
+            None => (0..count as u32).collect(),
+        };
+        Ok(Some(match primitive.mode {
+            4 => indices.chunks_exact(3).map(|corners| [corners[0], corners[1], corners[2]]).collect(),
+            // En la tira los impares van al revés para seguir antihorarios
+            5 => (2..indices.len())
+                .map(|i| if i % 2 == 0 { [indices[i - 2], indices[i - 1], indices[i]] } else { [indices[i - 1], indices[i - 2], indices[i]] })
+                .collect(),
+            6 => (2..indices.len()).map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    // Un triángulo en un nodo movido y girado, con un material y una luz puntual, todo en un
+    // .gltf con el buffer embebido
+    pub(crate) fn triangle_gltf() -> String {
+        let mut bytes = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        // Dos bytes de relleno para que las normales empiecen alineadas
+        for index in [0u16, 1, 2, 0] {
+            bytes.extend(index.to_le_bytes());
+        }
+        for value in [0.0f32, 0.6, 0.8, 0.0, 0.6, 0.8, 0.0, 0.6, 0.8] {
+            bytes.extend(value.to_le_bytes());
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0, 2] }}],
+                "nodes": [
+                    {{ "translation": [0, 0, -2], "children": [1] }},
+                    {{ "mesh": 0, "rotation": [0, 0.7071068, 0, 0.7071068] }},
+                    {{ "translation": [0, 3, 0], "extensions": {{ "KHR_lights_punctual": {{ "light": 0 }} }} }}
+                ],
+                "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0, "NORMAL": 2 }}, "indices": 1, "material": 0 }}] }}],
+                "materials": [{{ "name": "rojo", "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0 }} }}],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                    {{ "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC3" }}
+                ],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                    {{ "buffer": 0, "byteOffset": 44, "byteLength": 36 }}
+                ],
+                "buffers": [{{ "byteLength": 80, "uri": "data:application/octet-stream;base64,{}" }}],
+                "extensions": {{ "KHR_lights_punctual": {{ "lights": [{{ "type": "point", "color": [1, 1, 1], "intensity": 5 }}] }} }}
+            }}"#,
+            data
+        )
+    }
+
+    fn write(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("cubito-{}-{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn imports_nodes_meshes_materials_and_lights() {
+        let path = write("triangulo.gltf", triangle_gltf().as_bytes());
+        let imported = import(&path, &Mat4::identity(), false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(imported.objects.len(), 1);
+        let mesh = &imported.objects[0];
+        // El cuarto de vuelta alrededor de Y lleva +X a -Z, y el padre baja todo a z = -2
+        let bounds = mesh.bounds().unwrap();
+        assert!((bounds.min - Vec3::new(0.0, 0.0, -3.0)).norm() < 1e-5, "{:?}", bounds);
+        assert!((bounds.max - Vec3::new(0.0, 1.0, -2.0)).norm() < 1e-5, "{:?}", bounds);
+        assert_eq!(mesh.material().name.as_deref(), Some("rojo"));
+        assert_eq!(mesh.material().diffuse, Color::new(255.0, 0.0, 0.0));
+        assert_eq!(mesh.material().pbr.map(|pbr| pbr.metallic), Some(0.0));
+
+        // Las normales giran con el nodo: +Z pasa a +X
+        let hit = mesh.ray_intersect(&Ray::new(Vec3::new(5.0, 0.2, -2.5), Vec3::new(-1.0, 0.0, 0.0)));
+        assert!((hit.normal - Vec3::new(0.8, 0.6, 0.0)).norm() < 1e-5, "{:?}", hit.normal);
+
+        assert_eq!(imported.lights.len(), 1);
+        assert_eq!(imported.lights[0].position, Vec3::new(0.0, 3.0, 0.0));
+        assert_eq!(imported.lights[0].intensity, 5.0);
+    }
+
+    #[test]
+    fn reads_binary_gltf() {
+        let json = triangle_gltf().into_bytes();
+        let mut glb = GLB_MAGIC.to_vec();
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((20 + json.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(GLB_JSON.to_le_bytes());
+        glb.extend(&json);
+        let path = write("triangulo.glb", &glb);
+        let imported = import(&path, &glm::translation(&Vec3::new(10.0, 0.0, 0.0)), false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(imported.lights[0].position, Vec3::new(10.0, 3.0, 0.0));
+    }
+
+    // Un cuadrado en z = 0 hecho con dos primitivas: la primera roja y sin UV, la segunda con
+    // una textura PNG embebida
+    #[test]
+    fn joins_primitives_into_one_textured_mesh() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(2, 2, image::Rgb([0, 255, 0])).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let png = base64::engine::general_purpose::STANDARD.encode(png.into_inner());
+        let mut bytes = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2, 0, 2, 3] {
+            bytes.extend(index.to_le_bytes());
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let text = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "nodes": [{{ "mesh": 0 }}],
+                "meshes": [{{ "primitives": [
+                    {{ "attributes": {{ "POSITION": 0 }}, "indices": 2, "material": 0 }},
+                    {{ "attributes": {{ "POSITION": 0, "TEXCOORD_0": 1 }}, "indices": 3, "material": 1 }}
+                ] }}],
+                "materials": [
+                    {{ "name": "rojo", "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0, 0, 1] }} }},
+                    {{ "name": "pasto", "pbrMetallicRoughness": {{ "baseColorFactor": [0.5, 1, 1, 1], "baseColorTexture": {{ "index": 0 }} }} }}
+                ],
+                "textures": [{{ "source": 0 }}],
+                "images": [{{ "uri": "data:image/png;base64,{}" }}],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2" }},
+                    {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                    {{ "bufferView": 2, "byteOffset": 6, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                ],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 48 }},
+                    {{ "buffer": 0, "byteOffset": 48, "byteLength": 32 }},
+                    {{ "buffer": 0, "byteOffset": 80, "byteLength": 12 }}
+                ],
+                "buffers": [{{ "byteLength": 92, "uri": "data:application/octet-stream;base64,{}" }}]
+            }}"#,
+            png, data
+        );
+        let path = write("cuadrado.gltf", text.as_bytes());
+        let imported = import(&path, &Mat4::identity(), false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(imported.objects.len(), 1);
+        let mesh = &imported.objects[0];
+        let hit = |x: f32, y: f32| mesh.ray_intersect(&Ray::new(Vec3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0)));
+        let red = hit(0.8, 0.2);
+        assert_eq!(red.material.name.as_deref(), Some("rojo"));
+        assert!(red.material.texture.is_none());
+        assert_eq!(red.uv, Some((0.0, 0.0)));
+
+        let textured = hit(0.2, 0.7);
+        assert_eq!(textured.material.name.as_deref(), Some("pasto"));
+        assert!(textured.material.texture.is_some());
+        assert_eq!(textured.material.tint, Color::new(127.5, 255.0, 255.0));
+        let (u, v) = textured.uv.unwrap();
+        assert!((u - 0.2).abs() < 1e-5 && (v - 0.3).abs() < 1e-5, "{:?}", textured.uv);
+    }
+
+    #[test]
+    fn rejects_required_extensions() {
+        let text = triangle_gltf().replacen(r#""scene": 0,"#, r#""scene": 0, "extensionsRequired": ["KHR_draco_mesh_compression"],"#, 1);
+        let path = write("draco.gltf", text.as_bytes());
+        let error = import(&path, &Mat4::identity(), false).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.to_string().contains("KHR_draco_mesh_compression"), "{}", error);
+    }
+}
@@ -0,0 +1,107 @@
+// group.rs
+
+use crate::color::Color;
+use crate::object::Object;
+use crate::material::Material;
+
+// Conjunto de objetos (por índice) que comparten un material o un tinte,
+// por ejemplo la versión roja y la azul de una misma estructura
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<usize>,
+    pub material: Option<Material>,
+    pub tint: Option<Color>,
+    // Material que tenía cada miembro antes de que el grupo lo cambiara, para poder quitar
+    // el cambio
+    originals: Vec<(usize, Material)>,
+}
+
+impl Group {
+    pub fn new(name: &str, members: Vec<usize>) -> Self {
+        Group {
+            name: name.to_string(),
+            members,
+            material: None,
+            tint: None,
+            originals: Vec::new(),
+        }
+    }
+
+    // Escribe el material y/o tinte del grupo en cada miembro, sobre su material original.
+    // Los que dejaron de ser miembros, y todos si el grupo ya no cambia nada, vuelven a su
+    // material original
+    pub fn apply(&mut self, objects: &mut [Object]) {
+        let members = &self.members;
+        let (kept, removed): (Vec<_>, Vec<_>) = self.originals.drain(..).partition(|(index, _)| members.contains(index));
+        self.originals = kept;
+        for (index, original) in removed {
+            if let Some(object) = objects.get_mut(index) {
+                *object.material_mut() = original;
+            }
+        }
+
+        for &index in &self.members {
+            let Some(object) = objects.get_mut(index) else { continue };
+            let original = match self.originals.iter().find(|(member, _)| *member == index) {
+                Some((_, original)) => original.clone(),
+                None => {
+                    self.originals.push((index, object.material().clone()));
+                    object.material().clone()
+                }
+            };
+            let mut material = self.material.clone().unwrap_or(original);
+            if let Some(tint) = self.tint {
+                material.tint = tint;
+            }
+            *object.material_mut() = material;
+        }
+        // Sin nada que cambiar los miembros quedan como eran y se pueden editar uno por uno
+        if self.material.is_none() && self.tint.is_none() {
+            self.originals.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nalgebra_glm::Vec3;
+
+    use super::*;
+    use crate::cube::Cube;
+
+    fn cube(red: f32) -> Object {
+        let material = Material::new(Color::new(red, 0.0, 0.0), 10.0, [0.9, 0.1]);
+        Box::new(Cube { center: Vec3::zeros(), size: 1.0, material: Arc::new(material) })
+    }
+
+    // Quitar el material o sacar un miembro del grupo le devuelve su material de antes
+    #[test]
+    fn overrides_can_be_removed() {
+        let mut objects = vec![cube(10.0), cube(20.0), cube(30.0)];
+        let mut group = Group::new("equipo", vec![0, 1]);
+        group.material = Some(Material::new(Color::new(0.0, 0.0, 200.0), 10.0, [0.9, 0.1]));
+        group.tint = Some(Color::new(0.5, 0.5, 0.5));
+        group.apply(&mut objects);
+        assert_eq!(objects[0].material().diffuse.b, 200.0);
+        assert_eq!(objects[1].material().tint, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(objects[2].material().diffuse.r, 30.0);
+
+        group.members = vec![0];
+        group.apply(&mut objects);
+        assert_eq!(objects[1].material().diffuse, Color::new(20.0, 0.0, 0.0));
+        assert_eq!(objects[1].material().tint, Color::new(255.0, 255.0, 255.0));
+
+        // Solo el tinte, sobre el material de antes
+        group.material = None;
+        group.apply(&mut objects);
+        assert_eq!(objects[0].material().diffuse, Color::new(10.0, 0.0, 0.0));
+        assert_eq!(objects[0].material().tint, Color::new(0.5, 0.5, 0.5));
+
+        group.tint = None;
+        group.apply(&mut objects);
+        assert_eq!(objects[0].material().tint, Color::new(255.0, 255.0, 255.0));
+    }
+}
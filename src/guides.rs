@@ -0,0 +1,87 @@
+// guides.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::object::Object;
+use crate::framebuffer::Framebuffer;
+use crate::ray::Ray;
+
+const GRID_COLOR: u32 = 0x808080;
+const X_AXIS_COLOR: u32 = 0xE04040;
+const Y_AXIS_COLOR: u32 = 0x40C040;
+const Z_AXIS_COLOR: u32 = 0x4060E0;
+// Muestras por segmento cuando un extremo queda detrás de la cámara, y máximo en general
+const BEHIND_CAMERA_SAMPLES: usize = 1000;
+const MAX_SAMPLES: usize = 4000;
+
+// Guías en el mundo: cuadrícula en el plano XZ (y = 0) y ejes de colores en el origen
+#[derive(Debug, Clone, Copy)]
+pub struct Guides {
+    pub cell_size: f32,
+    // Número de celdas a cada lado del origen
+    pub extent: i32,
+}
+
+impl Default for Guides {
+    fn default() -> Self {
+        Guides { cell_size: 1.0, extent: 10 }
+    }
+}
+
+impl Guides {
+    // Dibuja las guías sobre el cuadro; los tramos tapados por objetos no se dibujan
+    pub fn draw(&self, framebuffer: &mut Framebuffer, camera: &Camera, objects: &[Object]) {
+        let half = self.extent as f32 * self.cell_size;
+
+        for i in -self.extent..=self.extent {
+            if i == 0 {
+                continue;
+            }
+            let offset = i as f32 * self.cell_size;
+            let segments = [
+                (Vec3::new(offset, 0.0, -half), Vec3::new(offset, 0.0, half)),
+                (Vec3::new(-half, 0.0, offset), Vec3::new(half, 0.0, offset)),
+            ];
+            for (start, end) in segments {
+                draw_segment(framebuffer, camera, objects, &start, &end, GRID_COLOR);
+            }
+        }
+
+        // En las líneas centrales de la cuadrícula van los ejes X y Z; Y sube desde el origen
+        draw_segment(framebuffer, camera, objects, &Vec3::new(-half, 0.0, 0.0), &Vec3::new(half, 0.0, 0.0), X_AXIS_COLOR);
+        draw_segment(framebuffer, camera, objects, &Vec3::new(0.0, 0.0, -half), &Vec3::new(0.0, 0.0, half), Z_AXIS_COLOR);
+        draw_segment(framebuffer, camera, objects, &Vec3::zeros(), &Vec3::new(0.0, half, 0.0), Y_AXIS_COLOR);
+    }
+}
+
+fn is_visible(camera: &Camera, objects: &[Object], point: &Vec3) -> bool {
+    let to_point = point - camera.position;
+    let distance = to_point.norm();
+    let ray = Ray::with_interval(camera.position, to_point / distance, 0.0, distance * 0.999);
+    !crate::scene_intersect(&ray, objects).is_intersecting
+}
+
+// Recorre el segmento en el mundo y pinta cada muestra visible; así los tramos
+// detrás de la cámara o de los objetos se recortan sin lógica aparte
+fn draw_segment(framebuffer: &mut Framebuffer, camera: &Camera, objects: &[Object], start: &Vec3, end: &Vec3, color: u32) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    let samples = match (camera.project(start, width, height), camera.project(end, width, height)) {
+        (Some((x0, y0)), Some((x1, y1))) => (((x1 - x0).abs().max((y1 - y0).abs()) * 2.0).ceil() as usize).min(MAX_SAMPLES),
+        _ => BEHIND_CAMERA_SAMPLES,
+    };
+
+    framebuffer.set_current_color(color);
+    for i in 0..=samples {
+        let point = start + (end - start) * (i as f32 / samples.max(1) as f32);
+        let Some((x, y)) = camera.project(&point, width, height) else { continue };
+        if x < 0.0 || y < 0.0 || x >= width || y >= height {
+            continue;
+        }
+        if is_visible(camera, objects, &point) {
+            framebuffer.point(x as usize, y as usize);
+        }
+    }
+}
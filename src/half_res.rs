@@ -0,0 +1,144 @@
+// half_res.rs
+
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::frame_graph::FrameContext;
+use crate::framebuffer::Framebuffer;
+use crate::ray::Ray;
+use crate::ray_intersect::Intersect;
+use crate::settings::{IntegratorKind, RenderSettings};
+use crate::{Surface, ambient_occlusion, shade_crystal, shadow_only_factor};
+
+// Diferencia relativa de distancia a la que el peso de un vecino cae a 1/e
+const DEPTH_SIGMA: f32 = 0.05;
+// Exponente del coseno entre normales: más alto separa mejor las caras de una arista
+const NORMAL_POWER: i32 = 8;
+// Por debajo de este peso total ningún vecino se parece al pixel (un borde fino, algo más
+// chico que dos pixeles) y el efecto se calcula ahí a resolución completa
+const MIN_WEIGHT: f32 = 1e-3;
+
+// Lo que se saca del primer impacto de los rayos de cámara para calcularlo a media resolución
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deferred {
+    // Todo el color de un cristal: su reflejo y lo que se ve a través
+    Reflection,
+    // El término ambiente, que se vuelve a sumar ya oscurecido por la oclusión
+    Occlusion,
+}
+
+// Efecto de un pixel de media resolución, con lo que hace falta para compararlo con los
+// pixeles de resolución completa que lo rodean
+struct Sample {
+    effect: Deferred,
+    distance: f32,
+    normal: Vec3,
+    reflection: Color,
+    occlusion: f32,
+}
+
+pub fn is_enabled(settings: &RenderSettings) -> bool {
+    settings.half_res_effects && settings.integrator == IntegratorKind::Whitted && settings.light_samples.is_none()
+}
+
+// Qué efecto se difiere en este impacto. Tiene que coincidir con lo que hace `shade_hit`:
+// desde dentro de un opaco el rayo sigue de largo, y los translúcidos y los espejos parciales
+// (que mezclan el ambiente con el reflejo) se calculan completos
+pub fn deferred(intersect: &Intersect, settings: &RenderSettings) -> Option<Deferred> {
+    let opaque = intersect.material.opacity >= 1.0;
+    if !intersect.is_intersecting || intersect.inside && opaque && !intersect.material.is_crystal {
+        None
+    } else if intersect.material.is_crystal {
+        Some(Deferred::Reflection)
+    } else if opaque && intersect.material.reflectivity <= 0.0 && settings.ao_samples > 0 {
+        Some(Deferred::Occlusion)
+    } else {
+        None
+    }
+}
+
+fn evaluate(ray: &Ray, intersect: &Intersect, effect: Deferred, context: &FrameContext) -> (Color, f32) {
+    let FrameContext { settings, scene, light_grid, .. } = context;
+    match effect {
+        Deferred::Reflection => (shade_crystal(ray, intersect, scene, light_grid, settings, 0), 1.0),
+        Deferred::Occlusion => {
+            // Con la normal de sombreado, igual que a resolución completa
+            let surface = Surface::new(ray, intersect.clone());
+            (Color::black(), ambient_occlusion(&surface.intersect, scene, settings))
+        }
+    }
+}
+
+// Calcula los efectos diferidos en un pixel de cada 2x2, los lleva a resolución completa con
+// un filtro bilateral (pesos bilineales que se anulan entre superficies de distinta
+// distancia o normal, para que un reflejo no se corra a la pared de al lado) y los suma al
+// cuadro que dejó `cast_camera_ray` en `framebuffer.hdr`
+pub fn composite(framebuffer: &mut Framebuffer, context: &FrameContext) {
+    let FrameContext { camera, lights, settings, scene, .. } = context;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+    // Los mismos rayos centrales (y el mismo desplazamiento del TAA) que el trazado
+    let jitter = if settings.taa { framebuffer.taa.jitter() } else { (0.0, 0.0) };
+    let camera_ray =
+        |x: f32, y: f32| camera.lens_ray(x + jitter.0, y + jitter.1, width as f32, height as f32, (0.0, 0.0));
+
+    let samples: Vec<Option<Sample>> = (0..half_width * half_height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = ((index % half_width) * 2, (index / half_width) * 2);
+            let ray = camera_ray(x as f32 + 0.5, y as f32 + 0.5);
+            let intersect = scene.intersect(&ray);
+            let effect = deferred(&intersect, settings)?;
+            let (reflection, occlusion) = evaluate(&ray, &intersect, effect, context);
+            Some(Sample { effect, distance: intersect.distance, normal: intersect.normal, reflection, occlusion })
+        })
+        .collect();
+
+    framebuffer.hdr.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let ray = camera_ray(x as f32, y as f32);
+            let intersect = scene.intersect(&ray);
+            let Some(effect) = deferred(&intersect, settings) else { continue };
+
+            // El pixel de media resolución i está centrado en el pixel 2i + 0.5
+            let (fx, fy) = ((x as f32 - 0.5) / 2.0, (y as f32 - 0.5) / 2.0);
+            let (x0, y0) = (fx.floor(), fy.floor());
+            let (tx, ty) = (fx - x0, fy - y0);
+            let (mut reflection, mut occlusion, mut total) = (Color::black(), 0.0, 0.0);
+            for (dx, dy, bilinear) in [(0, 0, (1.0 - tx) * (1.0 - ty)), (1, 0, tx * (1.0 - ty)), (0, 1, (1.0 - tx) * ty), (1, 1, tx * ty)] {
+                let (sx, sy) = (x0 as isize + dx, y0 as isize + dy);
+                if sx < 0 || sy < 0 || sx >= half_width as isize || sy >= half_height as isize {
+                    continue;
+                }
+                let Some(sample) = &samples[sy as usize * half_width + sx as usize] else { continue };
+                if sample.effect != effect {
+                    continue;
+                }
+                let difference = (sample.distance - intersect.distance).abs() / (DEPTH_SIGMA * intersect.distance);
+                let weight = bilinear
+                    * (-difference * difference).exp()
+                    * sample.normal.dot(&intersect.normal).max(0.0).powi(NORMAL_POWER);
+                reflection = reflection + sample.reflection * weight;
+                occlusion += sample.occlusion * weight;
+                total += weight;
+            }
+            let (reflection, occlusion) = if total > MIN_WEIGHT {
+                (reflection * (1.0 / total), occlusion / total)
+            } else {
+                evaluate(&ray, &intersect, effect, context)
+            };
+
+            // La niebla ya aclaró el pixel hacia su color; lo que se suma llega atenuado
+            let transmittance = settings.fog.map_or(1.0, |fog| fog.transmittance(intersect.distance));
+            let added = match effect {
+                Deferred::Reflection => reflection,
+                Deferred::Occlusion => {
+                    let surface = Surface::new(&ray, intersect);
+                    surface.ambient() * occlusion * shadow_only_factor(&surface.intersect, lights, scene, settings)
+                }
+            };
+            *pixel = *pixel + added * transmittance;
+        }
+    });
+}
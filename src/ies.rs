@@ -0,0 +1,137 @@
+// ies.rs
+
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use nalgebra_glm::Vec3;
+
+// Perfil fotométrico IES (LM-63). Los ángulos verticales se miden desde el nadir
+// (la luz apunta hacia -Y) y los horizontales alrededor del eje Y, en grados.
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+    vertical_angles: Vec<f32>,
+    horizontal_angles: Vec<f32>,
+    // candela[h][v], ya normalizada a [0, 1] con respecto al máximo
+    candela: Vec<Vec<f32>>,
+}
+
+// Índice inferior y peso de interpolación de `value` dentro de `angles` (ordenados)
+fn bracket(angles: &[f32], value: f32) -> (usize, f32) {
+    if angles.len() < 2 || value <= angles[0] {
+        return (0, 0.0);
+    }
+    let last = angles.len() - 1;
+    if value >= angles[last] {
+        return (last - 1, 1.0);
+    }
+    let i = angles.windows(2).position(|pair| value < pair[1]).unwrap_or(last - 1);
+    let span = angles[i + 1] - angles[i];
+    let weight = if span > 0.0 { (value - angles[i]) / span } else { 0.0 };
+    (i, weight)
+}
+
+// Por sus valores, para la firma de la escena (ver `integrator::scene_signature`)
+impl Hash for IesProfile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let candela = self.candela.iter().flatten();
+        for value in self.vertical_angles.iter().chain(&self.horizontal_angles).chain(candela) {
+            value.to_bits().hash(state);
+        }
+        self.vertical_angles.len().hash(state);
+        self.horizontal_angles.len().hash(state);
+    }
+}
+
+impl IesProfile {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        IesProfile::parse(&source).map_err(|error| format!("{}: {}", path.display(), error).into())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let tilt_start = source.find("TILT=").ok_or("falta la línea TILT=")?;
+        let after_tilt = &source[tilt_start + "TILT=".len()..];
+        let (tilt, data) = after_tilt.split_once('\n').ok_or("archivo IES incompleto")?;
+
+        let mut numbers = data
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f32>());
+        let mut next = || -> Result<f32, Box<dyn Error>> {
+            Ok(numbers.next().ok_or("faltan datos en el archivo IES")??)
+        };
+
+        // TILT=INCLUDE trae la tabla de inclinación antes de los datos; no se usa
+        if tilt.trim() == "INCLUDE" {
+            let _lamp_to_luminaire_geometry = next()?;
+            let pairs = next()? as usize;
+            for _ in 0..pairs * 2 {
+                next()?;
+            }
+        } else if tilt.trim() != "NONE" {
+            return Err("TILT con archivo externo no soportado".into());
+        }
+
+        let _lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()?;
+        if photometric_type != 1.0 {
+            return Err("solo se soporta fotometría tipo C".into());
+        }
+        // units, width, length, height, ballast factor, future use, input watts
+        for _ in 0..7 {
+            next()?;
+        }
+
+        let vertical_angles = (0..vertical_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..horizontal_count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+        let mut candela = (0..horizontal_count)
+            .map(|_| (0..vertical_count).map(|_| Ok(next()? * multiplier)).collect())
+            .collect::<Result<Vec<Vec<f32>>, Box<dyn Error>>>()?;
+
+        let max = candela.iter().flatten().cloned().fold(0.0, f32::max);
+        if vertical_count == 0 || horizontal_count == 0 || max <= 0.0 {
+            return Err("el perfil IES no tiene intensidad".into());
+        }
+        for value in candela.iter_mut().flatten() {
+            *value /= max;
+        }
+
+        Ok(IesProfile { vertical_angles, horizontal_angles, candela })
+    }
+
+    // Factor de intensidad [0, 1] en la dirección que sale de la luz hacia el punto
+    pub fn attenuation(&self, direction: &Vec3) -> f32 {
+        let direction = direction.normalize();
+        let vertical = (-direction.y).clamp(-1.0, 1.0).acos().to_degrees();
+        let mut horizontal = direction.z.atan2(direction.x).to_degrees();
+        if horizontal < 0.0 {
+            horizontal += 360.0;
+        }
+
+        // Simetrías según el último ángulo horizontal del archivo
+        let last_horizontal = *self.horizontal_angles.last().unwrap_or(&0.0);
+        horizontal = if last_horizontal <= 0.0 {
+            0.0
+        } else if last_horizontal <= 90.0 {
+            let h = horizontal % 180.0;
+            if h > 90.0 { 180.0 - h } else { h }
+        } else if last_horizontal <= 180.0 {
+            if horizontal > 180.0 { 360.0 - horizontal } else { horizontal }
+        } else {
+            horizontal
+        };
+
+        let (v, v_weight) = bracket(&self.vertical_angles, vertical);
+        let (h, h_weight) = bracket(&self.horizontal_angles, horizontal);
+        let v_next = (v + 1).min(self.vertical_angles.len() - 1);
+        let h_next = (h + 1).min(self.horizontal_angles.len() - 1);
+
+        let sample = |h: usize| self.candela[h][v] * (1.0 - v_weight) + self.candela[h][v_next] * v_weight;
+        sample(h) * (1.0 - h_weight) + sample(h_next) * h_weight
+    }
+}
@@ -0,0 +1,239 @@
+// inspector.rs
+
+use minifb::{Key, KeyRepeat, Window};
+
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::object::SceneObject;
+use crate::texture::Texture;
+
+const PANEL_WIDTH: usize = 150;
+const ROW_HEIGHT: usize = font::GLYPH_HEIGHT + 3;
+const PANEL_COLOR: u32 = 0x202020;
+const SELECTED_COLOR: u32 = 0x3A5A8C;
+const LABEL_COLOR: u32 = 0xA0A0A0;
+const VALUE_COLOR: u32 = 0xFFFFFF;
+
+// Propiedades editables, en el orden en que se muestran
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    CenterX,
+    CenterY,
+    CenterZ,
+    Size,
+    Specular,
+    AlbedoDiffuse,
+    AlbedoSpecular,
+    Crystal,
+    Opacity,
+}
+
+const FIELDS: [Field; 9] = [
+    Field::CenterX,
+    Field::CenterY,
+    Field::CenterZ,
+    Field::Size,
+    Field::Specular,
+    Field::AlbedoDiffuse,
+    Field::AlbedoSpecular,
+    Field::Crystal,
+    Field::Opacity,
+];
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::CenterX => "x",
+            Field::CenterY => "y",
+            Field::CenterZ => "z",
+            Field::Size => "size",
+            Field::Specular => "specular",
+            Field::AlbedoDiffuse => "albedo dif",
+            Field::AlbedoSpecular => "albedo spec",
+            Field::Crystal => "crystal",
+            Field::Opacity => "opacity",
+        }
+    }
+
+    fn value(self, object: &dyn SceneObject) -> String {
+        let material = object.material();
+        match self {
+            Field::CenterX => format!("{:.2}", object.center().x),
+            Field::CenterY => format!("{:.2}", object.center().y),
+            Field::CenterZ => format!("{:.2}", object.center().z),
+            Field::Size => format!("{:.2}", object.size()),
+            Field::Specular => format!("{:.0}", material.specular),
+            Field::AlbedoDiffuse => format!("{:.2}", material.albedo[0]),
+            Field::AlbedoSpecular => format!("{:.2}", material.albedo[1]),
+            Field::Crystal => material.is_crystal.to_string(),
+            Field::Opacity => format!("{:.2}", material.opacity),
+        }
+    }
+
+    // Cambia el valor `steps` pasos (negativo = hacia abajo); crystal se alterna
+    fn adjust(self, object: &mut dyn SceneObject, steps: f32) {
+        let mut center = object.center();
+        match self {
+            Field::CenterX => center.x += 0.1 * steps,
+            Field::CenterY => center.y += 0.1 * steps,
+            Field::CenterZ => center.z += 0.1 * steps,
+            Field::Size => object.set_size((object.size() + 0.1 * steps).max(0.05)),
+            Field::Specular => {
+                let material = object.material_mut();
+                material.specular = (material.specular + 5.0 * steps).max(1.0);
+            }
+            Field::AlbedoDiffuse => {
+                let material = object.material_mut();
+                material.albedo[0] = (material.albedo[0] + 0.05 * steps).clamp(0.0, 1.0);
+            }
+            Field::AlbedoSpecular => {
+                let material = object.material_mut();
+                material.albedo[1] = (material.albedo[1] + 0.05 * steps).clamp(0.0, 1.0);
+            }
+            Field::Crystal => {
+                let material = object.material_mut();
+                material.is_crystal = !material.is_crystal;
+            }
+            Field::Opacity => {
+                let material = object.material_mut();
+                material.opacity = (material.opacity + 0.05 * steps).clamp(0.0, 1.0);
+            }
+        }
+        object.set_center(center);
+    }
+
+    // Comienzo del comando de consola que escribe esta propiedad
+    fn command(self, index: usize) -> String {
+        match self {
+            Field::CenterX | Field::CenterY | Field::CenterZ => format!("set object.{}.center ", index),
+            Field::Size => format!("set object.{}.size ", index),
+            Field::Specular => format!("set material.{}.specular ", index),
+            Field::AlbedoDiffuse | Field::AlbedoSpecular => format!("set material.{}.albedo ", index),
+            Field::Crystal => format!("set material.{}.crystal ", index),
+            Field::Opacity => format!("set material.{}.opacity ", index),
+        }
+    }
+}
+
+fn material_name(object: &dyn SceneObject) -> String {
+    let material = object.material();
+    material
+        .name
+        .clone()
+        .or_else(|| {
+            material
+                .texture_path
+                .as_ref()
+                .and_then(|path| std::path::Path::new(path).file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "sin nombre".to_string())
+}
+
+fn flags(object: &dyn SceneObject) -> String {
+    let material = object.material();
+    let mut flags = Vec::new();
+    if material.is_crystal {
+        flags.push("cristal");
+    }
+    if material.opacity < 1.0 {
+        flags.push("translúcido");
+    }
+    if material.reflectivity > 0.0 {
+        flags.push("espejo");
+    }
+    if material.pbr.is_some() {
+        flags.push("pbr");
+    }
+    match material.texture {
+        Some(Texture::Image(_)) => flags.push("textura"),
+        Some(Texture::Procedural(_)) => flags.push("patrón"),
+        None => {}
+    }
+    if material.projection.is_some() {
+        flags.push("proyección");
+    }
+    if material.faces.is_some() {
+        flags.push("caras");
+    }
+    if material.height_map.is_some() {
+        flags.push("relieve");
+    }
+    if material.normal_map.is_some() {
+        flags.push("normales");
+    }
+    if flags.is_empty() { "-".to_string() } else { flags.join(" ") }
+}
+
+// Panel con las propiedades del objeto seleccionado. PageUp/PageDown eligen la
+// propiedad, izquierda/derecha la ajustan (con Shift, de a 10 pasos) y Enter abre
+// la consola con el comando para escribir el valor a mano
+#[derive(Default)]
+pub struct Inspector {
+    pub open: bool,
+    field: usize,
+}
+
+impl Inspector {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    // Aplica el teclado de este cuadro al objeto; devuelve el comando a completar
+    // en la consola si se presionó Enter
+    pub fn update(&mut self, window: &Window, object: &mut dyn SceneObject, index: usize) -> Option<String> {
+        if window.is_key_pressed(Key::PageDown, KeyRepeat::Yes) {
+            self.field = (self.field + 1) % FIELDS.len();
+        }
+        if window.is_key_pressed(Key::PageUp, KeyRepeat::Yes) {
+            self.field = (self.field + FIELDS.len() - 1) % FIELDS.len();
+        }
+
+        let field = FIELDS[self.field];
+        let scale = if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) { 10.0 } else { 1.0 };
+        if window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+            field.adjust(object, scale);
+        }
+        if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+            field.adjust(object, -scale);
+        }
+
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            return Some(field.command(index));
+        }
+        None
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, selected: Option<(usize, &dyn SceneObject)>) {
+        let x = framebuffer.width.saturating_sub(PANEL_WIDTH);
+        let Some((index, object)) = selected else {
+            font::draw_label(framebuffer, x, 0, "sin objeto seleccionado");
+            return;
+        };
+
+        let mut lines = vec![
+            (format!("object.{} {}", index, object.kind()), String::new(), false),
+            ("material".to_string(), material_name(object), false),
+            ("flags".to_string(), flags(object), false),
+        ];
+        lines.extend(
+            FIELDS
+                .iter()
+                .enumerate()
+                .map(|(i, field)| (field.label().to_string(), field.value(object), i == self.field)),
+        );
+
+        font::fill_rect(framebuffer, x, 0, PANEL_WIDTH, lines.len() * ROW_HEIGHT + 4, PANEL_COLOR);
+        for (row, (label, value, selected)) in lines.iter().enumerate() {
+            let y = 2 + row * ROW_HEIGHT;
+            if *selected {
+                font::fill_rect(framebuffer, x, y, PANEL_WIDTH, ROW_HEIGHT, SELECTED_COLOR);
+            }
+            font::draw_text(framebuffer, x + 3, y + 1, label, LABEL_COLOR);
+            let value_x = x + PANEL_WIDTH - 3 - font::text_width(value).min(PANEL_WIDTH - 6);
+            let max_chars = (PANEL_WIDTH - 6) / font::GLYPH_ADVANCE;
+            let value: String = value.chars().take(max_chars).collect();
+            font::draw_text(framebuffer, value_x, y + 1, &value, VALUE_COLOR);
+        }
+    }
+}
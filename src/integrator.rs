@@ -0,0 +1,434 @@
+// integrator.rs
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::depth_heatmap::DepthHeatmap;
+use crate::frame_graph::FrameContext;
+use crate::framebuffer::Framebuffer;
+use crate::light::{AreaShape, Light, LightKind};
+use crate::material::Material;
+use crate::object::Object;
+use crate::pathtrace::{PathTracer, cosine_sample};
+use crate::ray::Ray;
+use crate::restir::PixelRng;
+use crate::settings::{IntegratorKind, RenderSettings};
+use crate::texture::Texture;
+use crate::watchdog;
+use crate::{Surface, cast_camera_ray, sample_offset, secondary_ray};
+
+// Alcance de los rayos de la oclusión ambiental
+pub const AO_DISTANCE: f32 = 1.0;
+
+// Cómo se calcula el color de un rayo de cámara. `render` se encarga de los pixeles, las
+// muestras y el paralelismo, así que un algoritmo de iluminación nuevo solo implementa esto
+pub trait Integrator: Sync {
+    // Color lineal (0-255) que llega a la cámara por `ray`
+    fn radiance(&self, ray: &Ray, context: &FrameContext, rng: &mut PixelRng) -> Color;
+
+    // Los que tienen ruido promedian los cuadros mientras la cámara y la escena no cambien
+    fn accumulates(&self) -> bool {
+        false
+    }
+}
+
+// Luz directa, sombras, ambiente (con oclusión si hay `ao_samples`) y reflejos de espejo
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn radiance(&self, ray: &Ray, context: &FrameContext, _rng: &mut PixelRng) -> Color {
+        cast_camera_ray(ray, context.scene, context.light_grid, context.settings)
+    }
+}
+
+// Solo oclusión ambiental: blanco donde nada tapa el hemisferio hasta AO_DISTANCE, más
+// oscuro en rincones y contactos. Sirve para revisar la geometría sin luces ni materiales
+pub struct AmbientOcclusion;
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: &Ray, context: &FrameContext, rng: &mut PixelRng) -> Color {
+        let intersect = context.scene.intersect(ray);
+        if !intersect.is_intersecting {
+            return Color::new(255.0, 255.0, 255.0);
+        }
+        let direction = cosine_sample(&intersect.normal, rng);
+        let probe = secondary_ray(&intersect, &direction, AO_DISTANCE, context.settings);
+        if context.scene.any_hit(&probe).is_intersecting { Color::black() } else { Color::new(255.0, 255.0, 255.0) }
+    }
+
+    fn accumulates(&self) -> bool {
+        true
+    }
+}
+
+// Normal de sombreado (con el mapa de normales) como color: cada eje de -1..1 a 0..255.
+// El cielo queda negro
+pub struct NormalDebug;
+
+impl Integrator for NormalDebug {
+    fn radiance(&self, ray: &Ray, context: &FrameContext, _rng: &mut PixelRng) -> Color {
+        let intersect = context.scene.intersect(ray);
+        if !intersect.is_intersecting {
+            return Color::black();
+        }
+        let normal = Surface::new(ray, intersect).intersect.normal;
+        let [r, g, b] = [normal.x, normal.y, normal.z].map(|value| (value * 0.5 + 0.5) * 255.0);
+        Color::from_srgb(r, g, b)
+    }
+}
+
+// El integrador elegido en los ajustes, listo para este cuadro
+pub fn create(kind: IntegratorKind) -> Box<dyn Integrator> {
+    match kind {
+        IntegratorKind::Whitted => Box::new(Whitted),
+        IntegratorKind::PathTracing => Box::new(PathTracer),
+        IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusion),
+        IntegratorKind::Normals => Box::new(NormalDebug),
+        IntegratorKind::Depth => Box::new(DepthHeatmap),
+    }
+}
+
+// Suma de los cuadros trazados desde el último cambio de cámara o de escena
+#[derive(Default)]
+pub struct Accumulation {
+    sum: Vec<Color>,
+    pub frames: u32,
+    // Muestras por pixel sumadas hasta ahora; `samples` puede cambiar entre cuadros (tecla K)
+    // sin reiniciar lo acumulado
+    samples: u32,
+    signature: u64,
+}
+
+// Resumen de lo que, si cambia, invalida lo acumulado
+fn signature(objects: &[Object], camera: &Camera, lights: &[Light], settings: &RenderSettings) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    scene_signature(objects, lights, settings).hash(&mut hasher);
+    let mut add = |values: &[f32]| values.iter().for_each(|value| value.to_bits().hash(&mut hasher));
+    add(&[camera.position.x, camera.position.y, camera.position.z, camera.center.x, camera.center.y, camera.center.z]);
+    add(&[camera.up.x, camera.up.y, camera.up.z, camera.fov, camera.aperture, camera.focus_distance.unwrap_or(-1.0)]);
+    hasher.finish()
+}
+
+// La parte de `signature` que no depende de la cámara. Luces, materiales y ajustes se
+// desarman sin `..`: un campo nuevo no compila hasta decidir aquí si cambia la imagen
+pub fn scene_signature(objects: &[Object], lights: &[Light], settings: &RenderSettings) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    settings.integrator.hash(&mut hasher);
+    let mut textures = Vec::with_capacity(objects.len());
+    let mut profiles = Vec::with_capacity(lights.len());
+    let mut add = |values: &[f32]| values.iter().for_each(|value| value.to_bits().hash(&mut hasher));
+    for object in objects {
+        let center = object.center();
+        add(&[center.x, center.y, center.z, object.size()]);
+        // Girar un nodo deja igual el centro y el tamaño de sus hijos, pero no su caja ni su giro
+        if let Some(bounds) = object.bounds() {
+            add(&[bounds.min.x, bounds.min.y, bounds.min.z, bounds.max.x, bounds.max.y, bounds.max.z]);
+        }
+        add(object.linear().as_slice());
+        let material = object.material();
+        let Material {
+            name: _,
+            diffuse,
+            specular,
+            albedo,
+            pbr,
+            texture: _,
+            texture_path: _,
+            projection: _,
+            faces: _,
+            height_map: _,
+            height_scale,
+            normal_map: _,
+            is_crystal,
+            ior,
+            reflectivity,
+            opacity,
+            variation,
+            tint,
+            emissive,
+            max_depth,
+        } = material;
+        add(&[diffuse.r, diffuse.g, diffuse.b, *specular, *opacity, *variation, *height_scale]);
+        add(&[albedo[0], albedo[1], tint.r, tint.g, tint.b]);
+        add(&[*is_crystal as u8 as f32, *ior, emissive.r, emissive.g, emissive.b]);
+        add(&[max_depth.map_or(-1.0, |depth| depth as f32), *reflectivity]);
+        // Las imágenes (textura, caras, relieve y normales) cuentan por su dirección
+        textures.push(texture_identity(material));
+        match pbr {
+            Some(pbr) => add(&[1.0, pbr.metallic, pbr.roughness]),
+            None => add(&[0.0]),
+        }
+    }
+    for light in lights {
+        let Light { position, kind, color, intensity, casts_shadows, shadow_only, profile, attenuation, area, links } = light;
+        add(&[position.x, position.y, position.z, *intensity, *attenuation]);
+        add(&[color.r, color.g, color.b, *shadow_only as u8 as f32, *casts_shadows as u8 as f32]);
+        match *kind {
+            LightKind::Point => add(&[0.0]),
+            LightKind::Directional { direction } => add(&[1.0, direction.x, direction.y, direction.z]),
+            LightKind::Spot { direction, inner, outer } => add(&[2.0, direction.x, direction.y, direction.z, inner, outer]),
+        }
+        match *area {
+            Some(AreaShape::Sphere { radius }) => add(&[1.0, radius]),
+            Some(AreaShape::Rectangle { width, depth }) => add(&[2.0, width, depth]),
+            None => add(&[0.0]),
+        }
+        let include = links.include.iter().flatten();
+        add(&include.chain(&links.exclude).map(|&index| index as f32).collect::<Vec<_>>());
+        add(&[links.include.is_some() as u8 as f32, links.exclude.len() as f32]);
+        profiles.push(profile.as_ref());
+    }
+    let RenderSettings {
+        shadow_bias,
+        sky,
+        background,
+        portals,
+        fog,
+        light_samples,
+        max_depth,
+        shadow_samples,
+        ao_samples,
+        integrator: _,
+        // Lo que cambia cuántas muestras o cómo se muestrea, no a qué converge la imagen
+        samples: _,
+        taa: _,
+        half_res_effects: _,
+        shading_cache: _,
+        kernel: _,
+        watchdog: _,
+        // Lo que se aplica sobre `framebuffer.hdr` después de acumular
+        lens_flare: _,
+        guides: _,
+        dither: _,
+        tone_map: _,
+        output_transform: _,
+    } = settings;
+    add(&[*max_depth as f32, *ao_samples as f32, *shadow_samples as f32, *shadow_bias]);
+    add(&[light_samples.map_or(-1.0, |count| count as f32)]);
+    // El tiempo del cielo solo hace parpadear las estrellas
+    if let Some(sky) = sky {
+        add(&[sky.elevation, sky.azimuth, sky.turbidity]);
+    }
+    if let Some(fog) = fog {
+        add(&[fog.density, fog.color.r, fog.color.g, fog.color.b, fog.light_shafts as u8 as f32]);
+    }
+    for portal in portals.iter() {
+        let [u, v] = portal.edges;
+        add(&[portal.corner.x, portal.corner.y, portal.corner.z, u.x, u.y, u.z, v.x, v.y, v.z]);
+    }
+    textures.push(background_identity(background));
+    textures.hash(&mut hasher);
+    profiles.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Qué textura y proyección usa el material: las imágenes por su dirección (las copias del
+// material las comparten), los patrones y la proyección por sus parámetros
+fn texture_identity(material: &Material) -> String {
+    let texture = match &material.texture {
+        Some(Texture::Image(mipmap)) => format!("{:p}", Arc::as_ptr(mipmap)),
+        Some(Texture::Procedural(pattern)) => format!("{:?}", pattern),
+        None => String::new(),
+    };
+    let faces = material.faces.as_ref().map(Arc::as_ptr);
+    let height_map = material.height_map.as_ref().map(Arc::as_ptr);
+    let normal_map = material.normal_map.as_ref().map(Arc::as_ptr);
+    format!("{} {:?} {:?} {:?} {:?}", texture, faces, height_map, normal_map, material.projection)
+}
+
+// El fondo, con las imágenes por su dirección como en `texture_identity`
+fn background_identity(background: &Background) -> String {
+    match background {
+        Background::Image(image) => format!("Image({:p})", Arc::as_ptr(image)),
+        Background::Cubemap(faces) => format!("Cubemap({:p})", Arc::as_ptr(faces)),
+        other => format!("{:?}", other),
+    }
+}
+
+// Llena `framebuffer.hdr` con el integrador del contexto
+pub fn render(framebuffer: &mut Framebuffer, context: &FrameContext) {
+    if context.integrator.accumulates() {
+        render_progressive(framebuffer, context);
+    } else {
+        render_stratified(framebuffer, context);
+    }
+}
+
+// `samples` muestras por pixel en estratos; el mismo resultado en cada cuadro
+fn render_stratified(framebuffer: &mut Framebuffer, context: &FrameContext) {
+    let FrameContext { camera, settings, scene, integrator, .. } = context;
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    let samples = settings.samples.max(1);
+    let jitter = if settings.taa { framebuffer.taa.jitter() } else { (0.0, 0.0) };
+    framebuffer.hdr
+        .par_chunks_mut(framebuffer.width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut pixel_color = Color::black();
+                let mut invalid = false;
+                let mut rng = PixelRng::new(x, y, 0);
+                for sample in 0..samples {
+                    let (dx, dy) = sample_offset(sample, samples, &mut rng);
+                    let (dx, dy) = (dx + jitter.0, dy + jitter.1);
+                    // Con apertura cada muestra pasa por otro punto de la lente y el desenfoque converge
+                    let lens = if camera.aperture > 0.0 { (rng.next(), rng.next()) } else { (0.0, 0.0) };
+                    // Cada muestra en otro instante del obturador (estratificado) para el desenfoque de movimiento
+                    let time = if samples > 1 { (sample as f32 + rng.next()) / samples as f32 } else { 0.0 };
+                    let ray = camera.lens_ray(x as f32 + dx, y as f32 + dy, width, height, lens).at_time(time);
+                    let color = integrator.radiance(&ray, context, &mut rng);
+                    if settings.watchdog && watchdog::is_invalid(&color) {
+                        watchdog::report(color, x, y, &ray, scene);
+                        invalid = true;
+                    }
+                    pixel_color = pixel_color + color;
+                }
+                // Un NaN asegura que `resolve` lo pinte aunque el promedio haya quedado válido
+                *pixel = if invalid { Color::new(f32::NAN, f32::NAN, f32::NAN) } else { pixel_color * (1.0 / samples as f32) };
+            }
+        });
+}
+
+// `settings.samples` muestras al azar por pixel en cada cuadro, promediadas con los cuadros
+// anteriores mientras la cámara y la escena no cambien
+fn render_progressive(framebuffer: &mut Framebuffer, context: &FrameContext) {
+    let FrameContext { objects, camera, lights, settings, scene, integrator, .. } = context;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let signature = signature(objects, camera, lights, settings);
+    let accumulation = &mut framebuffer.accumulation;
+    if accumulation.signature != signature || accumulation.sum.len() != width * height {
+        accumulation.sum = vec![Color::black(); width * height];
+        accumulation.frames = 0;
+        accumulation.samples = 0;
+        accumulation.signature = signature;
+    }
+    let previous = accumulation.samples;
+    let samples = settings.samples.max(1);
+
+    accumulation.sum.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, sum) in row.iter_mut().enumerate() {
+            let mut rng = PixelRng::new(x, y, previous.wrapping_add(1));
+            for _ in 0..samples {
+                let (dx, dy) = (rng.next() - 0.5, rng.next() - 0.5);
+                let ray = camera
+                    .lens_ray(x as f32 + dx, y as f32 + dy, width as f32, height as f32, (rng.next(), rng.next()))
+                    .at_time(rng.next());
+                let color = integrator.radiance(&ray, context, &mut rng);
+                if settings.watchdog && watchdog::is_invalid(&color) {
+                    watchdog::report(color, x, y, &ray, scene);
+                }
+                *sum = *sum + color;
+            }
+        }
+    });
+    accumulation.frames += 1;
+    accumulation.samples += samples;
+
+    // Un camino inválido deja la suma en NaN o negativa hasta que se reinicia la acumulación
+    let scale = 1.0 / accumulation.samples as f32;
+    let accumulation = &framebuffer.accumulation;
+    framebuffer.hdr.par_iter_mut().zip(&accumulation.sum).for_each(|(pixel, sum)| *pixel = *sum * scale);
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::Vec3;
+
+    use super::*;
+    use crate::console;
+    use crate::cube::Cube;
+    use crate::pbr::Pbr;
+    use crate::scene_graph::SceneGraph;
+
+    fn scene(center: Vec3, material: Material) -> (Vec<Object>, Vec<Light>) {
+        let objects: Vec<Object> = vec![Box::new(Cube { center, size: 1.0, material: Arc::new(material) })];
+        (objects, vec![Light::new(Vec3::new(0.0, 4.0, 4.0), Color::new(255.0, 255.0, 255.0), 1.0)])
+    }
+
+    fn matte() -> Material {
+        Material::new(Color::new(200.0, 40.0, 40.0), 10.0, [0.9, 0.1])
+    }
+
+    #[test]
+    fn same_scene_same_signature() {
+        let settings = RenderSettings::default();
+        let (a, lights_a) = scene(Vec3::zeros(), matte());
+        let (b, lights_b) = scene(Vec3::zeros(), matte());
+        assert_eq!(scene_signature(&a, &lights_a, &settings), scene_signature(&b, &lights_b, &settings));
+    }
+
+    // Cada cambio que se ve en la imagen tiene que invalidar lo acumulado
+    #[test]
+    fn visible_changes_change_the_signature() {
+        let settings = RenderSettings::default();
+        let (objects, lights) = scene(Vec3::zeros(), matte());
+        let original = scene_signature(&objects, &lights, &settings);
+
+        let (moved, _) = scene(Vec3::new(0.0, 0.1, 0.0), matte());
+        let (rough, _) = scene(Vec3::zeros(), Material { pbr: Some(Pbr { metallic: 1.0, roughness: 0.2 }), ..matte() });
+        let (rougher, _) = scene(Vec3::zeros(), Material { pbr: Some(Pbr { metallic: 1.0, roughness: 0.6 }), ..matte() });
+        let (mirror, _) = scene(Vec3::zeros(), Material { reflectivity: 0.5, ..matte() });
+        let (_, mut dimmer) = scene(Vec3::zeros(), matte());
+        dimmer[0].intensity = 0.5;
+        let mut path = settings.clone();
+        path.integrator = IntegratorKind::PathTracing;
+
+        let signatures = [
+            scene_signature(&moved, &lights, &settings),
+            scene_signature(&rough, &lights, &settings),
+            scene_signature(&rougher, &lights, &settings),
+            scene_signature(&mirror, &lights, &settings),
+            scene_signature(&objects, &dimmer, &settings),
+            scene_signature(&objects, &lights, &path),
+        ];
+        for (index, signature) in signatures.iter().enumerate() {
+            assert_ne!(*signature, original, "cambio {}", index);
+        }
+        assert_ne!(signatures[1], signatures[2]);
+    }
+
+    // Todo lo que la consola puede cambiar de una luz se ve en la imagen
+    #[test]
+    fn every_console_light_field_changes_the_signature() {
+        let settings = RenderSettings::default();
+        let (mut objects, lights) = scene(Vec3::zeros(), matte());
+        let original = scene_signature(&objects, &lights, &settings);
+        let commands = [
+            "set light.0.intensity 0.5",
+            "set light.0.position 1 4 4",
+            "set light.0.color 255 0 0",
+            "set light.0.casts_shadows false",
+            "set light.0.shadow_only true",
+            "set light.0.ies assets/ies/downlight.ies",
+            "set light.0.attenuation 0.2",
+            "set light.0.radius 0.5",
+            "set light.0.panel 1 2",
+            "set light.0.direction 0 -1 0",
+            "set light.0.cone 20 30",
+            "set light.0.include 0",
+            "set light.0.exclude 0",
+        ];
+        for command in commands {
+            let (_, mut changed) = scene(Vec3::zeros(), matte());
+            console::execute(command, &mut objects, &mut changed, &mut Vec::new(), &mut SceneGraph::default()).unwrap();
+            assert_ne!(scene_signature(&objects, &changed, &settings), original, "{}", command);
+        }
+    }
+
+    // La cámara cuenta para lo acumulado pero no para la parte de la escena
+    #[test]
+    fn camera_only_changes_the_full_signature() {
+        let settings = RenderSettings::default();
+        let (objects, lights) = scene(Vec3::zeros(), matte());
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+        let mut closer = camera.clone();
+        closer.position.z = 4.0;
+        assert_ne!(signature(&objects, &camera, &lights, &settings), signature(&objects, &closer, &lights, &settings));
+    }
+}
@@ -0,0 +1,230 @@
+// kernels.rs
+
+use std::fmt;
+
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+
+// Implementación de la prueba de cajas del BVH, la parte más repetida de cada rayo. Se elige
+// al arrancar según el procesador, así el mismo binario usa AVX2 o NEON donde los hay y la
+// portable en el resto. Todas dan exactamente el mismo resultado que `Aabb::intersects`:
+// las mismas restas y productos (sin FMA, que redondea distinto justo en las caras, donde
+// están los cubos) y los mismos mínimos y máximos cuando una placa da NaN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    Portable,
+    // Las dos cajas hijas de un nodo en un solo registro de 8 floats
+    Avx2,
+    // Una caja por registro de 4 floats
+    Neon,
+}
+
+impl Kernel {
+    // La mejor que soporta este procesador
+    pub fn detect() -> Self {
+        [Kernel::Avx2, Kernel::Neon].into_iter().find(|kernel| kernel.is_supported()).unwrap_or(Kernel::Portable)
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Kernel::detect()),
+            "portable" => Some(Kernel::Portable),
+            "avx2" => Some(Kernel::Avx2),
+            "neon" => Some(Kernel::Neon),
+            _ => None,
+        }
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Kernel::Portable => true,
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    // Si el rayo atraviesa cada una de las dos cajas (los hijos de un nodo del BVH)
+    pub fn intersects_pair(self, a: &Aabb, b: &Aabb, ray: &Ray) -> (bool, bool) {
+        match self {
+            // SAFETY: solo se elige si `is_supported` lo encontró en este procesador
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => unsafe { avx2::intersects_pair(a, b, ray) },
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => unsafe { neon::intersects_pair(a, b, ray) },
+            _ => (a.intersects(ray), b.intersects(ray)),
+        }
+    }
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Kernel::Portable => "portable",
+            Kernel::Avx2 => "avx2",
+            Kernel::Neon => "neon",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// x, y, z y un cuarto float de relleno que no se mira
+type Lanes = [f32; 4];
+
+// Origen, inversa de la dirección y esquinas mínima y máxima de cada caja
+fn lanes(ray: &Ray, boxes: [&Aabb; 2]) -> (Lanes, Lanes, [Lanes; 2], [Lanes; 2]) {
+    let (origin, direction) = (ray.origin, ray.direction);
+    let origin = [origin.x, origin.y, origin.z, 0.0];
+    let inverse = [1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z, 1.0];
+    let min = boxes.map(|bounds| [bounds.min.x, bounds.min.y, bounds.min.z, 0.0]);
+    let max = boxes.map(|bounds| [bounds.max.x, bounds.max.y, bounds.max.z, 0.0]);
+    (origin, inverse, min, max)
+}
+
+// El final de `Aabb::intersects` con las placas ya calculadas, en el mismo orden
+fn overlaps(near: &[f32], far: &[f32], ray: &Ray) -> bool {
+    let mut t_near = ray.t_min;
+    let mut t_far = ray.t_max;
+    for axis in 0..3 {
+        t_near = t_near.max(near[axis]);
+        t_far = t_far.min(far[axis]);
+    }
+    t_near <= t_far
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use super::{lanes, overlaps};
+    use crate::aabb::Aabb;
+    use crate::ray::Ray;
+
+    // Como `f32::min` y `f32::max`: si `b` es NaN queda `a` (las instrucciones devuelven `b`)
+    #[target_feature(enable = "avx2")]
+    fn min(a: __m256, b: __m256) -> __m256 {
+        _mm256_blendv_ps(_mm256_min_ps(a, b), a, _mm256_cmp_ps::<_CMP_UNORD_Q>(b, b))
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn max(a: __m256, b: __m256) -> __m256 {
+        _mm256_blendv_ps(_mm256_max_ps(a, b), a, _mm256_cmp_ps::<_CMP_UNORD_Q>(b, b))
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn pack(low: [f32; 4], high: [f32; 4]) -> __m256 {
+        _mm256_setr_ps(low[0], low[1], low[2], low[3], high[0], high[1], high[2], high[3])
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn intersects_pair(a: &Aabb, b: &Aabb, ray: &Ray) -> (bool, bool) {
+        let (origin, inverse, [min_a, min_b], [max_a, max_b]) = lanes(ray, [a, b]);
+        let (origin, inverse) = (pack(origin, origin), pack(inverse, inverse));
+        let t1 = _mm256_mul_ps(_mm256_sub_ps(pack(min_a, min_b), origin), inverse);
+        let t2 = _mm256_mul_ps(_mm256_sub_ps(pack(max_a, max_b), origin), inverse);
+        let (mut near, mut far) = ([0.0; 8], [0.0; 8]);
+        // SAFETY: los arreglos tienen lugar para los 8 floats
+        unsafe {
+            _mm256_storeu_ps(near.as_mut_ptr(), min(t1, t2));
+            _mm256_storeu_ps(far.as_mut_ptr(), max(t1, t2));
+        }
+        (overlaps(&near[..4], &far[..4], ray), overlaps(&near[4..], &far[4..], ray))
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::{lanes, overlaps};
+    use crate::aabb::Aabb;
+    use crate::ray::Ray;
+
+    // vminnmq y vmaxnmq ya ignoran el NaN como `f32::min` y `f32::max`
+    #[target_feature(enable = "neon")]
+    fn slabs(origin: float32x4_t, inverse: float32x4_t, min: &[f32; 4], max: &[f32; 4]) -> ([f32; 4], [f32; 4]) {
+        let (mut near, mut far) = ([0.0; 4], [0.0; 4]);
+        // SAFETY: todos los arreglos son de 4 floats
+        unsafe {
+            let t1 = vmulq_f32(vsubq_f32(vld1q_f32(min.as_ptr()), origin), inverse);
+            let t2 = vmulq_f32(vsubq_f32(vld1q_f32(max.as_ptr()), origin), inverse);
+            vst1q_f32(near.as_mut_ptr(), vminnmq_f32(t1, t2));
+            vst1q_f32(far.as_mut_ptr(), vmaxnmq_f32(t1, t2));
+        }
+        (near, far)
+    }
+
+    #[target_feature(enable = "neon")]
+    pub fn intersects_pair(a: &Aabb, b: &Aabb, ray: &Ray) -> (bool, bool) {
+        let (origin, inverse, [min_a, min_b], [max_a, max_b]) = lanes(ray, [a, b]);
+        // SAFETY: arreglos de 4 floats
+        let (origin, inverse) = unsafe { (vld1q_f32(origin.as_ptr()), vld1q_f32(inverse.as_ptr())) };
+        let (near_a, far_a) = slabs(origin, inverse, &min_a, &max_a);
+        let (near_b, far_b) = slabs(origin, inverse, &min_b, &max_b);
+        (overlaps(&near_a, &far_a, ray), overlaps(&near_b, &far_b, ray))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::Vec3;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    // Coordenadas enteras chicas para que los orígenes caigan seguido justo sobre las caras y
+    // las direcciones tengan componentes en cero (placas infinitas y NaN)
+    fn grid_vec3(rng: &mut StdRng, extent: i32) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(-extent..=extent) as f32,
+            rng.gen_range(-extent..=extent) as f32,
+            rng.gen_range(-extent..=extent) as f32,
+        )
+    }
+
+    fn random_box(rng: &mut StdRng) -> Aabb {
+        let (a, b) = (grid_vec3(rng, 3), grid_vec3(rng, 3));
+        Aabb::new(a.inf(&b), a.sup(&b))
+    }
+
+    fn random_ray(rng: &mut StdRng) -> Ray {
+        let (origin, direction) = if rng.gen_bool(0.5) {
+            (grid_vec3(rng, 4), grid_vec3(rng, 1))
+        } else {
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            (grid_vec3(rng, 4) * rng.gen_range(0.5..1.5), direction)
+        };
+        let mut ray = Ray::new(origin, direction);
+        if rng.gen_bool(0.3) {
+            ray.t_max = rng.gen_range(0.0..5.0);
+        }
+        ray
+    }
+
+    // Cada kernel del procesador da lo mismo que `Aabb::intersects`, también en los casos borde
+    #[test]
+    fn simd_kernels_match_portable() {
+        let mut rng = StdRng::seed_from_u64(533);
+        let kernels: Vec<Kernel> = [Kernel::Avx2, Kernel::Neon].into_iter().filter(|kernel| kernel.is_supported()).collect();
+        for _ in 0..100_000 {
+            let (a, b, ray) = (random_box(&mut rng), random_box(&mut rng), random_ray(&mut rng));
+            let expected = (a.intersects(&ray), b.intersects(&ray));
+            assert_eq!(Kernel::Portable.intersects_pair(&a, &b, &ray), expected);
+            for kernel in &kernels {
+                assert_eq!(kernel.intersects_pair(&a, &b, &ray), expected, "{} con {:?}, {:?} y {:?}", kernel, a, b, ray);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(Kernel::parse("portable"), Some(Kernel::Portable));
+        assert_eq!(Kernel::parse("auto"), Some(Kernel::detect()));
+        assert_eq!(Kernel::parse("sse"), None);
+        assert!(Kernel::detect().is_supported());
+    }
+}
@@ -1,18 +1,164 @@
+use std::f32::consts::PI;
+
 use nalgebra_glm::Vec3;
 use crate::color::Color;
+use crate::ies::IesProfile;
+use crate::restir::PixelRng;
+
+// Intensidad por debajo de la cual una luz ya no cambia el color (menos de un nivel de 0-255)
+const MIN_INFLUENCE: f32 = 1.0 / 255.0;
+
+// Forma del emisor de una luz de área, centrada en la posición de la luz
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaShape {
+    Sphere { radius: f32 },
+    // Panel horizontal (en el plano XZ) de `width` en X por `depth` en Z
+    Rectangle { width: f32, depth: f32 },
+}
+
+impl AreaShape {
+    // Punto `index` de `samples` sobre el emisor visto desde `from`, estratificado y con jitter
+    pub fn sample(&self, center: &Vec3, from: &Vec3, index: u32, samples: u32, rng: &mut PixelRng) -> Vec3 {
+        let columns = (samples as f32).sqrt().ceil() as u32;
+        let rows = samples.div_ceil(columns);
+        let u = ((index % columns) as f32 + rng.next()) / columns as f32;
+        let v = ((index / columns) as f32 + rng.next()) / rows as f32;
+        match *self {
+            // La esfera se ve como un disco perpendicular a la dirección hacia el punto
+            AreaShape::Sphere { radius } => {
+                let axis = (center - from).normalize();
+                let helper = if axis.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+                let tangent = axis.cross(&helper).normalize();
+                let bitangent = axis.cross(&tangent);
+                let (distance, angle) = (radius * u.sqrt(), 2.0 * PI * v);
+                center + tangent * (distance * angle.cos()) + bitangent * (distance * angle.sin())
+            }
+            AreaShape::Rectangle { width, depth } => center + Vec3::new((u - 0.5) * width, 0.0, (v - 0.5) * depth),
+        }
+    }
+}
+
+// Cómo sale la luz de `position`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // En todas direcciones
+    Point,
+    // Rayos paralelos hacia `direction`, como el sol: no importa la posición ni la distancia
+    // y las sombras llegan hasta el infinito
+    Directional { direction: Vec3 },
+    // Cono hacia `direction` que se apaga suavemente entre los medios ángulos `inner` y
+    // `outer`, en radianes
+    Spot { direction: Vec3, inner: f32, outer: f32 },
+}
+
+// Enlace de luz: qué objetos (por índice en la escena) ilumina la luz y le hacen sombra
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LightLinks {
+    // Solo estos; None = todos
+    pub include: Option<Vec<usize>>,
+    // Nunca estos, aunque estén en `include`
+    pub exclude: Vec<usize>,
+}
+
+impl LightLinks {
+    pub fn is_restricted(&self) -> bool {
+        self.include.is_some() || !self.exclude.is_empty()
+    }
+
+    // Un impacto sin objeto conocido cuenta como enlazado
+    pub fn affects(&self, object: Option<usize>) -> bool {
+        let Some(object) = object else { return true };
+        !self.exclude.contains(&object) && self.include.as_ref().is_none_or(|include| include.contains(&object))
+    }
+}
 
+// Unidades, las mismas en Whitted, ReSTIR y el trazado de caminos: `intensity` es la irradiancia
+// de frente, tal que un difuso blanco de albedo 1 devuelve 255 con intensidad 1 (como bajo un
+// cielo emisivo de 255 que cubre el hemisferio, ver `Emitter`). `color`, en 0-255, tiñe el
+// especular y el difuso de los materiales físicos
 pub struct Light {
     pub position: Vec3,
+    pub kind: LightKind,
     pub color: Color,
     pub intensity: f32,
+    pub casts_shadows: bool,
+    // Luz que no ilumina: solo oscurece donde proyectaría sombra
+    pub shadow_only: bool,
+    // Distribución fotométrica opcional (la luz apunta hacia -Y)
+    pub profile: Option<IesProfile>,
+    // Atenuación cuadrática con la distancia, 1 / (1 + k·d²); 0 = sin atenuación
+    pub attenuation: f32,
+    // Emisor con tamaño para sombras suaves; None = luz puntual de sombras duras
+    pub area: Option<AreaShape>,
+    pub links: LightLinks,
 }
 
 impl Light {
     pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
         Light {
             position,
+            kind: LightKind::Point,
             color,
             intensity,
+            casts_shadows: true,
+            shadow_only: false,
+            profile: None,
+            attenuation: 0.0,
+            area: None,
+            links: LightLinks::default(),
+        }
+    }
+
+    // Distancia a partir de la cual la luz ya no aporta, o None si alcanza a todo
+    pub fn influence_radius(&self) -> Option<f32> {
+        if self.attenuation <= 0.0 || matches!(self.kind, LightKind::Directional { .. }) {
+            return None;
+        }
+        Some(((self.intensity / MIN_INFLUENCE - 1.0).max(0.0) / self.attenuation).sqrt())
+    }
+
+    // Caída con la distancia; una ventana suave la lleva a cero justo en el radio de influencia
+    fn distance_falloff(&self, distance: f32) -> f32 {
+        let Some(radius) = self.influence_radius() else { return 1.0 };
+        if distance >= radius {
+            return 0.0;
+        }
+        let window = 1.0 - (distance / radius).powi(4);
+        window * window / (1.0 + self.attenuation * distance * distance)
+    }
+
+    // Dirección normalizada desde `point` hacia la luz
+    pub fn direction_from(&self, point: &Vec3) -> Vec3 {
+        match self.kind {
+            LightKind::Directional { direction } => -direction.normalize(),
+            _ => (self.position - point).normalize(),
+        }
+    }
+
+    // Distancia desde `point` hasta la luz; infinita en las direccionales
+    pub fn distance_from(&self, point: &Vec3) -> f32 {
+        match self.kind {
+            LightKind::Directional { .. } => f32::INFINITY,
+            _ => (self.position - point).norm(),
+        }
+    }
+
+    // Intensidad en la dirección que va de la luz hacia `point`
+    pub fn intensity_towards(&self, point: &Vec3) -> f32 {
+        let to_point = match self.kind {
+            LightKind::Directional { direction } => direction.normalize(),
+            _ => point - self.position,
+        };
+        let mut intensity = self.intensity * self.distance_falloff(self.distance_from(point));
+        if let LightKind::Spot { direction, inner, outer } = self.kind {
+            let cos = direction.normalize().dot(&to_point.normalize());
+            let (cos_inner, cos_outer) = (inner.min(outer).cos(), outer.cos());
+            let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(1e-6)).clamp(0.0, 1.0);
+            intensity *= t * t * (3.0 - 2.0 * t);
+        }
+        match &self.profile {
+            Some(profile) => intensity * profile.attenuation(&to_point),
+            None => intensity,
         }
     }
 }
@@ -0,0 +1,93 @@
+// light_grid.rs
+
+use std::collections::HashMap;
+
+use nalgebra_glm::Vec3;
+
+use crate::emitter::{self, Emitter};
+use crate::light::Light;
+use crate::object::Object;
+use crate::shading_cache::ShadingCache;
+
+// Celdas que puede ocupar una luz antes de tratarla como de alcance infinito
+const MAX_CELLS_PER_LIGHT: usize = 4096;
+
+type Cell = (i32, i32, i32);
+
+// Cuadrícula uniforme sobre las esferas de influencia de las luces: para un punto
+// solo se revisan las luces de su celda, más las de alcance infinito. Lleva también los
+// objetos emisivos, que iluminan como luces en todos los integradores
+pub struct LightGrid<'a> {
+    pub lights: &'a [Light],
+    pub emitters: Vec<Emitter>,
+    // Visibilidad guardada de los cuadros anteriores, con --shading-cache
+    pub shading_cache: Option<&'a ShadingCache>,
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<usize>>,
+    unbounded: Vec<usize>,
+}
+
+impl<'a> LightGrid<'a> {
+    pub fn build(lights: &'a [Light], objects: &[Object]) -> Self {
+        let radii: Vec<Option<f32>> = lights.iter().map(|light| light.influence_radius()).collect();
+        let finite: Vec<f32> = radii.iter().flatten().copied().collect();
+        // Celdas del tamaño del radio promedio: cada luz ocupa unas pocas
+        let cell_size = if finite.is_empty() { 1.0 } else { (finite.iter().sum::<f32>() / finite.len() as f32).max(1e-3) };
+
+        let mut grid =
+            LightGrid {
+            lights,
+            emitters: emitter::collect(objects),
+            shading_cache: None,
+            cell_size,
+            cells: HashMap::new(),
+            unbounded: Vec::new(),
+        };
+        for (index, radius) in radii.into_iter().enumerate() {
+            let Some(radius) = radius else {
+                grid.unbounded.push(index);
+                continue;
+            };
+            let position = lights[index].position;
+            let min = grid.cell(&(position - Vec3::new(radius, radius, radius)));
+            let max = grid.cell(&(position + Vec3::new(radius, radius, radius)));
+            let count = ((max.0 - min.0 + 1) * (max.1 - min.1 + 1) * (max.2 - min.2 + 1)) as usize;
+            if count > MAX_CELLS_PER_LIGHT {
+                grid.unbounded.push(index);
+                continue;
+            }
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    for z in min.2..=max.2 {
+                        grid.cells.entry((x, y, z)).or_default().push(index);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    fn cell(&self, point: &Vec3) -> Cell {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+            (point.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    // Luces cuya esfera de influencia contiene al punto
+    pub fn near<'b>(&'b self, point: &'b Vec3) -> impl Iterator<Item = &'a Light> + 'b {
+        let lights = self.lights;
+        self.near_indices(point).map(move |index| &lights[index])
+    }
+
+    // Lo mismo, como índices en `lights`
+    pub fn near_indices<'b>(&'b self, point: &'b Vec3) -> impl Iterator<Item = usize> + 'b {
+        let lights = self.lights;
+        let cell = self.cells.get(&self.cell(point)).map(|indices| indices.as_slice()).unwrap_or(&[]);
+        self.unbounded.iter().copied().chain(cell.iter().copied().filter(move |&index| {
+            let light = &lights[index];
+            light.influence_radius().is_none_or(|radius| (light.position - point).norm_squared() < radius * radius)
+        }))
+    }
+}
@@ -0,0 +1,93 @@
+// magnifier.rs
+
+use crate::font;
+use crate::framebuffer::Framebuffer;
+
+// Pixeles a cada lado del central y lado en pantalla de cada pixel ampliado
+const RADIUS: i32 = 5;
+const ZOOM: usize = 8;
+// Separación entre el cursor y el recuadro
+const CURSOR_GAP: usize = 16;
+
+// Lupa: los pixeles alrededor del cursor ampliados, con el valor del central en 8 bits y en
+// el color lineal antes del mapeo de tonos, para ver diferencias de uno o dos pixeles
+#[derive(Default)]
+pub struct Magnifier {
+    pub active: bool,
+}
+
+impl Magnifier {
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+    }
+
+    // Se dibuja antes que los demás paneles, para ampliar solo el render
+    pub fn draw(&self, framebuffer: &mut Framebuffer, cursor: Option<(f32, f32)>) {
+        let Some((x, y)) = cursor else { return };
+        let (cx, cy) = (x as i32, y as i32);
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        if cx < 0 || cy < 0 || cx as usize >= width || cy as usize >= height {
+            return;
+        }
+        let side = (2 * RADIUS as usize + 1) * ZOOM;
+        let center = cy as usize * width + cx as usize;
+        let (color, hdr) = (framebuffer.buffer[center], framebuffer.hdr.get(center).copied());
+        let label = match hdr {
+            Some(hdr) => format!(
+                "{} {}  ({}, {}, {})  lineal ({:.1}, {:.1}, {:.1})",
+                cx,
+                cy,
+                (color >> 16) & 0xFF,
+                (color >> 8) & 0xFF,
+                color & 0xFF,
+                hdr.r,
+                hdr.g,
+                hdr.b
+            ),
+            None => format!("{} {}  ({}, {}, {})", cx, cy, (color >> 16) & 0xFF, (color >> 8) & 0xFF, color & 0xFF),
+        };
+
+        // Abajo a la derecha del cursor, o del otro lado si no entra
+        let box_height = side + font::GLYPH_HEIGHT + 6;
+        let left = if x as usize + CURSOR_GAP + side <= width {
+            x as usize + CURSOR_GAP
+        } else {
+            (x as usize).saturating_sub(CURSOR_GAP + side)
+        };
+        let top = if y as usize + CURSOR_GAP + box_height <= height {
+            y as usize + CURSOR_GAP
+        } else {
+            (y as usize).saturating_sub(CURSOR_GAP + box_height)
+        };
+
+        let pixels: Vec<u32> = (-RADIUS..=RADIUS)
+            .flat_map(|dy| (-RADIUS..=RADIUS).map(move |dx| (cx + dx, cy + dy)))
+            .map(|(px, py)| {
+                // Fuera de la imagen, gris oscuro
+                if px < 0 || py < 0 || px as usize >= width || py as usize >= height {
+                    0x101010
+                } else {
+                    framebuffer.buffer[py as usize * width + px as usize]
+                }
+            })
+            .collect();
+        font::fill_rect(framebuffer, left.saturating_sub(1), top.saturating_sub(1), side + 2, side + 2, 0xFFFFFF);
+        let columns = 2 * RADIUS as usize + 1;
+        for (index, pixel) in pixels.into_iter().enumerate() {
+            font::fill_rect(framebuffer, left + index % columns * ZOOM, top + index / columns * ZOOM, ZOOM, ZOOM, pixel);
+        }
+
+        // Marco del pixel central, en el color opuesto para que se vea sobre cualquiera
+        framebuffer.set_current_color(!color & 0xFFFFFF);
+        let (frame_x, frame_y) = (left + RADIUS as usize * ZOOM, top + RADIUS as usize * ZOOM);
+        for offset in 0..ZOOM {
+            framebuffer.point(frame_x + offset, frame_y);
+            framebuffer.point(frame_x + offset, frame_y + ZOOM - 1);
+            framebuffer.point(frame_x, frame_y + offset);
+            framebuffer.point(frame_x + ZOOM - 1, frame_y + offset);
+        }
+
+        let label_x = left.min(width.saturating_sub(font::text_width(&label) + 3));
+        font::draw_label(framebuffer, label_x, top + side + 2, &label);
+    }
+}
@@ -1,199 +1,1244 @@
-use image::GenericImageView;
-use nalgebra_glm::{Vec3, normalize};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use nalgebra_glm::Vec3;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
 
-use rayon::prelude::*; 
+use base64::Engine;
 
 mod framebuffer;
+mod ray;
 mod ray_intersect;
 mod cube; 
 mod color;
 mod camera;
 mod light;
 mod material;
+mod terminal;
+mod export;
+mod obj_export;
+mod console;
+mod remote;
+mod font;
+mod contact_sheet;
+mod material_preview;
+mod motion;
+mod exposure;
+mod gizmo;
+mod settings;
+mod parallax;
+mod group;
+mod stress;
+mod aabb;
+mod ies;
+mod sky;
+mod flare;
+mod measure;
+mod guides;
+mod placement;
+mod object;
+mod sphere;
+mod outliner;
+mod plane;
+mod inspector;
+mod eyedropper;
+mod bvh;
+mod restir;
+mod light_grid;
+mod scene;
+mod dither;
+mod color_management;
+mod block;
+mod pathtrace;
+mod sweep;
+mod watchdog;
+mod moving;
+mod background;
+mod fog;
+mod tonemap;
+mod bracket;
+mod taa;
+mod normal_map;
+mod texture;
+mod frame_graph;
+mod integrator;
+mod half_res;
+mod procedural;
+mod pbr;
+mod demos;
+mod emitter;
+mod shading_cache;
+mod projection;
+mod magnifier;
+mod transformed;
+mod kernels;
+mod scene_graph;
+mod buffer_pool;
+mod depth_heatmap;
+mod focus;
+mod batch;
+mod scene_diff;
+mod scene_error;
+mod portal;
+mod mesh;
+mod gltf;
 
 use framebuffer::Framebuffer;
 use cube::Cube;
+use plane::Plane;
+use object::Object;
 use color::Color;
-use ray_intersect::{Intersect, RayIntersect};
+use ray::Ray;
+use ray_intersect::Intersect;
 use camera::Camera;
-use light::Light;
+use light::{Light, LightKind};
 use material::Material;
+use export::OutputFormat;
+use console::Console;
+use settings::{IntegratorKind, MAX_PASS_THROUGH, MAX_RAY_DEPTH, RenderSettings};
+use group::Group;
+use scene_graph::SceneGraph;
+use aabb::Aabb;
+use bvh::Bvh;
+use light_grid::LightGrid;
+use remote::{RemoteServer, Request, Response};
+use frame_graph::{FrameContext, FrameGraph};
+use half_res::Deferred;
+use integrator::AO_DISTANCE;
+use restir::PixelRng;
 
-const SHADOW_BIAS: f32 = 1e-4;
-const MAX_RAY_DEPTH: u32 = 1;
+// Límite del factor por el que se multiplica el t_min en impactos rasantes
+const MAX_BIAS_SCALE: f32 = 100.0;
+// Grados que gira el sol por cuadro con las flechas
+const SUN_STEP_DEGREES: f32 = 2.0;
+// Luz que queda por debajo de la cual un rayo de sombra deja de buscar más objetos
+const MIN_TRANSMITTANCE: f32 = 1.0 / 255.0;
+// Tinte de una luz que llega sin sombra
+const UNSHADOWED: Color = Color { r: 255.0, g: 255.0, b: 255.0 };
 
 fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
+// Rayo secundario que sale del punto de impacto en `direction`. En lugar de desplazar el origen,
+// se descartan los impactos con t < t_min; t_min crece con la magnitud de las coordenadas y la
+// distancia del impacto (precisión de f32) y con lo rasante del rayo respecto a la superficie.
+fn secondary_ray(intersect: &Intersect, direction: &Vec3, t_max: f32, settings: &RenderSettings) -> Ray {
+    let magnitude = intersect.point.abs().max().max(intersect.distance);
+    let cos_angle = intersect.normal.dot(direction).abs().max(1.0 / MAX_BIAS_SCALE);
+    let t_min = settings.shadow_bias * (1.0 + magnitude) / cos_angle;
+
+    Ray::with_interval(intersect.point, *direction, t_min, t_max).at_time(intersect.time)
+}
+
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
-    objects: &[Cube],
-) -> f32 {
-    let light_dir = (light.position - intersect.point).normalize();
-    let light_distance = (light.position - intersect.point).magnitude();
-
-    let offset_normal = intersect.normal * SHADOW_BIAS;
-    let shadow_ray_origin = if light_dir.dot(&intersect.normal) < 0.0 {
-        intersect.point - offset_normal
-    } else {
-        intersect.point + offset_normal
+    scene: &Bvh,
+    settings: &RenderSettings,
+) -> Color {
+    let point = intersect.point;
+    let Some(area) = light.area.as_ref().filter(|_| !matches!(light.kind, LightKind::Directional { .. })) else {
+        return shadow_towards(intersect, light, &light.direction_from(&point), light.distance_from(&point), scene, settings);
     };
 
-    let mut shadow_intensity = 0.0;
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance > 1e-3 && shadow_intersect.distance < light_distance {
-            let distance_ratio = shadow_intersect.distance / light_distance;
-            shadow_intensity = 1.0 - distance_ratio.powf(2.0).min(1.0);
+    // Luz de área: promedio de lo que llega desde puntos del emisor. La penumbra sale sola:
+    // cuanto más grande la luz o más cerca el punto, más cambia qué parte del emisor se ve
+    let samples = settings.shadow_samples.max(1);
+    let bits = intersect.point.map(|value| value.to_bits());
+    let mut rng = restir::PixelRng::new(bits.x as usize ^ bits.z.rotate_left(16) as usize, bits.y as usize, samples);
+    let total = (0..samples)
+        .map(|index| {
+            let to_target = area.sample(&light.position, &point, index, samples, &mut rng) - point;
+            shadow_towards(intersect, light, &to_target.normalize(), to_target.norm(), scene, settings)
+        })
+        .fold(Color::black(), |total, filter| total + filter);
+    total * (1.0 / samples as f32)
+}
+
+// Luz que llega desde un punto de la luz a `light_distance` en `light_dir`, como tinte: blanco
+// si se ve, negro si lo tapa un objeto opaco cercano (siempre con las direccionales, que están
+// en el infinito)
+fn shadow_towards(
+    intersect: &Intersect,
+    light: &Light,
+    light_dir: &Vec3,
+    light_distance: f32,
+    scene: &Bvh,
+    settings: &RenderSettings,
+) -> Color {
+    let mut shadow_ray = secondary_ray(intersect, light_dir, light_distance, settings);
+    if !scene.any_hit(&shadow_ray).is_intersecting {
+        return UNSHADOWED;
+    }
+
+    // Se recorren los impactos en orden y cada uno deja pasar su parte de la luz: un opaco
+    // según lo lejos que esté del punto (cerca de la luz la sombra se desvanece), una vez por
+    // objeto, y cada cara de un translúcido según su opacidad. Un cristal deja pasar lo que no
+    // refleja en cada cara y tiñe con su color al entrar, como la refracción; la luz no se
+    // desvía, así que no hay cáusticas. Varios objetos oscurecen más que uno y el resultado no
+    // depende del orden de la escena. Los no enlazados no cuentan
+    let opaque_shadow = |distance: f32| 1.0 - (distance / light_distance).powf(2.0).min(1.0);
+    let mut transmittance = 1.0;
+    let mut tint = UNSHADOWED;
+    while transmittance > MIN_TRANSMITTANCE {
+        let hit = scene.intersect(&shadow_ray);
+        if !hit.is_intersecting {
             break;
         }
+        if light.links.affects(hit.object) {
+            if hit.material.is_crystal {
+                let ior = hit.material.ior;
+                transmittance *= 1.0 - ((ior - 1.0) / (ior + 1.0)).powi(2);
+                if !hit.inside {
+                    tint = tint.tinted(hit.material.diffuse);
+                }
+            } else if hit.material.opacity < 1.0 {
+                transmittance *= 1.0 - hit.material.opacity;
+            } else if !hit.inside {
+                transmittance *= 1.0 - opaque_shadow(hit.distance);
+            }
+        }
+        shadow_ray.t_min = hit.distance + settings.shadow_bias * (1.0 + hit.distance);
     }
-    shadow_intensity
+    tint * transmittance
 }
 
-pub fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
-    objects: &[Cube],
-    lights: &[Light],
-    depth: u32,
-) -> Color {
-    if depth > MAX_RAY_DEPTH {
-        return Color::new(135.0, 206.0, 235.0);
+// Color de los rayos que no golpean ningún objeto
+fn background(direction: &Vec3, settings: &RenderSettings) -> Color {
+    match &settings.sky {
+        Some(sky) => sky.color(direction),
+        None => settings.background.color(direction),
     }
+}
 
+// Intersección más cercana del rayo contra todos los objetos; cada impacto acorta el t_max
+pub fn scene_intersect(ray: &Ray, objects: &[Object]) -> Intersect {
     let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+    let mut ray = *ray;
 
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
+    for (index, object) in objects.iter().enumerate() {
+        let mut i = object.ray_intersect(&ray);
+        if i.is_intersecting {
+            i.object = Some(index);
+            ray.t_max = i.distance;
             intersect = i;
         }
     }
 
-    if !intersect.is_intersecting {
-        return Color::new(135.0, 206.0, 235.0);
-    }
+    intersect
+}
+
+// Punto golpeado listo para iluminar: UV desplazada por el relieve y color base ya muestreado
+pub struct Surface {
+    pub intersect: Intersect,
+    pub view_dir: Vec3,
+    uv: Option<(f32, f32)>,
+    relief_depth: f32,
+    pub base_color: Color,
+}
+
+impl Surface {
+    pub fn new(ray: &Ray, mut intersect: Intersect) -> Self {
+        let view_dir = (ray.origin - intersect.point).normalize();
+        if let Some(projection) = intersect.material.projection {
+            projection.apply(&mut intersect);
+        }
+
+        // Relieve: desplazar la UV según el mapa de alturas antes de muestrear la textura
+        let mut uv = intersect.uv;
+        let mut relief_depth = 0.0;
+        if let Some(height_map) = &intersect.material.height_map
+            && let Some(face_uv) = uv
+        {
+            let (offset_uv, depth) =
+                parallax::occlusion_uv(height_map, face_uv, &view_dir, &intersect, intersect.material.height_scale);
+            uv = Some(offset_uv);
+            relief_depth = depth;
+        }
 
-    let view_dir = (ray_origin - intersect.point).normalize();
-    let is_crystal = intersect.material.is_crystal;
+        // Mapa de normales: inclina la normal de sombreado, con la UV ya desplazada por el relieve
+        let mapped_normal = match (&intersect.material.normal_map, uv) {
+            (Some(normal_map), Some(face_uv)) if intersect.tangent != Vec3::zeros() => {
+                Some(normal_map::perturb(normal_map, face_uv, &intersect))
+            }
+            _ => None,
+        };
+        if let Some(normal) = mapped_normal {
+            intersect.normal = normal;
+        }
 
-    // Color base: textura si existe
-    let mut base_color = intersect.material.diffuse;
-    if let Some(tex) = &intersect.material.texture {
-        if let Some((u, v)) = intersect.uv {
-            let (tw, th) = tex.dimensions();
-            let tx = ((u.clamp(0.0, 1.0)) * (tw - 1) as f32) as u32;
-            let ty = ((v.clamp(0.0, 1.0)) * (th - 1) as f32) as u32;
-            let pixel = tex.get_pixel(tx, ty);
-            base_color = Color::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        // Color base: textura si existe, filtrada según cuánto de ella cubre el pixel. De frente
+        // el pixel mide spread·distancia; de costado se estira (hasta un límite, o todo se borronea)
+        let mut base_color = intersect.material.diffuse;
+        let facing = intersect.normal.dot(&view_dir).abs().max(0.2);
+        let pixel_size = ray.spread * intersect.distance / facing;
+        let footprint = if intersect.uv_scale > 0.0 { pixel_size / intersect.uv_scale } else { 0.0 };
+        // La textura propia de la cara golpeada, si tiene, va derecha vista desde afuera
+        let face_texture = uv.zip(intersect.face).zip(intersect.material.faces.as_ref()).and_then(|((face_uv, face), faces)| {
+            faces[face.index()].as_ref().map(|texture| texture.sample(face.upright_uv(face_uv), footprint))
+        });
+        if let Some(color) = face_texture {
+            base_color = color;
+        } else if let Some(texture) = &intersect.material.texture
+            // Un poco hacia adentro, para que una cara justo en el borde de una casilla del
+            // tablero no salte entre las dos
+            && let Some(color) = texture.sample(uv, &(intersect.point - intersect.normal * 1e-3), footprint, pixel_size)
+        {
+            base_color = color;
         }
+        base_color = base_color.tinted(intersect.material.tint);
+        base_color = intersect.material.varied(base_color, &intersect.instance);
+
+        Surface { intersect, view_dir, uv, relief_depth, base_color }
+    }
+
+    pub fn ambient(&self) -> Color {
+        self.base_color * 0.3
     }
 
-    let ambient = base_color * 0.3;
-    let mut lighting_color = ambient;
+    // Fracción de la luz que devuelve en un rebote difuso
+    pub fn diffuse_albedo(&self) -> Color {
+        match &self.intersect.material.pbr {
+            Some(pbr) => pbr.diffuse(self.base_color),
+            None => self.base_color * self.intersect.material.albedo[0],
+        }
+    }
 
-    for light in lights {
-        let light_dir = (light.position - intersect.point).normalize();
+    // Difuso + especular de una luz sin contar las sombras de otros objetos
+    // (sí el auto-sombreado del relieve)
+    pub fn light_contribution(&self, light: &Light) -> Color {
+        let intersect = &self.intersect;
+        if !light.links.affects(intersect.object) {
+            return Color::black();
+        }
+        let light_dir = light.direction_from(&intersect.point);
         let reflect_dir = reflect(&-light_dir, &intersect.normal);
 
-        let shadow_intensity = cast_shadow(&intersect, light, objects);
-        let lit_amount = 1.0 - shadow_intensity;
+        let mut lit_amount = 1.0;
+        if let Some(height_map) = &intersect.material.height_map
+            && let Some(relief_uv) = self.uv
+        {
+            lit_amount *= parallax::self_shadow(
+                height_map,
+                relief_uv,
+                self.relief_depth,
+                &light_dir,
+                intersect,
+                intersect.material.height_scale,
+            );
+        }
 
-        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
-        let diffuse = base_color * intersect.material.albedo[0] * diffuse_intensity * light.intensity * lit_amount;
+        let light_intensity = light.intensity_towards(&intersect.point);
+        if let Some(pbr) = &intersect.material.pbr {
+            let shaded = pbr.shade(self.base_color, intersect.material.ior, light.color, &intersect.normal, &self.view_dir, &light_dir);
+            return shaded * (light_intensity * lit_amount);
+        }
+        let diffuse_intensity = intersect.normal.dot(&light_dir).clamp(0.0, 1.0);
+        let diffuse = self.base_color * intersect.material.albedo[0] * diffuse_intensity * light_intensity * lit_amount;
 
-        let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-        let specular = light.color * intersect.material.albedo[1] * specular_intensity * light.intensity * lit_amount;
+        let specular_intensity = self.view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
+        let specular = light.color * intersect.material.albedo[1] * specular_intensity * light_intensity * lit_amount;
 
-        lighting_color = lighting_color + diffuse + specular;
+        diffuse + specular
     }
+}
 
-    if is_crystal {
-        let reflect_dir = reflect(ray_direction, &intersect.normal).normalize();
-        let reflect_origin = if reflect_dir.dot(&intersect.normal) < 0.0 {
-            intersect.point - intersect.normal * SHADOW_BIAS
-        } else {
-            intersect.point + intersect.normal * SHADOW_BIAS
-        };
-        return cast_ray(&reflect_origin, &reflect_dir, objects, lights, depth + 1);
+// Lo que dejan pasar los objetos entre el punto y la luz, como tinte (ver `shadow_towards`)
+fn light_filter(intersect: &Intersect, light: &Light, scene: &Bvh, settings: &RenderSettings) -> Color {
+    if light.casts_shadows && light.links.affects(intersect.object) {
+        cast_shadow(intersect, light, scene, settings)
+    } else {
+        UNSHADOWED
     }
+}
 
-    lighting_color
+// Fracción del hemisferio sobre el punto que nada tapa hasta AO_DISTANCE, con `ao_samples`
+// rayos. Las direcciones salen de la posición, así que el ruido no parpadea entre cuadros
+fn ambient_occlusion(intersect: &Intersect, scene: &Bvh, settings: &RenderSettings) -> f32 {
+    let point = intersect.point;
+    let seed = point.y.to_bits() ^ point.z.to_bits().rotate_left(16);
+    let mut rng = PixelRng::new(point.x.to_bits() as usize, seed as usize, 0);
+    let open = (0..settings.ao_samples)
+        .filter(|_| {
+            let direction = pathtrace::cosine_sample(&intersect.normal, &mut rng);
+            !scene.any_hit(&secondary_ray(intersect, &direction, AO_DISTANCE, settings)).is_intersecting
+        })
+        .count();
+    open as f32 / settings.ao_samples as f32
 }
 
-// Render usando threads con rayon
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, lights: &[Light]) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+// Oscurecimiento acumulado de las luces que solo proyectan sombra
+fn shadow_only_factor(intersect: &Intersect, lights: &[Light], scene: &Bvh, settings: &RenderSettings) -> f32 {
+    lights
+        .iter()
+        .filter(|light| light.shadow_only)
+        .map(|light| {
+            let filter = light_filter(intersect, light, scene, settings);
+            let shadow = 1.0 - (filter.r + filter.g + filter.b) / (3.0 * 255.0);
+            1.0 - shadow * light.intensity.clamp(0.0, 1.0)
+        })
+        .product()
+}
 
-    framebuffer.buffer
-        .par_chunks_mut(framebuffer.width as usize)
-        .enumerate()
-        .for_each(|(y, row)| {
-            for x in 0..framebuffer.width {
-                let screen_x = (2.0 * x as f32) / width - 1.0;
-                let screen_y = -(2.0 * y as f32) / height + 1.0;
+// Rebotes hasta los que se refleja en una superficie: los de su material o los de los ajustes
+fn depth_limit(material: &Material, settings: &RenderSettings) -> u32 {
+    material.max_depth.unwrap_or(settings.max_depth)
+}
 
-                let screen_x = screen_x * aspect_ratio * perspective_scale;
-                let screen_y = screen_y * perspective_scale;
+// Pasado el límite de rebotes de la superficie el reflejo ve solo el fondo
+fn reflect_crystal(ray: &Ray, intersect: &Intersect, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings, depth: u32) -> Color {
+    let reflect_dir = reflect(&ray.direction, &intersect.normal).normalize();
+    let reflect_ray = secondary_ray(intersect, &reflect_dir, f32::INFINITY, settings);
+    if depth >= depth_limit(&intersect.material, settings) {
+        let sky = background(&reflect_ray.direction, settings);
+        return apply_fog(sky, &reflect_ray, f32::INFINITY, scene, lights, settings, depth + 1);
+    }
+    cast_ray(&reflect_ray, scene, lights, settings, depth + 1)
+}
 
-                let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-                let rotated_direction = camera.basis_change(&ray_direction);
-                let pixel_color = cast_ray(&camera.position, &rotated_direction, objects, lights, 0);
-                row[x] = pixel_color.to_hex();
-            }
-        });
+// Dirección refractada por la ley de Snell con `eta` = n1 / n2 (la normal mira hacia el
+// rayo); None si hay reflexión total interna
+fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_incident = -incident.dot(normal).clamp(-1.0, 1.0);
+    let sin2_transmitted = eta * eta * (1.0 - cos_incident * cos_incident);
+    if sin2_transmitted > 1.0 {
+        return None;
+    }
+    Some((incident * eta + normal * (eta * cos_incident - (1.0 - sin2_transmitted).sqrt())).normalize())
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 400;  
-    let framebuffer_height = 300;
-    let frame_delay = Duration::from_millis(16);
+// Fracción reflejada y dirección refractada en el borde de un cristal (aproximación de
+// Schlick, con el coseno del lado más denso). Con reflexión total interna se refleja todo
+fn crystal_fresnel(ray: &Ray, intersect: &Intersect) -> (f32, Option<Vec3>) {
+    let ior = intersect.material.ior;
+    let (n1, n2) = if intersect.inside { (ior, 1.0) } else { (1.0, ior) };
+    let direction = ray.direction.normalize();
+    let Some(refracted) = refract(&direction, &intersect.normal, n1 / n2) else { return (1.0, None) };
+    let cos = if n1 > n2 { -refracted.dot(&intersect.normal) } else { -direction.dot(&intersect.normal) };
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    (r0 + (1.0 - r0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5), Some(refracted))
+}
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Cubito",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    ).unwrap();
+// Reflejo y refracción mezclados por Fresnel. Lo que entra se tiñe con el color del cristal;
+// atravesarlo no cuenta como rebote (como en los translúcidos), así que un cristal se ve
+// transparente aun con --max-depth 1
+fn shade_crystal(ray: &Ray, intersect: &Intersect, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings, depth: u32) -> Color {
+    let (reflectance, refracted) = crystal_fresnel(ray, intersect);
+    let reflected = if reflectance > 0.0 { reflect_crystal(ray, intersect, scene, lights, settings, depth) } else { Color::black() };
+    let Some(refracted) = refracted else { return reflected };
+    let transmitted_ray = secondary_ray(intersect, &refracted, f32::INFINITY, settings).passed_through(ray);
+    let mut transmitted = cast_ray(&transmitted_ray, scene, lights, settings, depth);
+    if !intersect.inside {
+        transmitted = transmitted.tinted(intersect.material.diffuse);
+    }
+    reflected * reflectance + transmitted * (1.0 - reflectance)
+}
 
-    window.set_position(500, 500);
-    window.update();
+pub fn cast_ray(
+    ray: &Ray,
+    scene: &Bvh,
+    lights: &LightGrid,
+    settings: &RenderSettings,
+    depth: u32,
+) -> Color {
+    trace_ray(ray, scene, lights, settings, depth, false)
+}
+
+// Rayo de cámara. Con los efectos a media resolución deja afuera del primer impacto lo que
+// después agrega `half_res::composite`
+pub fn cast_camera_ray(ray: &Ray, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings) -> Color {
+    trace_ray(ray, scene, lights, settings, 0, half_res::is_enabled(settings))
+}
 
+fn trace_ray(
+    ray: &Ray,
+    scene: &Bvh,
+    lights: &LightGrid,
+    settings: &RenderSettings,
+    depth: u32,
+    deferring: bool,
+) -> Color {
+    // Los reflejos ya cortan en `depth_limit`; esto solo frena un rebote que no lo consulte.
+    // Atravesar no es un rebote, así que tiene su propio tope
+    if depth > MAX_RAY_DEPTH || ray.passes > MAX_PASS_THROUGH {
+        return apply_fog(background(&ray.direction, settings), ray, f32::INFINITY, scene, lights, settings, depth);
+    }
+    depth_heatmap::record(depth);
+    let intersect = scene.intersect(ray);
+    let distance = if intersect.is_intersecting { intersect.distance } else { f32::INFINITY };
+    let color = shade_hit(ray, intersect, scene, lights, settings, depth, deferring);
+    apply_fog(color, ray, distance, scene, lights, settings, depth)
+}
+
+// Niebla sobre el tramo del rayo hasta `distance`; los haces de luz, que son caros,
+// solo en los rayos de cámara
+pub fn apply_fog(
+    color: Color,
+    ray: &Ray,
+    distance: f32,
+    scene: &Bvh,
+    lights: &LightGrid,
+    settings: &RenderSettings,
+    depth: u32,
+) -> Color {
+    match settings.fog {
+        Some(fog) if depth == 0 => fog.apply(color, ray, distance, scene, lights.lights),
+        Some(fog) => fog::Fog { light_shafts: false, ..fog }.apply(color, ray, distance, scene, lights.lights),
+        None => color,
+    }
+}
+
+// Color del punto que golpeó el rayo (o del fondo), sin la niebla del camino
+fn shade_hit(
+    ray: &Ray,
+    intersect: Intersect,
+    scene: &Bvh,
+    lights: &LightGrid,
+    settings: &RenderSettings,
+    depth: u32,
+    deferring: bool,
+) -> Color {
+    if !intersect.is_intersecting {
+        return background(&ray.direction, settings);
+    }
+    let deferred = if deferring { half_res::deferred(&intersect, settings) } else { None };
+    // Dentro de un objeto opaco (la cámara metida en un cubo): sus caras interiores no se
+    // dibujan, el rayo sigue desde la salida. En los translúcidos y los cristales sí se ve la
+    // cara de adentro
+    if intersect.inside && intersect.material.opacity >= 1.0 && !intersect.material.is_crystal {
+        let continued = secondary_ray(&intersect, &ray.direction, ray.t_max, settings).passed_through(ray);
+        return cast_ray(&continued, scene, lights, settings, depth);
+    }
+    if deferred == Some(Deferred::Reflection) {
+        return Color::black();
+    }
+    if intersect.material.is_crystal {
+        return shade_crystal(ray, &intersect, scene, lights, settings, depth);
+    }
+
+    let surface = Surface::new(ray, intersect);
+    let visibility = shading_cache::visibility(&surface.intersect, scene, lights, settings, deferred.is_none());
+    let mut lighting_color = match deferred {
+        Some(_) => Color::black(),
+        None if settings.ao_samples > 0 => surface.ambient() * visibility.occlusion,
+        None => surface.ambient(),
+    };
+    // Solo las luces cuyo radio de influencia alcanza al punto
+    for &(index, filter) in visibility.lit() {
+        lighting_color = lighting_color + surface.light_contribution(&lights.lights[index]).tinted(filter);
+    }
+
+    // Reflejo del entorno en los materiales físicos pulidos, pesado por Fresnel. Los rugosos
+    // reflejan un entorno borroso que Whitted no calcula, así que se apaga con la rugosidad
+    let mut reflection = Color::black();
+    if let Some(pbr) = &surface.intersect.material.pbr
+        && pbr.roughness < 1.0
+        && depth < depth_limit(&surface.intersect.material, settings)
+    {
+        let cos = surface.intersect.normal.dot(&surface.view_dir);
+        let fresnel = pbr.fresnel(surface.base_color, surface.intersect.material.ior, cos) * (1.0 - pbr.roughness).powi(2);
+        reflection = reflect_crystal(ray, &surface.intersect, scene, lights, settings, depth).tinted(fresnel);
+    }
+
+    // Lo que emite la superficie no depende de las luces ni de las sombras; la luz de los
+    // objetos emisivos tampoco, como en el trazado de caminos
+    let mut color = lighting_color * visibility.shadow_only
+        + visibility.emitted.tinted(surface.diffuse_albedo())
+        + reflection
+        + surface.intersect.material.emissive;
+    // Espejo parcial: la mezcla reemplaza parte del sombreado, así que un espejo perfecto
+    // (1) se ve igual que un cristal
+    let reflectivity = surface.intersect.material.reflectivity;
+    if reflectivity > 0.0 {
+        let mirrored = reflect_crystal(ray, &surface.intersect, scene, lights, settings, depth);
+        color = color.blend(mirrored, reflectivity);
+    }
+    let opacity = surface.intersect.material.opacity;
+    if opacity >= 1.0 {
+        return color;
+    }
+
+    // Superficie translúcida: el rayo sigue de largo y lo de atrás se ve filtrado por su color.
+    // Atravesar no cuenta como rebote; el rayo siempre avanza y termina al salir de la escena o
+    // al llegar a MAX_PASS_THROUGH superficies
+    let continued = secondary_ray(&surface.intersect, &ray.direction, ray.t_max, settings).passed_through(ray);
+    let behind = cast_ray(&continued, scene, lights, settings, depth);
+    color * opacity + behind.tinted(surface.base_color) * (1.0 - opacity)
+}
+
+// Desplazamiento dentro del pixel de la muestra `index`: el pixel se divide en una
+// cuadrícula de estratos y cada muestra cae al azar dentro del suyo (jitter estratificado).
+// Con una sola muestra no hay desplazamiento
+fn sample_offset(index: u32, samples: u32, rng: &mut restir::PixelRng) -> (f32, f32) {
+    if samples <= 1 {
+        return (0.0, 0.0);
+    }
+    let columns = (samples as f32).sqrt().ceil() as u32;
+    let rows = samples.div_ceil(columns);
+    let dx = ((index % columns) as f32 + rng.next()) / columns as f32;
+    let dy = ((index / columns) as f32 + rng.next()) / rows as f32;
+    (dx - 0.5, dy - 0.5)
+}
+
+// Render usando threads con rayon, con las pasadas estándar del cuadro
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    objects: &[Object],
+    camera: &Camera,
+    lights: &[Light],
+    settings: &RenderSettings,
+) {
+    render_with(&mut FrameGraph::default(), framebuffer, objects, camera, lights, settings);
+}
+
+// Igual que `render` pero con las pasadas (y los tiempos) de `graph`
+pub fn render_with(
+    graph: &mut FrameGraph,
+    framebuffer: &mut Framebuffer,
+    objects: &[Object],
+    camera: &Camera,
+    lights: &[Light],
+    settings: &RenderSettings,
+) {
+    buffer_pool::next_frame();
+    // Se reconstruye en cada cuadro: la escena se puede editar entre cuadros
+    let scene = Bvh::build(objects).with_kernel(settings.kernel);
+    // El caché sale del framebuffer mientras dura el cuadro: las pasadas lo toman prestado
+    // mutable
+    let mut shading_cache = std::mem::take(&mut framebuffer.shading_cache);
+    let mut light_grid = LightGrid::build(lights, objects);
+    if settings.shading_cache {
+        shading_cache.prepare(integrator::scene_signature(objects, lights, settings));
+        light_grid.shading_cache = Some(&shading_cache);
+    }
+    let integrator = integrator::create(settings.integrator);
+    let context = FrameContext {
+        objects,
+        camera,
+        lights,
+        settings,
+        scene: &scene,
+        light_grid: &light_grid,
+        integrator: integrator.as_ref(),
+    };
+    graph.execute(framebuffer, &context);
+    drop(light_grid);
+    framebuffer.shading_cache = shading_cache;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_remote_request(
+    request: Request,
+    camera: &mut Camera,
+    objects: &mut Vec<Object>,
+    lights: &mut [Light],
+    groups: &mut Vec<Group>,
+    graph: &mut SceneGraph,
+    framebuffer: &mut Framebuffer,
+    settings: &RenderSettings,
+) -> Response {
+    match request {
+        Request::Orbit { yaw, pitch } => {
+            camera.orbit(yaw, pitch);
+            Response::ok("cámara actualizada")
+        }
+        Request::Camera { position, center } => {
+            camera.position = Vec3::from(position);
+            camera.center = Vec3::from(center);
+            Response::ok("cámara actualizada")
+        }
+        Request::Set { command } => match console::execute(&command, objects, lights, groups, graph) {
+            Ok(message) => Response::ok(message),
+            Err(error) => Response::error(error),
+        },
+        Request::Render => {
+            let start = Instant::now();
+            render(framebuffer, objects, camera, lights, settings);
+            Response::ok(format!("render en {:.1} ms", start.elapsed().as_secs_f32() * 1000.0))
+        }
+        Request::Frame => match export::encode_png(framebuffer) {
+            Ok(bytes) => Response {
+                ok: true,
+                png: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+                ..Response::default()
+            },
+            Err(error) => Response::error(error.to_string()),
+        },
+    }
+}
+
+// Gira el sol: el del cielo físico, con su luz y la de la luna, o sin cielo la primera luz
+// direccional de la escena. La elevación se queda entre -90° y 90°
+fn move_sun(settings: &mut RenderSettings, lights: &mut [Light], sun_light_index: Option<usize>, elevation: f32, azimuth: f32) {
+    if let (Some(sky), Some(index)) = (settings.sky, sun_light_index) {
+        let sky = sky.turned(elevation, azimuth);
+        lights[index] = sky.sun_light();
+        lights[index + 1] = sky.moon_light();
+        settings.sky = Some(sky);
+        println!("sol: elevación {:.0}°, azimut {:.0}°", sky.elevation, sky.azimuth);
+        return;
+    }
+    let Some(light) = lights.iter_mut().find(|light| matches!(light.kind, LightKind::Directional { .. })) else { return };
+    let LightKind::Directional { direction } = light.kind else { return };
+    // El sol está del lado contrario al que van sus rayos; mismos ángulos que el cielo
+    let towards_sun = -direction.normalize();
+    let current_elevation = towards_sun.y.clamp(-1.0, 1.0).asin().to_degrees();
+    let current_azimuth = towards_sun.z.atan2(towards_sun.x).to_degrees();
+    let (elevation, azimuth) = ((current_elevation + elevation).clamp(-90.0, 90.0), (current_azimuth + azimuth).rem_euclid(360.0));
+    let (elevation_rad, azimuth_rad) = (elevation.to_radians(), azimuth.to_radians());
+    let towards_sun = Vec3::new(elevation_rad.cos() * azimuth_rad.cos(), elevation_rad.sin(), elevation_rad.cos() * azimuth_rad.sin());
+    light.kind = LightKind::Directional { direction: -towards_sun };
+    println!("sol: elevación {:.0}°, azimut {:.0}°", elevation, azimuth);
+}
+
+// Posición del ratón en pixeles del framebuffer; la ventana es más grande que el framebuffer
+fn mouse_position(window: &Window, framebuffer: &Framebuffer) -> Option<(f32, f32)> {
+    let (mouse_x, mouse_y) = window.get_mouse_pos(MouseMode::Discard)?;
+    let (window_width, window_height) = window.get_size();
+    Some((
+        mouse_x * framebuffer.width as f32 / window_width as f32,
+        mouse_y * framebuffer.height as f32 / window_height as f32,
+    ))
+}
+
+fn frame_scene(camera: &mut Camera, objects: &[Object], aspect_ratio: f32) {
+    if let Some(bounds) = Aabb::enclosing(objects.iter().filter_map(|object| object.bounds())) {
+        camera.frame_bounds(&bounds, aspect_ratio);
+    }
+}
+
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+}
+
+// Todos los valores de una opción que se puede repetir, como --set
+fn arg_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+        .collect()
+}
+
+// La descripción de la escena en `args[index]`, para los subcomandos que no la renderizan
+fn scene_argument(args: &[String], index: usize, usage: &str) -> scene::SceneDescription {
+    let path = std::path::Path::new(args.get(index).filter(|arg| !arg.starts_with("--")).expect(usage));
+    scene::parse_scene(path).unwrap_or_else(|error| {
+        eprintln!("No se pudo cargar la escena {}: {}", path.display(), error);
+        std::process::exit(1);
+    })
+}
+
+// Escena incluida: el cubo texturizado con dos luces y, opcionalmente, un piso de tablero
+fn default_scene(floor: bool) -> scene::Scene {
     // Material texturizado
     let textured_cube = Material::with_texture(
         "./assets/flores.webp",
         80.0,
         [0.7, 0.3],
-    );
+    )
+    .unwrap_or_else(|error| panic!("No se pudo cargar la textura: {}", error));
 
-    let light1 = Light::new(Vec3::new(0.0, 0.0, 5.0), Color::new(255.0, 200.0, 100.0), 1.0);
-    let light2 = Light::new(Vec3::new(3.0, 4.0, 6.0), Color::new(100.0, 200.0, 255.0), 0.8);
-    let lights = [light1, light2];
+    let light1 = Light::new(Vec3::new(0.0, 0.0, 5.0), Color::from_srgb(255.0, 200.0, 100.0), 1.0);
+    let light2 = Light::new(Vec3::new(3.0, 4.0, 6.0), Color::from_srgb(100.0, 200.0, 255.0), 0.8);
 
-    let objects = [
-        Cube { center: Vec3::new(0.0, 0.0, 0.0), size: 1.5, material: textured_cube },
+    let mut objects: Vec<Object> = vec![
+        Box::new(Cube { center: Vec3::new(0.0, 0.0, 0.0), size: 1.5, material: textured_cube.into() }),
     ];
+    // Piso de tablero justo debajo del cubo
+    if floor {
+        let checker = Material::checker(Color::from_srgb(200.0, 200.0, 200.0), Color::from_srgb(90.0, 90.0, 90.0), 30.0, [0.9, 0.1]);
+        objects.push(Box::new(Plane::new(Vec3::new(0.0, -0.75, 0.0), Vec3::new(0.0, 1.0, 0.0), 2.0, checker)));
+    }
 
-    let mut camera = Camera::new(
+    scene::Scene { objects, lights: vec![light1, light2], camera: None, background: None, max_depth: None, graph: SceneGraph::default(), portals: Vec::new(), groups: Vec::new() }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // `Cube batch escenas/*.ron --out renders/`: solo lanza un render por escena, así que va
+    // antes de leer las demás opciones (que pueden ser distintas para cada escena)
+    if args.get(1).is_some_and(|arg| arg == "batch") {
+        let batch = batch::Batch::parse(&args[2..]).unwrap_or_else(|error| {
+            eprintln!("batch: {}", error);
+            std::process::exit(1);
+        });
+        match batch::run(&batch) {
+            Ok(0) => println!("{} escenas -> {}", batch.scenes.len(), batch.out.display()),
+            Ok(failed) => {
+                eprintln!("batch: fallaron {} de {} escenas", failed, batch.scenes.len());
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("batch: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    // `Cube diff antes.ron después.ron` y `Cube merge base.ron nuestra.ron suya.ron [--output mezcla.ron]`
+    match args.get(1).map(String::as_str) {
+        Some("diff") => {
+            let [old, new] = [2, 3].map(|index| scene_argument(&args, index, "uso: diff <antes.ron> <después.ron>"));
+            for line in scene_diff::diff(&old, &new) {
+                println!("{}", line);
+            }
+            return;
+        }
+        Some("merge") => {
+            let usage = "uso: merge <base.ron> <nuestra.ron> <suya.ron> [--output mezcla.ron]";
+            let [base, ours, theirs] = [2, 3, 4].map(|index| scene_argument(&args, index, usage));
+            let merged = scene_diff::merge(&base, &ours, &theirs).unwrap_or_else(|conflicts| {
+                for conflict in conflicts {
+                    eprintln!("conflicto: {}", conflict);
+                }
+                std::process::exit(1);
+            });
+            let text = ron::ser::to_string_pretty(&merged, ron::ser::PrettyConfig::default()).expect("No se pudo escribir la escena");
+            match arg_value(&args, "--output") {
+                Some(path) => std::fs::write(path, text + "\n").expect("No se pudo guardar la escena"),
+                None => println!("{}", text),
+            }
+            return;
+        }
+        _ => {}
+    }
+    let terminal_mode = args.iter().any(|arg| arg == "--terminal");
+    let output_path = arg_value(&args, "--output").map(std::path::PathBuf::from);
+    let output_format = arg_value(&args, "--format")
+        .map(|name| OutputFormat::from_name(name).expect("Formato de salida desconocido"));
+    let animation_path = arg_value(&args, "--gif").map(std::path::PathBuf::from);
+    // Carpeta para la órbita como secuencia frame_0001.png, frame_0002.png, ...
+    let sequence_dir = arg_value(&args, "--animate").map(std::path::PathBuf::from);
+    let animation_frames: u32 = arg_value(&args, "--frames")
+        .map(|value| value.parse().ok().filter(|&frames| frames >= 1).expect("--frames debe ser un entero mayor que cero"))
+        .unwrap_or(60);
+    let motion_vectors_dir = arg_value(&args, "--motion-vectors").map(std::path::PathBuf::from);
+    let obj_export_path = arg_value(&args, "--export-obj").map(std::path::PathBuf::from);
+    // Horquillado de exposición: con --bracket se renderiza una vez y se guarda a varias
+    // exposiciones en esa carpeta; en la ventana, B hace lo mismo con la vista actual
+    let bracket_dir = arg_value(&args, "--bracket").map(std::path::PathBuf::from);
+    let bracket_stops = arg_value(&args, "--bracket-stops")
+        .map(|text| bracket::parse_stops(text).expect("--bracket-stops debe ser una lista como -2,0,2"))
+        .unwrap_or_else(|| bracket::DEFAULT_STOPS.to_vec());
+    let auto_frame = args.iter().any(|arg| arg == "--auto-frame");
+    let floor = args.iter().any(|arg| arg == "--floor");
+    let scene_path = arg_value(&args, "--scene").map(std::path::PathBuf::from);
+    let auto_exposure_enabled = args.iter().any(|arg| arg == "--auto-exposure");
+    let mut settings = RenderSettings::default();
+    if let Some(bias) = arg_value(&args, "--shadow-bias") {
+        settings.shadow_bias = bias.parse().expect("--shadow-bias debe ser un número");
+    }
+    // Cielo de Preetham: se activa con cualquiera de los parámetros del sol
+    if ["--sun-elevation", "--sun-azimuth", "--turbidity"].iter().any(|flag| args.iter().any(|arg| arg == flag)) {
+        let parse = |flag: &str, default: f32| {
+            arg_value(&args, flag)
+                .map(|value| value.parse().unwrap_or_else(|_| panic!("{} debe ser un número", flag)))
+                .unwrap_or(default)
+        };
+        settings.sky = Some(sky::PreethamSky::new(
+            parse("--sun-elevation", 45.0),
+            parse("--sun-azimuth", 30.0),
+            parse("--turbidity", 3.0),
+        ));
+    }
+    if args.iter().any(|arg| arg == "--lens-flare") {
+        let mut lens_flare = flare::LensFlare::default();
+        if let Some(size) = arg_value(&args, "--flare-size") {
+            lens_flare.size = size.parse().expect("--flare-size debe ser un número");
+        }
+        if let Some(intensity) = arg_value(&args, "--flare-intensity") {
+            lens_flare.intensity = intensity.parse().expect("--flare-intensity debe ser un número");
+        }
+        if let Some(rays) = arg_value(&args, "--flare-rays") {
+            lens_flare.rays = rays.parse().expect("--flare-rays debe ser un entero");
+        }
+        settings.lens_flare = Some(lens_flare);
+    }
+    if let Some(samples) = arg_value(&args, "--samples") {
+        settings.samples = samples.parse().expect("--samples debe ser un entero");
+    }
+    if let Some(depth) = arg_value(&args, "--max-depth") {
+        settings.max_depth = depth.parse::<u32>().expect("--max-depth debe ser un entero").min(MAX_RAY_DEPTH);
+    }
+    if let Some(name) = arg_value(&args, "--dither") {
+        settings.dither = Some(dither::Dither::parse(name).expect("--dither debe ser ordered o noise"));
+    }
+    settings.watchdog = args.iter().any(|arg| arg == "--debug-nan");
+    // Antialiasing temporal en la ventana; T lo activa o desactiva
+    settings.taa = args.iter().any(|arg| arg == "--taa");
+    if let Some(samples) = arg_value(&args, "--ao-samples") {
+        settings.ao_samples = samples.parse().expect("--ao-samples debe ser un entero");
+    }
+    // Reflejos y oclusión a media resolución; H los alterna en la ventana
+    settings.half_res_effects = args.iter().any(|arg| arg == "--half-res");
+    // Sombras y oclusión guardadas por texel: al orbitar una escena quieta solo se resombrea
+    settings.shading_cache = args.iter().any(|arg| arg == "--shading-cache");
+    // --kernel portable para comparar con la versión sin SIMD
+    if let Some(name) = arg_value(&args, "--kernel") {
+        settings.kernel = kernels::Kernel::parse(name)
+            .filter(|kernel| kernel.is_supported())
+            .expect("--kernel debe ser auto, portable o uno que soporte este procesador (avx2, neon)");
+    }
+    // Pasadas del cuadro: --disable-pass NOMBRE (repetible) apaga una y --profile-passes
+    // informa cuánto tardó cada una al exportar; en la ventana, P muestra los del último cuadro
+    let mut frame_graph = FrameGraph::default();
+    for name in arg_values(&args, "--disable-pass") {
+        frame_graph.set_enabled(name, false).unwrap_or_else(|error| panic!("--disable-pass: {}", error));
+    }
+    let profile_passes = args.iter().any(|arg| arg == "--profile-passes");
+    if let Some(density) = arg_value(&args, "--fog") {
+        let mut fog = fog::Fog::new(density.parse().expect("--fog debe ser un número"));
+        if let Some(color) = arg_value(&args, "--fog-color") {
+            fog.color = Color::parse(color).expect("--fog-color debe ser R,G,B");
+        }
+        fog.light_shafts = args.iter().any(|arg| arg == "--light-shafts");
+        settings.fog = Some(fog);
+    }
+    if let Some(name) = arg_value(&args, "--tonemap") {
+        settings.tone_map = Some(tonemap::ToneMap::parse(name).expect("--tonemap debe ser reinhard o aces"));
+    }
+    if let Some(text) = arg_value(&args, "--background") {
+        settings.background = background::Background::parse(text)
+            .expect("--background debe ser R,G,B, R,G,B:R,G,B (horizonte:cenit), una imagen (también .hdr/.exr) o la carpeta de un cubemap");
+    }
+    if let Some(samples) = arg_value(&args, "--shadow-samples") {
+        settings.shadow_samples = samples.parse().expect("--shadow-samples debe ser un entero");
+    }
+    if let Some(name) = arg_value(&args, "--integrator") {
+        settings.integrator = match name {
+            "whitted" => IntegratorKind::Whitted,
+            "path" => IntegratorKind::PathTracing,
+            "ao" => IntegratorKind::AmbientOcclusion,
+            "normals" => IntegratorKind::Normals,
+            "depth" => IntegratorKind::Depth,
+            _ => panic!("--integrator debe ser whitted, path, ao, normals o depth"),
+        };
+    }
+    if let Some(name) = arg_value(&args, "--output-transform") {
+        settings.output_transform = color_management::OutputTransform::parse(name)
+            .expect("--output-transform debe ser srgb, p3 o rec709");
+    }
+    // Etiqueta los PNG exportados con el espacio de color de salida
+    let color_tag = args.iter().any(|arg| arg == "--tag-color-space").then_some(settings.output_transform);
+    if let Some(samples) = arg_value(&args, "--light-samples") {
+        settings.light_samples = Some(samples.parse().expect("--light-samples debe ser un entero"));
+    }
+    // Guías del mundo; X las muestra u oculta en la ventana
+    let mut guides_config = guides::Guides::default();
+    if let Some(cell_size) = arg_value(&args, "--grid-size") {
+        guides_config.cell_size = cell_size.parse().expect("--grid-size debe ser un número");
+    }
+    if args.iter().any(|arg| arg == "--guides") {
+        settings.guides = Some(guides_config);
+    }
+    // Duración en segundos de un ciclo completo de día y noche en la ventana
+    let day_length: Option<f32> = arg_value(&args, "--day-length")
+        .map(|value| value.parse().expect("--day-length debe ser un número"));
+    let listen_address = arg_value(&args, "--listen");
+    let headless = args.iter().any(|arg| arg == "--headless");
+    let contact_sheet_path = match args.get(1).map(|arg| arg.as_str()) {
+        Some("contact-sheet") => Some(std::path::PathBuf::from(
+            args.get(2).expect("uso: contact-sheet <salida.png> [--angles N]"),
+        )),
+        _ => None,
+    };
+    let material_preview_path = match args.get(1).map(|arg| arg.as_str()) {
+        Some("preview-material") => Some(std::path::PathBuf::from(
+            args.get(2).expect("uso: preview-material <material.ron> [--output preview.png]"),
+        )),
+        _ => None,
+    };
+    let sweep_dir = match args.get(1).map(|arg| arg.as_str()) {
+        Some("sweep") => Some(std::path::PathBuf::from(
+            args.get(2).expect("uso: sweep <carpeta> <ruta>=<desde>:<hasta> [--steps N]"),
+        )),
+        _ => None,
+    };
+    let sweep_steps: usize = arg_value(&args, "--steps")
+        .map(|value| value.parse().expect("--steps debe ser un entero"))
+        .unwrap_or(10);
+    let stress_mode = args.get(1).is_some_and(|arg| arg == "stress");
+    let stress_objects: usize = arg_value(&args, "--objects")
+        .map(|value| value.parse().expect("--objects debe ser un entero"))
+        .unwrap_or(1000);
+    let stress_lights: Option<usize> = arg_value(&args, "--lights")
+        .map(|value| value.parse().expect("--lights debe ser un entero"));
+    // Atenuación de las luces de la escena de prueba; con ella se reparten entre los objetos
+    let stress_attenuation: f32 = arg_value(&args, "--light-attenuation")
+        .map(|value| value.parse().expect("--light-attenuation debe ser un número"))
+        .unwrap_or(0.0);
+    // `Cube demo NOMBRE`: una escena de demostración armada en código en lugar de un archivo
+    let demo_name = args.get(1).filter(|arg| *arg == "demo").map(|_| args.get(2).map_or("", String::as_str));
+    // Semilla de las escenas generadas (stress y demo)
+    let stress_seed: u64 = arg_value(&args, "--seed")
+        .map(|value| value.parse().expect("--seed debe ser un entero"))
+        .unwrap_or(42);
+    let contact_sheet_angles: usize = arg_value(&args, "--angles")
+        .map(|value| value.parse().expect("--angles debe ser un entero"))
+        .unwrap_or(8);
+
+    // Resolución del render; la ventana mide al menos 800 de ancho y conserva la proporción
+    let framebuffer_width: usize = arg_value(&args, "--width")
+        .map(|value| value.parse().ok().filter(|&width| width > 0).expect("--width debe ser un entero mayor que cero"))
+        .unwrap_or(400);
+    let framebuffer_height: usize = arg_value(&args, "--height")
+        .map(|value| value.parse().ok().filter(|&height| height > 0).expect("--height debe ser un entero mayor que cero"))
+        .unwrap_or(300);
+    let window_width = framebuffer_width.max(800);
+    let window_height = window_width * framebuffer_height / framebuffer_width;
+    let frame_delay = Duration::from_millis(16);
+
+    // Escena aleatoria para medir rendimiento, sin ventana
+    if stress_mode {
+        let framebuffer = stress::run(stress_objects, stress_lights, stress_attenuation, stress_seed, framebuffer_width, framebuffer_height, &settings);
+        if let Some(path) = &output_path {
+            let format = output_format
+                .or_else(|| OutputFormat::from_path(path))
+                .unwrap_or(OutputFormat::Png);
+            export::save_framebuffer(&framebuffer, path, format, &settings, color_tag).expect("No se pudo guardar la imagen");
+        }
+        return;
+    }
+
+    if let Some(path) = material_preview_path {
+        let material = material::load_material(&path).unwrap_or_else(|error| {
+            eprintln!("No se pudo cargar el material {}: {}", path.display(), error);
+            std::process::exit(1);
+        });
+        let preview = material_preview::render_material_preview(material, framebuffer_width, framebuffer_height, &settings);
+        let output = output_path.unwrap_or_else(|| path.with_extension("png"));
+        let format = output_format
+            .or_else(|| OutputFormat::from_path(&output))
+            .unwrap_or(OutputFormat::Png);
+        export::save_framebuffer(&preview, &output, format, &settings, color_tag).expect("No se pudo guardar la vista previa");
+        return;
+    }
+
+    // Escena de demostración, de un archivo .ron o la escena de siempre
+    let scene = match (demo_name, &scene_path) {
+        (Some(name), _) => demos::build(name, stress_seed)
+            .unwrap_or_else(|| panic!("demo desconocida: '{}' (hay {})", name, demos::NAMES.join(", "))),
+        (None, Some(path)) => scene::load_scene(path).unwrap_or_else(|error| {
+            eprintln!("No se pudo cargar la escena {}: {}", path.display(), error);
+            std::process::exit(1);
+        }),
+        (None, None) => default_scene(floor),
+    };
+    // El fondo de la escena, salvo que --background lo reemplace
+    if let Some(background) = scene.background
+        && arg_value(&args, "--background").is_none()
+    {
+        settings.background = background;
+    }
+    // Lo mismo con los rebotes y --max-depth
+    if let Some(depth) = scene.max_depth
+        && arg_value(&args, "--max-depth").is_none()
+    {
+        settings.max_depth = depth.min(MAX_RAY_DEPTH);
+    }
+    let mut objects = scene.objects;
+    let mut lights = scene.lights;
+    let mut graph = scene.graph;
+    settings.portals = scene.portals.into();
+    // Los grupos del archivo, que la consola puede seguir editando
+    let mut groups = scene.groups;
+    // El sol y la luna del cielo físico se agregan como dos luces más
+    let sun_light_index = settings.sky.map(|sky| {
+        lights.push(sky.sun_light());
+        lights.push(sky.moon_light());
+        lights.len() - 2
+    });
+
+    let aspect_ratio = framebuffer_width as f32 / framebuffer_height as f32;
+
+    let mut camera = scene.camera.unwrap_or_else(|| Camera::new(
         Vec3::new(0.0, 0.0, 5.0),
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(0.0, 1.0, 0.0)
-    );
+    ));
+    if let Some(aperture) = arg_value(&args, "--aperture") {
+        camera.aperture = aperture.parse::<f32>().expect("--aperture debe ser un número").max(0.0);
+    }
+    if let Some(distance) = arg_value(&args, "--focus-distance") {
+        camera.focus_distance = Some(distance.parse().expect("--focus-distance debe ser un número"));
+    }
+    if auto_frame {
+        frame_scene(&mut camera, &objects, aspect_ratio);
+    }
+
+    // --set ruta=valor, después de cargar, para barrer parámetros sin escribir otra escena
+    for assignment in arg_values(&args, "--set") {
+        if let Err(error) = scene::apply_override(assignment, &mut objects, &mut lights, &mut graph, &mut camera) {
+            eprintln!("--set {}: {}", assignment, error);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(path) = obj_export_path {
+        obj_export::export_obj(&objects, &path).expect("No se pudo exportar la escena");
+        return;
+    }
+
+    // Serie de cuadros variando un parámetro, para tiras de comparación
+    if let Some(dir) = sweep_dir {
+        let range = args.get(3).expect("uso: sweep <carpeta> <ruta>=<desde>:<hasta> [--steps N]");
+        let frames = sweep::Sweep::parse(range, sweep_steps).and_then(|sweep| {
+            let frames = sweep::render_sweep(&sweep, &mut objects, &mut lights, &mut camera, &settings, framebuffer_width, framebuffer_height)?;
+            Ok((sweep, frames))
+        });
+        match frames {
+            Ok((sweep, frames)) => sweep::save_sweep(&sweep, &frames, &dir, &settings, color_tag).expect("No se pudo guardar el barrido"),
+            Err(error) => {
+                eprintln!("sweep {}: {}", range, error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(path) = contact_sheet_path {
+        let sheet = contact_sheet::render_contact_sheet(
+            &objects,
+            &lights,
+            &camera,
+            contact_sheet_angles,
+            framebuffer_width,
+            framebuffer_height,
+            &settings,
+        );
+        sheet.save(&path).expect("No se pudo guardar la hoja de contactos");
+        return;
+    }
+
+    // Vista previa en la terminal (útil por SSH o en logs de CI)
+    if terminal_mode {
+        let mut preview = Framebuffer::new(terminal::PREVIEW_WIDTH, terminal::PREVIEW_HEIGHT);
+        render(&mut preview, &objects, &camera, &lights, &settings);
+        terminal::print_framebuffer(&preview).expect("No se pudo escribir en la terminal");
+        return;
+    }
+
+    // Exportar un solo cuadro a imagen (formato por --format o por extensión); nunca abre
+    // ventana, así que sirve en un servidor sin pantalla (con --headless, además informa el tiempo)
+    if let Some(path) = output_path {
+        let format = output_format
+            .or_else(|| OutputFormat::from_path(&path))
+            .unwrap_or(OutputFormat::Png);
+        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+        let start = Instant::now();
+        render_with(&mut frame_graph, &mut framebuffer, &objects, &camera, &lights, &settings);
+        let rendering = start.elapsed();
+        export::save_framebuffer(&framebuffer, &path, format, &settings, color_tag).expect("No se pudo guardar la imagen");
+        if headless {
+            println!(
+                "{}x{}, {} muestras por pixel, {:.1} ms -> {}",
+                framebuffer_width,
+                framebuffer_height,
+                settings.samples.max(1),
+                rendering.as_secs_f64() * 1000.0,
+                path.display()
+            );
+        }
+        if profile_passes {
+            println!("{}", frame_graph.report());
+        }
+        return;
+    }
+
+    if let Some(dir) = &bracket_dir
+        && headless
+    {
+        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+        render(&mut framebuffer, &objects, &camera, &lights, &settings);
+        let paths = bracket::save_brackets(&framebuffer, &settings, &bracket_stops, dir, color_tag)
+            .expect("No se pudo guardar el horquillado");
+        println!("{} imágenes -> {}", paths.len(), dir.display());
+        return;
+    }
+
+    // Animación de una órbita completa alrededor del centro, como GIF/APNG (--gif)
+    // y/o como secuencia de imágenes (--animate)
+    if animation_path.is_some() || sequence_dir.is_some() {
+        if let Some(dir) = &sequence_dir {
+            std::fs::create_dir_all(dir).expect("No se pudo crear la carpeta de la secuencia");
+        }
+        if let Some(dir) = &motion_vectors_dir
+            && let Err(error) = std::fs::create_dir_all(dir)
+        {
+            eprintln!("No se pudo crear la carpeta {}: {}", dir.display(), error);
+            std::process::exit(1);
+        }
+        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+        let yaw_step = 2.0 * PI / animation_frames as f32;
+        let mut frames = Vec::with_capacity(animation_frames as usize);
+        let start_camera = camera.clone();
+        let mut previous_camera = camera.clone();
+        for frame in 0..animation_frames {
+            // Cada cuadro se calcula desde la cámara inicial para que los ángulos no acumulen error
+            let mut camera = start_camera.clone();
+            camera.orbit(yaw_step * frame as f32, 0.0);
+            render(&mut framebuffer, &objects, &camera, &lights, &settings);
+            if animation_path.is_some() {
+                frames.push(export::framebuffer_to_image(&framebuffer));
+            }
+            if let Some(dir) = &sequence_dir {
+                let path = dir.join(format!("frame_{:04}.png", frame + 1));
+                export::save_framebuffer(&framebuffer, &path, OutputFormat::Png, &settings, color_tag)
+                    .expect("No se pudo guardar el cuadro");
+            }
+
+            // Vectores de movimiento respecto al cuadro anterior, uno por cuadro
+            if let Some(dir) = &motion_vectors_dir {
+                let motion = motion::MotionBuffer::compute(
+                    &objects,
+                    &camera,
+                    &previous_camera,
+                    framebuffer_width,
+                    framebuffer_height,
+                );
+                let path = dir.join(format!("motion_{:04}.exr", frame + 1));
+                if let Err(error) = motion.save_exr(&path) {
+                    eprintln!("No se pudieron guardar los vectores de movimiento en {}: {}", path.display(), error);
+                    std::process::exit(1);
+                }
+            }
+
+            previous_camera = camera;
+        }
+        if let Some(path) = animation_path {
+            export::save_animation(&frames, &path).expect("No se pudo guardar la animación");
+        }
+        return;
+    }
+
+    // Servicio de render sin ventana, controlado por el servidor remoto
+    if headless {
+        let Some(address) = listen_address else {
+            eprintln!("--headless requiere --output <archivo> o --listen <dirección>");
+            std::process::exit(1);
+        };
+        let server = RemoteServer::start(address).expect("No se pudo iniciar el servidor de control");
+        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+        render(&mut framebuffer, &objects, &camera, &lights, &settings);
+        server.serve(|request| {
+            handle_remote_request(request, &mut camera, &mut objects, &mut lights, &mut groups, &mut graph, &mut framebuffer, &settings)
+        });
+        return;
+    }
+
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut window = Window::new(
+        "Cubito",
+        window_width,
+        window_height,
+        WindowOptions::default(),
+    ).unwrap();
+
+    window.set_position(500, 500);
+    window.update();
 
     let mut yaw_velocity: f32 = 0.0;
     let mut pitch_velocity: f32 = 0.0;
@@ -201,21 +1246,303 @@ fn main() {
     let damping: f32 = 0.85;
     let max_velocity: f32 = PI / 30.0;
 
+    let mut console = Console::new(&mut window);
+    let remote = listen_address
+        .map(|address| RemoteServer::start(address).expect("No se pudo iniciar el servidor de control"));
+    let mut window_title = String::from("Cubito");
+    let mut auto_exposure = exposure::AutoExposure::new(auto_exposure_enabled);
+    // Modo de edición de luces: muestra los gizmos y permite mover la luz seleccionada
+    let mut light_edit_mode = false;
+    let mut selected_light: usize = 0;
+    let light_move_speed: f32 = 0.1;
+    // Grados por cuadro con que las flechas giran una luz direccional
+    let light_turn_speed: f32 = 2.0;
+    // Luz que se está arrastrando con el mouse
+    let mut dragged_light: Option<usize> = None;
+    // Modo de medición: M lo activa y cada clic izquierdo elige un punto
+    let mut measure_tool = measure::MeasureTool::default();
+    // Lupa: Z muestra los pixeles alrededor del cursor ampliados y con sus valores
+    let mut magnifier = magnifier::Magnifier::default();
+    // Con apertura, el clic izquierdo fuera del modo de medición enfoca lo que hay bajo el cursor
+    let mut focus_picker = focus::FocusPicker::default();
+    let mut mouse_was_down = false;
+    // Panel con la lista de la escena; O lo abre y las flechas cambian la selección
+    let mut outliner = outliner::Outliner::default();
+    // Propiedades del objeto seleccionado en el panel; I lo abre
+    let mut inspector = inspector::Inspector::default();
+    // Material tomado con el cuentagotas, listo para pegar
+    let mut material_slot: Option<Material> = None;
+
     while window.is_open() {
-        if window.is_key_down(Key::Escape) { break; }
+        if let Some(command) = console.update(&window) {
+            match console::execute(&command, &mut objects, &mut lights, &mut groups, &mut graph) {
+                Ok(message) => println!("{}", message),
+                Err(error) => eprintln!("Error: {}", error),
+            }
+        }
+
+        if let Some(server) = &remote {
+            server.poll(|request| {
+                handle_remote_request(request, &mut camera, &mut objects, &mut lights, &mut groups, &mut graph, &mut framebuffer, &settings)
+            });
+        }
+
+        // Mientras la consola está abierta, la línea se muestra en el título de la ventana
+        let title = if console.open { format!("Cubito  {}", console.prompt()) } else { String::from("Cubito") };
+        if title != window_title {
+            window.set_title(&title);
+            window_title = title;
+        }
 
-        if window.is_key_down(Key::A) { yaw_velocity = (yaw_velocity + acceleration).min(max_velocity); }
-        if window.is_key_down(Key::D) { yaw_velocity = (yaw_velocity - acceleration).max(-max_velocity); }
-        if window.is_key_down(Key::W) { pitch_velocity = (pitch_velocity - acceleration).max(-max_velocity); }
-        if window.is_key_down(Key::S) { pitch_velocity = (pitch_velocity + acceleration).min(max_velocity); }
+        // Solo al presionar: si Escape cierra la consola, mantenerla apretada no cierra la ventana
+        if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+            if !console.open { break; }
+            console.open = false;
+        }
+
+        if !console.open {
+            if window.is_key_down(Key::A) { yaw_velocity = (yaw_velocity + acceleration).min(max_velocity); }
+            if window.is_key_down(Key::D) { yaw_velocity = (yaw_velocity - acceleration).max(-max_velocity); }
+            if window.is_key_down(Key::W) { pitch_velocity = (pitch_velocity - acceleration).max(-max_velocity); }
+            if window.is_key_down(Key::S) { pitch_velocity = (pitch_velocity + acceleration).min(max_velocity); }
+
+            if window.is_key_pressed(Key::F, KeyRepeat::No) {
+                frame_scene(&mut camera, &objects, aspect_ratio);
+            }
+            if window.is_key_pressed(Key::G, KeyRepeat::No) {
+                light_edit_mode = !light_edit_mode;
+            }
+            // Muestras por pixel: 1, 4 o 16
+            if window.is_key_pressed(Key::K, KeyRepeat::No) {
+                settings.samples = match settings.samples {
+                    0..=3 => 4,
+                    4..=15 => 16,
+                    _ => 1,
+                };
+                println!("muestras por pixel: {}", settings.samples);
+            }
+            if window.is_key_pressed(Key::O, KeyRepeat::No) {
+                outliner.toggle();
+            }
+            if outliner.open {
+                outliner.update(&window, &objects, &lights, &groups);
+                if let Some(outliner::Selection::Light(index)) = outliner.selection(&objects, &lights, &groups) {
+                    selected_light = index;
+                }
+            }
+            if window.is_key_pressed(Key::I, KeyRepeat::No) {
+                inspector.toggle();
+            }
+            if inspector.open
+                && let Some(outliner::Selection::Object(index)) = outliner.selection(&objects, &lights, &groups)
+                && let Some(command) = inspector.update(&window, objects[index].as_mut(), index)
+            {
+                console.open_with(&command);
+            }
+            // Con los paneles abiertos las flechas son de ellos y no mueven la luz
+            if light_edit_mode && !outliner.open && !inspector.open && !lights.is_empty() {
+                if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+                    selected_light = (selected_light + 1) % lights.len();
+                }
+
+                let mut offset = Vec3::zeros();
+                if window.is_key_down(Key::Left) { offset.x -= light_move_speed; }
+                if window.is_key_down(Key::Right) { offset.x += light_move_speed; }
+                if window.is_key_down(Key::Up) { offset.z -= light_move_speed; }
+                if window.is_key_down(Key::Down) { offset.z += light_move_speed; }
+                if window.is_key_down(Key::PageUp) { offset.y += light_move_speed; }
+                if window.is_key_down(Key::PageDown) { offset.y -= light_move_speed; }
+
+                let light = &mut lights[selected_light];
+                match &mut light.kind {
+                    // Una luz direccional no tiene dónde estar: las flechas la giran
+                    LightKind::Directional { direction } => {
+                        let step = light_turn_speed.to_radians() / light_move_speed;
+                        if offset.x != 0.0 || offset.z != 0.0 {
+                            *direction = gizmo::turn_direction(direction, -offset.x * step, -offset.z * step);
+                            println!("set light.{}.direction {:.3} {:.3} {:.3}", selected_light, direction.x, direction.y, direction.z);
+                        }
+                    }
+                    _ if offset != Vec3::zeros() => {
+                        light.position += offset;
+                        println!(
+                            "set light.{}.position {:.2} {:.2} {:.2}",
+                            selected_light, light.position.x, light.position.y, light.position.z
+                        );
+                    }
+                    _ => {}
+                }
+
+                // Enter guarda las luces en el archivo de la escena
+                if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                    match &scene_path {
+                        Some(path) => match scene::save_lights(path, &lights) {
+                            Ok(count) => println!("{} luces guardadas en {}", count, path.display()),
+                            Err(error) => eprintln!("Error: {}", error),
+                        },
+                        None => eprintln!("Error: la escena no salió de un archivo (--scene)"),
+                    }
+                }
+            }
+            // Fuera del modo de edición de luces, las flechas mueven el sol: izquierda y derecha
+            // el azimut, arriba y abajo la elevación
+            if !light_edit_mode && !outliner.open && !inspector.open {
+                let mut turn = (0.0, 0.0);
+                if window.is_key_down(Key::Left) { turn.1 -= SUN_STEP_DEGREES; }
+                if window.is_key_down(Key::Right) { turn.1 += SUN_STEP_DEGREES; }
+                if window.is_key_down(Key::Up) { turn.0 += SUN_STEP_DEGREES; }
+                if window.is_key_down(Key::Down) { turn.0 -= SUN_STEP_DEGREES; }
+                if turn != (0.0, 0.0) {
+                    move_sun(&mut settings, &mut lights, sun_light_index, turn.0, turn.1);
+                }
+            }
+
+            if window.is_key_pressed(Key::X, KeyRepeat::No) {
+                settings.guides = match settings.guides {
+                    Some(_) => None,
+                    None => Some(guides_config),
+                };
+            }
+            if window.is_key_pressed(Key::M, KeyRepeat::No) {
+                measure_tool.toggle();
+            }
+            if window.is_key_pressed(Key::Z, KeyRepeat::No) {
+                magnifier.toggle();
+            }
+            let mouse_down = window.get_mouse_down(MouseButton::Left);
+            // En el modo de edición de luces, un clic sobre un gizmo elige esa luz y arrastrarlo
+            // la mueve en el plano de la pantalla
+            let (width, height) = (framebuffer_width as f32, framebuffer_height as f32);
+            if light_edit_mode && mouse_down && !mouse_was_down
+                && let Some((x, y)) = mouse_position(&window, &framebuffer)
+            {
+                dragged_light = gizmo::pick_light(&camera, &lights, x, y, width, height);
+                selected_light = dragged_light.unwrap_or(selected_light);
+            }
+            if let Some(index) = dragged_light
+                && let Some((x, y)) = mouse_position(&window, &framebuffer)
+                && let Some(position) = gizmo::drag_position(&camera, &lights[index].position, x, y, width, height)
+            {
+                lights[index].position = position;
+            }
+            if !mouse_down && let Some(index) = dragged_light.take() {
+                let position = lights[index].position;
+                println!("set light.{}.position {:.2} {:.2} {:.2}", index, position.x, position.y, position.z);
+            }
+            if measure_tool.active && mouse_down && !mouse_was_down
+                && let Some((x, y)) = mouse_position(&window, &framebuffer)
+            {
+                let (width, height) = (framebuffer_width as f32, framebuffer_height as f32);
+                if let Some(measurement) = measure_tool.click(x, y, &camera, &objects, width, height) {
+                    println!("{}", measurement);
+                }
+            }
+            if !measure_tool.active && dragged_light.is_none() && camera.aperture > 0.0 && mouse_down && !mouse_was_down
+                && let Some((x, y)) = mouse_position(&window, &framebuffer)
+            {
+                let (width, height) = (framebuffer_width as f32, framebuffer_height as f32);
+                if let Some(distance) = focus_picker.click(x, y, &camera, &objects, width, height) {
+                    println!("foco: {:.2}", distance);
+                }
+            }
+            mouse_was_down = mouse_down;
+
+            // Cuentagotas: C copia el material bajo el cursor, V lo pega en el objeto seleccionado
+            if window.is_key_pressed(Key::C, KeyRepeat::No)
+                && let Some((x, y)) = mouse_position(&window, &framebuffer)
+                && let Some(sample) = eyedropper::pick(x, y, &camera, &objects, &framebuffer)
+            {
+                println!("{}", eyedropper::describe(&sample));
+                material_slot = Some(sample.material);
+            }
+            if window.is_key_pressed(Key::V, KeyRepeat::No)
+                && let Some(material) = &material_slot
+            {
+                match outliner.selection(&objects, &lights, &groups) {
+                    Some(outliner::Selection::Object(index)) => {
+                        *objects[index].material_mut() = material.clone();
+                        println!("material copiado a object.{}", index);
+                    }
+                    _ => eprintln!("Error: no hay un objeto seleccionado"),
+                }
+            }
+
+            if window.is_key_pressed(Key::P, KeyRepeat::No) {
+                println!("{}", frame_graph.report());
+            }
+            if window.is_key_pressed(Key::T, KeyRepeat::No) {
+                settings.taa = !settings.taa;
+                println!("antialiasing temporal: {}", if settings.taa { "sí" } else { "no" });
+            }
+            // [ y ] bajan y suben los rebotes de los reflejos
+            if window.is_key_pressed(Key::LeftBracket, KeyRepeat::Yes) && settings.max_depth > 0 {
+                settings.max_depth -= 1;
+                println!("rebotes: {}", settings.max_depth);
+            }
+            if window.is_key_pressed(Key::RightBracket, KeyRepeat::Yes) && settings.max_depth < MAX_RAY_DEPTH {
+                settings.max_depth += 1;
+                println!("rebotes: {}", settings.max_depth);
+            }
+            if window.is_key_pressed(Key::H, KeyRepeat::No) {
+                settings.half_res_effects = !settings.half_res_effects;
+                println!("efectos a media resolución: {}", if settings.half_res_effects { "sí" } else { "no" });
+            }
+            if window.is_key_pressed(Key::B, KeyRepeat::No) {
+                let dir = bracket_dir.clone().unwrap_or_else(|| std::path::PathBuf::from("bracket"));
+                match bracket::save_brackets(&framebuffer, &settings, &bracket_stops, &dir, color_tag) {
+                    Ok(paths) => println!("{} imágenes -> {}", paths.len(), dir.display()),
+                    Err(error) => eprintln!("Error: no se pudo guardar el horquillado: {}", error),
+                }
+            }
+            if window.is_key_pressed(Key::E, KeyRepeat::No) {
+                auto_exposure.enabled = !auto_exposure.enabled;
+                if !auto_exposure.enabled {
+                    auto_exposure.exposure = 1.0;
+                }
+            }
+        }
+
+        if let (Some(sky), Some(day_length), Some(index)) = (settings.sky, day_length, sun_light_index) {
+            let seconds = frame_delay.as_secs_f32();
+            let sky = sky.advanced(360.0 / day_length * seconds, seconds);
+            lights[index] = sky.sun_light();
+            lights[index + 1] = sky.moon_light();
+            settings.sky = Some(sky);
+        }
 
         camera.orbit(yaw_velocity, pitch_velocity);
         yaw_velocity *= damping;
         pitch_velocity *= damping;
+        let focusing = focus_picker.update(&mut camera);
 
-        render(&mut framebuffer, &objects, &camera, &lights);
+        framebuffer.exposure = auto_exposure.exposure;
+        // Mientras la cámara gira o cambia el foco basta una muestra por pixel; quieta, se usan todas
+        let moving = yaw_velocity.abs() + pitch_velocity.abs() > 1e-3 || focusing;
+        let frame_settings = RenderSettings { samples: if moving { 1 } else { settings.samples }, ..settings.clone() };
+        render_with(&mut frame_graph, &mut framebuffer, &objects, &camera, &lights, &frame_settings);
+        auto_exposure.update(&framebuffer);
+        if magnifier.active {
+            let cursor = mouse_position(&window, &framebuffer);
+            magnifier.draw(&mut framebuffer, cursor);
+        }
+        if light_edit_mode {
+            gizmo::draw_light_gizmos(&mut framebuffer, &camera, &lights, Some(selected_light));
+        }
+        if measure_tool.active {
+            measure_tool.draw(&mut framebuffer, &camera);
+        }
+        if outliner.open {
+            outliner.draw(&mut framebuffer, &objects, &lights, &groups);
+        }
+        if inspector.open {
+            let selected = match outliner.selection(&objects, &lights, &groups) {
+                Some(outliner::Selection::Object(index)) => Some((index, objects[index].as_ref())),
+                _ => None,
+            };
+            inspector.draw(&mut framebuffer, selected);
+        }
 
         window.update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height).unwrap();
         std::thread::sleep(frame_delay);
     }
-}
\ No newline at end of file
+}
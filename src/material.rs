@@ -1,54 +1,390 @@
 use crate::color::Color;
+use crate::cube::Face;
+use crate::pbr::Pbr;
+use crate::procedural::Pattern;
+use crate::projection::Projection;
+use crate::settings::MAX_RAY_DEPTH;
+use crate::texture::{FaceTexture, Mipmap, Texture};
 use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use nalgebra_glm::Vec3;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+// Lado en pixeles de la textura del tablero de ajedrez
+const CHECKER_TEXTURE_SIZE: u32 = 64;
+// Índice de refracción del vidrio común
+const DEFAULT_IOR: f32 = 1.5;
 
 #[derive(Debug, Clone)]
 pub struct Material {
+    // Nombre para mostrar en el editor, por ejemplo el del archivo .ron del que se cargó
+    pub name: Option<String>,
     pub diffuse: Color,
     pub specular: f32,
     pub albedo: [f32; 2],
-    pub texture: Option<DynamicImage>,
+    // Modelo físico metálico/rugosidad en lugar de Phong; con él no se usan `specular` ni `albedo`
+    pub pbr: Option<Pbr>,
+    // Imagen o patrón del color base; las imágenes van con sus mipmaps, compartidos entre
+    // las copias del material
+    pub texture: Option<Texture>,
+    pub texture_path: Option<String>,
+    // UV calculada por proyección en lugar de la de la primitiva, para texturas y mapas
+    pub projection: Option<Projection>,
+    // Textura propia de cada cara en cubos y bloques (índice `Face::index`); las que no
+    // tienen usan `texture`
+    pub faces: Option<Arc<[Option<FaceTexture>; 6]>>,
+    // Mapa de alturas para parallax occlusion mapping (blanco = superficie, negro = hundido);
+    // como el de normales, compartido entre las copias del material
+    pub height_map: Option<Arc<DynamicImage>>,
+    // Profundidad máxima del relieve, en fracción del tamaño de la cara
+    pub height_scale: f32,
+    // Mapa de normales en espacio tangente (ver `normal_map::perturb`)
+    pub normal_map: Option<Arc<DynamicImage>>,
+    // Cristal: refleja y refracta según Fresnel; lo que lo atraviesa se tiñe con `diffuse`
     pub is_crystal: bool,
+    // Índice de refracción del cristal y de la parte dieléctrica de `pbr` (vidrio 1.5, agua 1.33)
+    pub ior: f32,
+    // Fracción del color que es reflejo de espejo, mezclada con el sombreado de Phong sin
+    // dejar pasar luz (metales pulidos, espejos); 0 = nada
+    pub reflectivity: f32,
+    // 1 = opaco; menos de 1 deja pasar los rayos filtrados por el color de la superficie (agua, vidrio de color)
+    pub opacity: f32,
+    // Variación por instancia del tono y el brillo (0 = todas iguales, 0.1 = ±10% de brillo y ±6° de tono)
+    pub variation: f32,
+    // Multiplicador del color base (textura o difuso); blanco = sin cambio
+    pub tint: Color,
+    // Luz propia que se suma al color sin importar la iluminación; negro = no brilla
+    pub emissive: Color,
+    // Rebotes hasta los que todavía se refleja en esta superficie, en lugar de los de los
+    // ajustes: más en un cristal que se ve a través de otros, menos en un espejo lejano
+    pub max_depth: Option<u32>,
 }
 
 impl Material {
     pub fn new(diffuse: Color, specular: f32, albedo: [f32; 2]) -> Self {
         Self {
+            name: None,
             diffuse,
             specular,
             albedo,
+            pbr: None,
             texture: None,
+            texture_path: None,
+            projection: None,
+            faces: None,
+            height_map: None,
+            height_scale: 0.0,
+            normal_map: None,
             is_crystal: false,
+            ior: DEFAULT_IOR,
+            reflectivity: 0.0,
+            opacity: 1.0,
+            variation: 0.0,
+            tint: Color::new(255.0, 255.0, 255.0),
+            emissive: Color::black(),
+            max_depth: None,
         }
     }
 
-    pub fn with_texture(path: &str, specular: f32, albedo: [f32; 2]) -> Self {
-        let img = image::open(path).expect("No se pudo cargar la textura");
+    pub fn with_texture(path: &str, specular: f32, albedo: [f32; 2]) -> Result<Self, Box<dyn Error>> {
+        let img = open_image(path)?;
+        Ok(Self {
+            texture: Some(Texture::Image(Arc::new(Mipmap::new(&img)))),
+            texture_path: Some(path.to_string()),
+            ..Self::new(Color::new(255.0, 255.0, 255.0), specular, albedo)
+        })
+    }
+
+    // Tablero de ajedrez generado: cada repetición de la textura tiene 2x2 casillas
+    pub fn checker(light: Color, dark: Color, specular: f32, albedo: [f32; 2]) -> Self {
+        let size = CHECKER_TEXTURE_SIZE;
+        let image = image::RgbImage::from_fn(size, size, |x, y| {
+            let color = if (x < size / 2) == (y < size / 2) { light } else { dark };
+            image::Rgb(color.to_srgb().map(|value| value.round() as u8))
+        });
+        let texture = DynamicImage::ImageRgb8(image);
         Self {
-            diffuse: Color::new(255.0, 255.0, 255.0),
-            specular,
-            albedo,
-            texture: Some(img),
-            is_crystal: false,
+            name: Some("tablero".to_string()),
+            texture: Some(Texture::Image(Arc::new(Mipmap::new(&texture)))),
+            ..Self::new(Color::new(255.0, 255.0, 255.0), specular, albedo)
         }
     }
 
-    pub fn crystal(diffuse: Color, specular: f32, albedo: [f32; 2]) -> Self {
-        Self {
-            diffuse,
-            specular,
-            albedo,
-            texture: None,
-            is_crystal: true,
+    // Color base de una instancia: según la posición del objeto se corre un poco el tono
+    // (giro alrededor del eje gris) y el brillo, para que muchos bloques iguales no se vean clonados
+    pub fn varied(&self, color: Color, instance: &Vec3) -> Color {
+        if self.variation <= 0.0 {
+            return color;
+        }
+        let mut hasher = DefaultHasher::new();
+        for value in [instance.x, instance.y, instance.z] {
+            ((value * 1000.0).round() as i64).hash(&mut hasher);
         }
+        let seed = hasher.finish();
+        let random = |bits: u64| (bits & 0xFFFF) as f32 / 0xFFFF as f32 * 2.0 - 1.0;
+        let brightness = (1.0 + self.variation * random(seed)).max(0.0);
+        let angle = self.variation * random(seed >> 16) * PI / 3.0;
+
+        let (sin, cos) = angle.sin_cos();
+        let same = cos + (1.0 - cos) / 3.0;
+        let ahead = (1.0 - cos) / 3.0 + sin / 3.0_f32.sqrt();
+        let behind = (1.0 - cos) / 3.0 - sin / 3.0_f32.sqrt();
+        // En los colores saturados el giro saca algún canal por debajo de cero
+        Color::new(
+            (color.r * same + color.g * behind + color.b * ahead).max(0.0),
+            (color.r * ahead + color.g * same + color.b * behind).max(0.0),
+            (color.r * behind + color.g * ahead + color.b * same).max(0.0),
+        ) * brightness
+    }
+
+    pub fn with_height_map(mut self, path: &str, height_scale: f32) -> Result<Self, Box<dyn Error>> {
+        self.height_map = Some(Arc::new(open_image(path)?));
+        self.height_scale = height_scale;
+        Ok(self)
+    }
+
+    pub fn with_normal_map(mut self, path: &str) -> Result<Self, Box<dyn Error>> {
+        self.normal_map = Some(Arc::new(open_image(path)?));
+        Ok(self)
+    }
+
+    pub fn crystal(diffuse: Color, specular: f32, albedo: [f32; 2]) -> Self {
+        Self { is_crystal: true, ..Self::new(diffuse, specular, albedo) }
     }
 
     pub fn black() -> Self {
-        Self {
-            diffuse: Color::new(0.0, 0.0, 0.0),
-            specular: 0.0,
-            albedo: [0.0, 0.0],
-            texture: None,
-            is_crystal: false,
+        Self::new(Color::black(), 0.0, [0.0, 0.0])
+    }
+}
+
+// Imagen de una cara: la ruta sola o una región de un atlas en pixeles, por ejemplo
+// (texture: "atlas.png", region: (16, 0, 16, 16))
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FaceTextureDescription {
+    Image(String),
+    Atlas { texture: String, region: [u32; 4] },
+}
+
+// Texturas por cara al estilo de los bloques: `side` vale para los cuatro costados y
+// `north`, `south`, `east` y `west` lo reemplazan en uno
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct FacesDescription {
+    #[serde(default)]
+    pub top: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub bottom: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub side: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub north: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub south: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub east: Option<FaceTextureDescription>,
+    #[serde(default)]
+    pub west: Option<FaceTextureDescription>,
+}
+
+impl FacesDescription {
+    // Cada imagen se carga una vez aunque la usen varias caras (un atlas). Las rutas son
+    // relativas a `dir`
+    pub fn build(&self, dir: &Path) -> Result<[Option<FaceTexture>; 6], Box<dyn Error>> {
+        let mut images: HashMap<&str, Arc<Mipmap>> = HashMap::new();
+        let mut faces: [Option<FaceTexture>; 6] = Default::default();
+        for (slot, face) in faces.iter_mut().zip(Face::ALL) {
+            let description = match face {
+                Face::Top => self.top.as_ref(),
+                Face::Bottom => self.bottom.as_ref(),
+                Face::North => self.north.as_ref().or(self.side.as_ref()),
+                Face::South => self.south.as_ref().or(self.side.as_ref()),
+                Face::East => self.east.as_ref().or(self.side.as_ref()),
+                Face::West => self.west.as_ref().or(self.side.as_ref()),
+            };
+            let Some(description) = description else { continue };
+            let (path, region) = match description {
+                FaceTextureDescription::Image(path) => (path.as_str(), None),
+                FaceTextureDescription::Atlas { texture, region } => (texture.as_str(), Some(*region)),
+            };
+            let mipmap = match images.get(path) {
+                Some(mipmap) => mipmap.clone(),
+                None => Arc::new(Mipmap::new(&open_image(&resolve(dir, path))?)),
+            };
+            images.insert(path, mipmap.clone());
+            *slot = Some(FaceTexture::new(mipmap, region));
+        }
+        Ok(faces)
+    }
+}
+
+fn default_diffuse() -> [f32; 3] {
+    [255.0, 255.0, 255.0]
+}
+
+// Con `pbr` no se usan y se pueden omitir
+fn default_specular() -> f32 {
+    10.0
+}
+
+fn default_albedo() -> [f32; 2] {
+    [0.9, 0.1]
+}
+
+fn default_height_scale() -> f32 {
+    0.05
+}
+
+fn default_ior() -> f32 {
+    DEFAULT_IOR
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+// Definición de un material en archivo RON, por ejemplo:
+// (diffuse: (200, 40, 40), specular: 50, albedo: (0.8, 0.2))
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MaterialDescription {
+    #[serde(default = "default_diffuse")]
+    pub diffuse: [f32; 3],
+    #[serde(default = "default_specular")]
+    pub specular: f32,
+    #[serde(default = "default_albedo")]
+    pub albedo: [f32; 2],
+    #[serde(default)]
+    pub pbr: Option<Pbr>,
+    #[serde(default)]
+    pub texture: Option<String>,
+    #[serde(default)]
+    pub projection: Option<Projection>,
+    // Patrón procedural en lugar de `texture`
+    #[serde(default)]
+    pub pattern: Option<Pattern>,
+    #[serde(default)]
+    pub height_map: Option<String>,
+    #[serde(default = "default_height_scale")]
+    pub height_scale: f32,
+    #[serde(default)]
+    pub normal_map: Option<String>,
+    #[serde(default)]
+    pub faces: Option<FacesDescription>,
+    #[serde(default)]
+    pub crystal: bool,
+    #[serde(default = "default_ior")]
+    pub ior: f32,
+    #[serde(default)]
+    pub reflectivity: f32,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    #[serde(default)]
+    pub variation: f32,
+    #[serde(default)]
+    pub emissive: [f32; 3],
+    // Por ejemplo Some(6) en un cristal o Some(2) en un espejo
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+impl MaterialDescription {
+    // Las rutas de las imágenes son relativas a `dir`, la carpeta del archivo que lo define
+    pub fn build(&self, dir: &Path) -> Result<Material, Box<dyn Error>> {
+        let [r, g, b] = self.diffuse;
+        let mut material = match &self.texture {
+            Some(path) => Material::with_texture(&resolve(dir, path), self.specular, self.albedo)?,
+            None => Material::new(Color::from_srgb(r, g, b), self.specular, self.albedo),
+        };
+        material.diffuse = Color::from_srgb(r, g, b);
+        if let Some(pattern) = self.pattern {
+            material.texture = Some(Texture::Procedural(pattern.linearized()));
+        }
+        material.projection = self.projection;
+        material.is_crystal = self.crystal;
+        material.ior = self.ior.max(1.0);
+        material.reflectivity = self.reflectivity.clamp(0.0, 1.0);
+        material.pbr = self.pbr;
+        material.opacity = self.opacity.clamp(0.0, 1.0);
+        material.variation = self.variation.max(0.0);
+        let [r, g, b] = self.emissive;
+        material.emissive = Color::from_srgb(r, g, b);
+        material.max_depth = self.max_depth.map(|depth| depth.min(MAX_RAY_DEPTH));
+        if let Some(path) = &self.height_map {
+            material = material.with_height_map(&resolve(dir, path), self.height_scale)?;
+        }
+        if let Some(path) = &self.normal_map {
+            material = material.with_normal_map(&resolve(dir, path))?;
+        }
+        if let Some(faces) = &self.faces {
+            material.faces = Some(Arc::new(faces.build(dir)?));
+        }
+        Ok(material)
+    }
+}
+
+// Ruta de una imagen nombrada en un archivo de la carpeta `dir`; las absolutas quedan igual
+fn resolve(dir: &Path, path: &str) -> String {
+    dir.join(path).to_string_lossy().into_owned()
+}
+
+// La ruta va en el error: en una escena con muchas texturas no se sabría cuál falta
+fn open_image(path: &str) -> Result<DynamicImage, Box<dyn Error>> {
+    image::open(path).map_err(|error| format!("{}: {}", path, error).into())
+}
+
+pub fn load_material(path: &Path) -> Result<Material, Box<dyn Error>> {
+    let source = std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let description: MaterialDescription = ron::from_str(&source).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let mut material = description.build(path.parent().unwrap_or(Path::new("")))?;
+    material.name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+    Ok(material)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varying(variation: f32) -> Material {
+        Material { variation, ..Material::new(Color::new(120.0, 120.0, 120.0), 10.0, [0.9, 0.1]) }
+    }
+
+    #[test]
+    fn no_variation_keeps_the_color() {
+        let color = Color::new(200.0, 40.0, 40.0);
+        assert_eq!(varying(0.0).varied(color, &Vec3::new(3.0, 1.0, -2.0)), color);
+    }
+
+    #[test]
+    fn same_place_same_color_and_places_differ() {
+        let (material, color) = (varying(0.3), Color::new(200.0, 40.0, 40.0));
+        let here = material.varied(color, &Vec3::new(3.0, 1.0, -2.0));
+        assert_eq!(material.varied(color, &Vec3::new(3.0, 1.0, -2.0)), here);
+        let colors: Vec<Color> = (0..10).map(|x| material.varied(color, &Vec3::new(x as f32, 0.0, 0.0))).collect();
+        assert!(colors.iter().any(|other| *other != colors[0]));
+    }
+
+    // El giro del tono es alrededor del eje gris: un gris solo cambia de brillo, y no más que `variation`
+    #[test]
+    fn gray_only_changes_brightness() {
+        let material = varying(0.25);
+        let gray = Color::new(100.0, 100.0, 100.0);
+        for x in 0..50 {
+            let varied = material.varied(gray, &Vec3::new(x as f32, 2.0 * x as f32, 0.5));
+            assert!((varied.r - varied.g).abs() < 1e-3 && (varied.g - varied.b).abs() < 1e-3, "{:?}", varied);
+            assert!((75.0 - 1e-3..=125.0 + 1e-3).contains(&varied.r), "{:?}", varied);
+        }
+    }
+
+    #[test]
+    fn saturated_colors_stay_non_negative() {
+        let material = varying(1.0);
+        for x in 0..50 {
+            let varied = material.varied(Color::new(255.0, 0.0, 0.0), &Vec3::new(x as f32, 0.0, 0.0));
+            assert!(varied.r >= 0.0 && varied.g >= 0.0 && varied.b >= 0.0, "{:?}", varied);
         }
     }
 }
@@ -0,0 +1,51 @@
+// material_preview.rs
+
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::material::Material;
+use crate::object::Object;
+use crate::render;
+use crate::settings::RenderSettings;
+
+const BACKDROP_GRAY: f32 = 128.0;
+
+// Escena estándar de estudio: el objeto al centro, piso y pared grises, luz principal, de relleno y de contorno
+pub fn render_material_preview(
+    material: Material,
+    width: usize,
+    height: usize,
+    settings: &RenderSettings,
+) -> Framebuffer {
+    let backdrop = Arc::new(Material::new(Color::from_srgb(BACKDROP_GRAY, BACKDROP_GRAY, BACKDROP_GRAY), 10.0, [0.9, 0.1]));
+
+    let objects: [Object; 3] = [
+        Box::new(Cube { center: Vec3::new(0.0, 0.0, 0.0), size: 1.5, material: material.into() }),
+        // Piso: la cara superior queda justo debajo del objeto
+        Box::new(Cube { center: Vec3::new(0.0, -40.75, 0.0), size: 80.0, material: backdrop.clone() }),
+        // Pared de fondo
+        Box::new(Cube { center: Vec3::new(0.0, 0.0, -46.0), size: 80.0, material: backdrop }),
+    ];
+
+    let lights = [
+        Light::new(Vec3::new(4.0, 5.0, 5.0), Color::from_srgb(255.0, 245.0, 230.0), 1.0),
+        Light::new(Vec3::new(-5.0, 2.0, 3.0), Color::from_srgb(230.0, 240.0, 255.0), 0.4),
+        Light::new(Vec3::new(0.0, 4.0, -4.0), Color::from_srgb(255.0, 255.0, 255.0), 0.6),
+    ];
+
+    let camera = Camera::new(
+        Vec3::new(3.0, 2.5, 4.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    );
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    render(&mut framebuffer, &objects, &camera, &lights, settings);
+    framebuffer
+}
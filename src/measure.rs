@@ -0,0 +1,88 @@
+// measure.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::object::Object;
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::ray::Ray;
+
+const MARKER_RADIUS: i32 = 2;
+
+// Herramienta de medición: cada clic toma el punto de la superficie bajo el cursor;
+// con dos puntos muestra la distancia entre ellos y las diferencias por eje
+#[derive(Default)]
+pub struct MeasureTool {
+    pub active: bool,
+    points: Vec<Vec3>,
+}
+
+impl MeasureTool {
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        self.points.clear();
+    }
+
+    // Lanza un rayo por el pixel (x, y) y guarda el punto golpeado; un tercer clic
+    // empieza una medición nueva. Devuelve el resultado al completar una medición
+    pub fn click(&mut self, x: f32, y: f32, camera: &Camera, objects: &[Object], width: f32, height: f32) -> Option<String> {
+        let direction = camera.primary_ray_direction(x, y, width, height);
+        let intersect = crate::scene_intersect(&Ray::new(camera.position, direction), objects);
+        if !intersect.is_intersecting {
+            return None;
+        }
+
+        if self.points.len() == 2 {
+            self.points.clear();
+        }
+        self.points.push(intersect.point);
+        self.measurement()
+    }
+
+    pub fn measurement(&self) -> Option<String> {
+        let [a, b] = self.points.as_slice() else { return None };
+        let delta = b - a;
+        Some(format!(
+            "distancia {:.3}  dx {:.3}  dy {:.3}  dz {:.3}",
+            delta.norm(), delta.x, delta.y, delta.z
+        ))
+    }
+
+    // Marca los puntos elegidos, une los dos con una línea y escribe la medición abajo
+    pub fn draw(&self, framebuffer: &mut Framebuffer, camera: &Camera) {
+        let width = framebuffer.width as f32;
+        let height = framebuffer.height as f32;
+        let projected: Vec<(f32, f32)> = self
+            .points
+            .iter()
+            .filter_map(|point| camera.project(point, width, height))
+            .collect();
+
+        framebuffer.set_current_color(0xFFFF00);
+        if let [(x0, y0), (x1, y1)] = projected.as_slice() {
+            let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil() as usize;
+            for i in 0..=steps {
+                let t = i as f32 / steps.max(1) as f32;
+                plot(framebuffer, (x0 + (x1 - x0) * t) as i32, (y0 + (y1 - y0) * t) as i32);
+            }
+        }
+        for (x, y) in &projected {
+            for dy in -MARKER_RADIUS..=MARKER_RADIUS {
+                for dx in -MARKER_RADIUS..=MARKER_RADIUS {
+                    plot(framebuffer, *x as i32 + dx, *y as i32 + dy);
+                }
+            }
+        }
+
+        let text = self.measurement().unwrap_or_else(|| format!("medir: punto {} de 2", self.points.len() + 1));
+        let y = framebuffer.height.saturating_sub(font::GLYPH_HEIGHT + 6);
+        font::draw_label(framebuffer, 2, y, &text);
+    }
+}
+
+fn plot(framebuffer: &mut Framebuffer, x: i32, y: i32) {
+    if x >= 0 && y >= 0 {
+        framebuffer.point(x as usize, y as usize);
+    }
+}
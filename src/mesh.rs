@@ -0,0 +1,566 @@
+// mesh.rs
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+use crate::buffer_pool;
+use crate::material::Material;
+use crate::obj_export::{self, ObjTransform};
+use crate::object::{Object, SceneObject};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Triángulos por hoja del BVH de la malla
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+// Nodo del BVH de los triángulos: con `count` = 0 sus hijos son `left` y `left + 1`; si no, es
+// una hoja con los triángulos `start..start + count`
+#[derive(Debug, Clone)]
+struct MeshNode {
+    bounds: Aabb,
+    left: usize,
+    start: usize,
+    count: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Triangle {
+    // Índices de `positions`, antihorarios vistos desde afuera
+    vertices: [u32; 3],
+    // Índice del material en los de la malla
+    submesh: u32,
+}
+
+// Vértices y triángulos de una malla con su BVH, compartidos entre las copias de la malla
+// hasta que se edita una
+#[derive(Debug, Clone)]
+struct Geometry {
+    positions: Vec<Vec3>,
+    // Normal de cada vértice, para sombrear la malla suave; sin ellas se ven las caras. Una
+    // normal nula usa la de la cara
+    normals: Option<Vec<Vec3>>,
+    // Coordenadas de textura de cada vértice
+    uvs: Option<Vec<(f32, f32)>>,
+    // En el orden del BVH
+    triangles: Vec<Triangle>,
+    nodes: Vec<MeshNode>,
+}
+
+impl Geometry {
+    fn corners(&self, triangle: usize) -> [Vec3; 3] {
+        self.triangles[triangle].vertices.map(|index| self.positions[index as usize])
+    }
+
+    fn face_normal(&self, triangle: usize) -> Vec3 {
+        let [a, b, c] = self.corners(triangle);
+        (b - a).cross(&(c - a)).normalize()
+    }
+
+    fn bounds(&self, triangles: std::ops::Range<usize>) -> Aabb {
+        let corners = triangles.flat_map(|triangle| self.corners(triangle)).map(|corner| Aabb::new(corner, corner));
+        Aabb::enclosing(corners).expect("un nodo del BVH tiene al menos un triángulo")
+    }
+
+    // Parte cada nodo por la mitad de sus triángulos a lo largo del eje más largo de sus
+    // centros, hasta que quedan pocos por hoja
+    fn build(&mut self) {
+        let count = self.triangles.len();
+        self.nodes = vec![MeshNode { bounds: self.bounds(0..count), left: 0, start: 0, count }];
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            let MeshNode { start, count, .. } = self.nodes[index];
+            if count <= MAX_LEAF_TRIANGLES {
+                continue;
+            }
+            let positions = &self.positions;
+            let centroid = |triangle: &Triangle| triangle.vertices.iter().map(|&index| positions[index as usize]).sum::<Vec3>() / 3.0;
+            let triangles = &mut self.triangles[start..start + count];
+            let spread = Aabb::enclosing(triangles.iter().map(|triangle| {
+                let center = centroid(triangle);
+                Aabb::new(center, center)
+            }))
+            .expect("el nodo tiene triángulos")
+            .size();
+            let axis = if spread.x >= spread.y && spread.x >= spread.z { 0 } else if spread.y >= spread.z { 1 } else { 2 };
+            triangles.sort_unstable_by(|a, b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+            let half = count / 2;
+            let left = self.nodes.len();
+            for (start, count) in [(start, half), (start + half, count - half)] {
+                self.nodes.push(MeshNode { bounds: self.bounds(start..start + count), left: 0, start, count });
+            }
+            self.nodes[index].left = left;
+            self.nodes[index].count = 0;
+            pending.extend([left, left + 1]);
+        }
+    }
+}
+
+// Malla de triángulos, por ejemplo la de un modelo importado de glTF (ver `gltf`). Los
+// vértices ya están en el mundo. Cada grupo de triángulos (submalla) tiene su material
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    geometry: Arc<Geometry>,
+    materials: Vec<Arc<Material>>,
+    // Sombreado por caras aunque tenga normales por vértice
+    pub flat: bool,
+}
+
+impl Mesh {
+    // Una submalla por par de triángulos y material; None si no hay triángulos o alguno usa un
+    // vértice que no existe
+    pub fn new(positions: Vec<Vec3>, submeshes: Vec<(Vec<[u32; 3]>, Arc<Material>)>) -> Option<Self> {
+        let mut triangles = Vec::new();
+        let mut materials = Vec::new();
+        for (submesh, (vertices, material)) in submeshes.into_iter().enumerate() {
+            triangles.extend(vertices.into_iter().map(|vertices| Triangle { vertices, submesh: submesh as u32 }));
+            materials.push(material);
+        }
+        if triangles.is_empty() || triangles.iter().flat_map(|triangle| triangle.vertices).any(|index| index as usize >= positions.len()) {
+            return None;
+        }
+        let mut geometry = Geometry { positions, normals: None, uvs: None, triangles, nodes: Vec::new() };
+        geometry.build();
+        Some(Mesh { geometry: Arc::new(geometry), materials, flat: false })
+    }
+
+    // Con una normal por vértice, que se interpola sobre cada triángulo; None si no hay una
+    // por vértice
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Option<Self> {
+        if normals.len() != self.geometry.positions.len() {
+            return None;
+        }
+        let normals = normals.iter().map(|normal| normal.try_normalize(1e-12).unwrap_or_else(Vec3::zeros)).collect();
+        Arc::make_mut(&mut self.geometry).normals = Some(normals);
+        Some(self)
+    }
+
+    // Con coordenadas de textura por vértice (v = 0 arriba de la imagen); None si no hay unas
+    // por vértice
+    pub fn with_uvs(mut self, uvs: Vec<(f32, f32)>) -> Option<Self> {
+        if uvs.len() != self.geometry.positions.len() {
+            return None;
+        }
+        Arc::make_mut(&mut self.geometry).uvs = Some(uvs);
+        Some(self)
+    }
+
+    // Esquinas de cada triángulo, antihorarias vistas desde afuera
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        (0..self.geometry.triangles.len()).map(|triangle| self.geometry.corners(triangle))
+    }
+
+    // Normales de las esquinas de cada triángulo, en el orden de `triangles`: las de los
+    // vértices, o la de la cara si la malla es plana
+    pub fn corner_normals(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        let geometry = &self.geometry;
+        (0..geometry.triangles.len()).map(move |triangle| {
+            let face = geometry.face_normal(triangle);
+            match &geometry.normals {
+                Some(normals) if !self.flat => geometry.triangles[triangle].vertices.map(|index| match normals[index as usize] {
+                    normal if normal == Vec3::zeros() => face,
+                    normal => normal,
+                }),
+                _ => [face; 3],
+            }
+        })
+    }
+
+    // Coordenadas de textura de las esquinas de cada triángulo, en el orden de `triangles`;
+    // (0, 0) si la malla no tiene
+    pub fn corner_uvs(&self) -> impl Iterator<Item = [(f32, f32); 3]> + '_ {
+        let geometry = &self.geometry;
+        geometry.triangles.iter().map(move |triangle| match &geometry.uvs {
+            Some(uvs) => triangle.vertices.map(|index| uvs[index as usize]),
+            None => [(0.0, 0.0); 3],
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.geometry.nodes[0].bounds
+    }
+
+    // Mueve o escala todos los vértices con `map`, que no puede girar ni espejar: así las
+    // cajas del BVH siguen conteniendo a sus triángulos y no hace falta rearmarlo
+    fn map_positions(&mut self, map: impl Fn(&Vec3) -> Vec3) {
+        let geometry = Arc::make_mut(&mut self.geometry);
+        for position in &mut geometry.positions {
+            *position = map(position);
+        }
+        for node in &mut geometry.nodes {
+            node.bounds = Aabb::new(map(&node.bounds.min), map(&node.bounds.max));
+        }
+    }
+}
+
+// Prueba estanca de Woop, Benthin y Wald: los vértices se llevan a un espacio donde el rayo
+// sale del origen por +Z y el cruce se decide con el signo de tres áreas. Dos triángulos que
+// comparten un lado calculan su área con los mismos números, así que un rayo que pasa justo por
+// el lado golpea al menos a uno y no se cuela entre los dos (puntos sueltos en sombras y bordes)
+struct Watertight {
+    // Eje donde la dirección es más larga (`z`) y los otros dos, en el orden que mantiene el
+    // sentido de giro de los triángulos
+    x: usize,
+    y: usize,
+    z: usize,
+    shear: Vec3,
+}
+
+impl Watertight {
+    fn new(ray: &Ray) -> Self {
+        let direction = ray.direction;
+        let z = direction.iamax();
+        let (mut x, mut y) = ((z + 1) % 3, (z + 2) % 3);
+        if direction[z] < 0.0 {
+            std::mem::swap(&mut x, &mut y);
+        }
+        let shear = Vec3::new(direction[x] / direction[z], direction[y] / direction[z], 1.0 / direction[z]);
+        Watertight { x, y, z, shear }
+    }
+
+    // Distancia y coordenadas baricéntricas de `b` y `c` si el rayo cruza el triángulo dentro de
+    // su intervalo, por cualquiera de las dos caras
+    fn intersect(&self, ray: &Ray, corners: &[Vec3; 3]) -> Option<(f32, f32, f32)> {
+        let [a, b, c] = corners.map(|corner| {
+            let corner = corner - ray.origin;
+            Vec3::new(corner[self.x] - self.shear.x * corner[self.z], corner[self.y] - self.shear.y * corner[self.z], self.shear.z * corner[self.z])
+        });
+        let mut u = c.x * b.y - c.y * b.x;
+        let mut v = a.x * c.y - a.y * c.x;
+        let mut w = b.x * a.y - b.y * a.x;
+        // Justo sobre un lado el área en f32 puede dar cero por redondeo; en f64 se sabe de qué
+        // lado cae
+        if u == 0.0 || v == 0.0 || w == 0.0 {
+            let area = |p: &Vec3, q: &Vec3| (p.x as f64 * q.y as f64 - p.y as f64 * q.x as f64) as f32;
+            (u, v, w) = (area(&c, &b), area(&a, &c), area(&b, &a));
+        }
+        if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+            return None;
+        }
+        let determinant = u + v + w;
+        if determinant == 0.0 {
+            return None;
+        }
+        let t = (u * a.z + v * b.z + w * c.z) / determinant;
+        ray.contains(t).then_some((t, v / determinant, w / determinant))
+    }
+}
+
+// La normal interpolada puede mirar hacia el rayo aunque la cara no, o reflejarlo hacia adentro
+// de la superficie en los bordes de la silueta. Se dobla hacia la de la cara (`face`, que mira
+// hacia el rayo) hasta que el reflejo sale por encima: así los reflejos y las refracciones
+// siguen del lado correcto
+fn shading_normal(interpolated: &Vec3, face: &Vec3, direction: &Vec3) -> Vec3 {
+    const STEPS: usize = 8;
+    let interpolated = if interpolated.dot(face) < 0.0 { -interpolated } else { *interpolated };
+    let direction = direction.normalize();
+    (0..STEPS)
+        .map(|step| interpolated.lerp(face, step as f32 / STEPS as f32).normalize())
+        .find(|normal| {
+            let reflected = direction - 2.0 * direction.dot(normal) * normal;
+            direction.dot(normal) < 0.0 && reflected.dot(face) > 0.0
+        })
+        .unwrap_or(*face)
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let geometry = &self.geometry;
+        // Cada impacto acorta el rayo, así que las cajas más lejanas ya no se recorren
+        let mut ray = *ray;
+        let watertight = Watertight::new(&ray);
+        let mut closest = None;
+        buffer_pool::with_stack(|stack| {
+            stack.push(0);
+            while let Some(index) = stack.pop() {
+                let node = &geometry.nodes[index];
+                if !node.bounds.intersects(&ray) {
+                    continue;
+                }
+                if node.count == 0 {
+                    stack.extend([node.left, node.left + 1]);
+                    continue;
+                }
+                for triangle in node.start..node.start + node.count {
+                    if let Some((t, u, v)) = watertight.intersect(&ray, &geometry.corners(triangle)) {
+                        ray.t_max = t;
+                        closest = Some((triangle, t, u, v));
+                    }
+                }
+            }
+        });
+        let Some((triangle, t, u, v)) = closest else { return Intersect::empty() };
+
+        let Triangle { vertices, submesh } = geometry.triangles[triangle];
+        let weights = [1.0 - u - v, u, v];
+        let face = geometry.face_normal(triangle);
+        // Por detrás de la cara es desde adentro de la malla (o del lado de atrás de una abierta)
+        let inside = face.dot(&ray.direction) > 0.0;
+        let normal = match &geometry.normals {
+            Some(normals) if !self.flat => {
+                let corners = vertices.map(|index| match normals[index as usize] {
+                    normal if normal == Vec3::zeros() => face,
+                    normal => normal,
+                });
+                let interpolated = corners.iter().zip(weights).map(|(normal, weight)| normal * weight).sum::<Vec3>();
+                let facing = if inside { -face } else { face };
+                let normal = shading_normal(&interpolated, &facing, &ray.direction);
+                if inside { -normal } else { normal }
+            }
+            _ => face,
+        };
+
+        let material = self.materials[submesh as usize].clone();
+        let Some(uvs) = &geometry.uvs else {
+            let intersect = Intersect::new(ray.at(t), normal, t, material, None);
+            return if inside { intersect.seen_from_inside() } else { intersect };
+        };
+        let [ta, tb, tc] = vertices.map(|index| uvs[index as usize]);
+        let uv = (ta.0 * weights[0] + tb.0 * u + tc.0 * v, ta.1 * weights[0] + tb.1 * u + tc.1 * v);
+        let mut intersect = Intersect::new(ray.at(t), normal, t, material, Some((uv.0.rem_euclid(1.0), uv.1.rem_euclid(1.0))));
+        // Tangentes en las direcciones en que crecen u y v sobre el triángulo; si sus
+        // coordenadas no tienen área no hay ninguna
+        let [a, b, c] = geometry.corners(triangle);
+        let (e1, e2) = (b - a, c - a);
+        let (du1, dv1, du2, dv2) = (tb.0 - ta.0, tb.1 - ta.1, tc.0 - ta.0, tc.1 - ta.1);
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() > 1e-12 {
+            let tangent = (e1 * dv2 - e2 * dv1) / determinant;
+            let bitangent = (e2 * du1 - e1 * du2) / determinant;
+            if let (Some(tangent), Some(bitangent)) = (tangent.try_normalize(1e-12), bitangent.try_normalize(1e-12)) {
+                // Lado del mundo que ocupa una unidad de textura
+                let uv_scale = (e1.cross(&e2).norm() / determinant.abs()).sqrt();
+                intersect = intersect.with_tangents(tangent, bitangent).with_uv_scale(uv_scale);
+            }
+        }
+        if inside { intersect.seen_from_inside() } else { intersect }
+    }
+}
+
+impl SceneObject for Mesh {
+    fn kind(&self) -> &'static str {
+        "mesh"
+    }
+
+    fn center(&self) -> Vec3 {
+        self.bounding_box().center()
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        let offset = center - self.center();
+        self.map_positions(|position| position + offset);
+    }
+
+    fn size(&self) -> f32 {
+        self.bounding_box().size().max()
+    }
+
+    // Escala la malla alrededor de su centro
+    fn set_size(&mut self, size: f32) {
+        let (center, ratio) = (self.center(), size.max(1e-6) / self.size().max(1e-6));
+        self.map_positions(|position| center + (position - center) * ratio);
+    }
+
+    // El de la primera submalla, que es el que editan la consola y los grupos
+    fn material(&self) -> &Material {
+        &self.materials[0]
+    }
+
+    // Si otro objeto comparte el material, este pasa a tener su propia copia
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.materials[0])
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(self.bounding_box())
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(self.clone())
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        obj_export::write_mesh(obj, self, vertex_offset, transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::color::Color;
+
+    fn material() -> Arc<Material> {
+        Arc::new(Material::new(Color::new(200.0, 200.0, 200.0), 10.0, [0.9, 0.1]))
+    }
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray::new(Vec3::from(origin), Vec3::from(direction))
+    }
+
+    // Cuadrado de lado 2 en z = 0 mirando hacia +Z
+    fn square() -> Mesh {
+        let positions = vec![
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+        ];
+        Mesh::new(positions, vec![(vec![[0, 1, 2], [0, 2, 3]], material())]).unwrap()
+    }
+
+    #[test]
+    fn hits_the_front_and_the_back() {
+        let mesh = square();
+        let front = mesh.ray_intersect(&ray([0.3, 0.2, 5.0], [0.0, 0.0, -1.0]));
+        assert!(front.is_intersecting && !front.inside);
+        assert!((front.distance - 5.0).abs() < 1e-5);
+        assert_eq!(front.normal, Vec3::z());
+
+        let back = mesh.ray_intersect(&ray([0.3, 0.2, -5.0], [0.0, 0.0, 1.0]));
+        assert!(back.is_intersecting && back.inside);
+        assert_eq!(back.normal, -Vec3::z());
+
+        assert!(!mesh.ray_intersect(&ray([1.5, 0.0, 5.0], [0.0, 0.0, -1.0])).is_intersecting);
+    }
+
+    #[test]
+    fn rejects_missing_vertices() {
+        assert!(Mesh::new(vec![Vec3::zeros(); 2], vec![(vec![[0, 1, 2]], material())]).is_none());
+        assert!(Mesh::new(Vec::new(), vec![(Vec::new(), material())]).is_none());
+    }
+
+    // El BVH encuentra el mismo impacto que probar todos los triángulos
+    #[test]
+    fn bvh_finds_the_nearest_triangle() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut point = || Vec3::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0));
+        let positions: Vec<Vec3> = (0..300).map(|_| point()).collect();
+        let triangles: Vec<[u32; 3]> = (0..100).map(|index| [3 * index, 3 * index + 1, 3 * index + 2]).collect();
+        let mesh = Mesh::new(positions, vec![(triangles, material())]).unwrap();
+        for _ in 0..200 {
+            let (origin, target) = (point() * 3.0, point());
+            let ray = Ray::new(origin, (target - origin).normalize());
+            let nearest = mesh
+                .triangles()
+                .filter_map(|corners| Watertight::new(&ray).intersect(&ray, &corners))
+                .map(|(t, _, _)| t)
+                .reduce(f32::min);
+            let hit = mesh.ray_intersect(&ray);
+            assert_eq!(hit.is_intersecting, nearest.is_some());
+            if let Some(t) = nearest {
+                assert!((hit.distance - t).abs() < 1e-4);
+            }
+        }
+    }
+
+    // Rayos justo sobre los lados y el vértice que comparten los triángulos de un abanico
+    // inclinado: ninguno pasa entre ellos
+    #[test]
+    fn rays_through_shared_edges_do_not_leak() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let mut point = || Vec3::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0));
+            let center = point();
+            let ring: Vec<Vec3> = (0..6)
+                .map(|step| {
+                    let angle = step as f32 * std::f32::consts::TAU / 6.0;
+                    center + Vec3::new(angle.cos() * 0.7, angle.sin() * 0.5, angle.sin() * 0.3 + angle.cos() * 0.2)
+                })
+                .collect();
+            let mut positions = vec![center];
+            positions.extend(&ring);
+            let triangles = (0..6).map(|step| [0, 1 + step, 1 + (step + 1) % 6]).collect();
+            let mesh = Mesh::new(positions, vec![(triangles, material())]).unwrap();
+            let origin = point() * 4.0;
+            for target in ring.iter().map(|corner| center + (corner - center) * 0.37).chain([center]) {
+                let ray = Ray::new(origin, (target - origin).normalize());
+                assert!(mesh.ray_intersect(&ray).is_intersecting, "{:?} -> {:?}", origin, target);
+            }
+        }
+    }
+
+    // Normales hacia afuera en los vértices de abajo y hacia arriba en los de arriba
+    fn bent_square() -> Mesh {
+        let normals = vec![
+            Vec3::new(0.0, -1.0, 1.0),
+            Vec3::new(0.0, -1.0, 1.0),
+            Vec3::new(0.0, 1.0, 1.0),
+            Vec3::new(0.0, 1.0, 1.0),
+        ];
+        square().with_normals(normals).unwrap()
+    }
+
+    #[test]
+    fn interpolates_vertex_normals_unless_flat() {
+        let mut mesh = bent_square();
+        let hit = |mesh: &Mesh, y: f32| mesh.ray_intersect(&ray([0.0, y, 5.0], [0.0, 0.0, -1.0])).normal;
+        assert!((hit(&mesh, 0.0) - Vec3::z()).norm() < 1e-5);
+        let low = hit(&mesh, -0.5);
+        assert!(low.y < -0.3 && low.z > 0.0, "{:?}", low);
+        // Desde atrás la normal interpolada también se da vuelta hacia el rayo
+        let back = mesh.ray_intersect(&ray([0.0, -0.5, -5.0], [0.0, 0.0, 1.0])).normal;
+        assert!(back.y > 0.3 && back.z < 0.0, "{:?}", back);
+
+        mesh.flat = true;
+        assert_eq!(hit(&mesh, -0.5), Vec3::z());
+        assert!(square().with_normals(vec![Vec3::z(); 3]).is_none());
+    }
+
+    // Un rayo rasante no se refleja hacia adentro de la cara ni se encuentra con la normal de espaldas
+    #[test]
+    fn grazing_reflections_stay_above_the_surface() {
+        let mesh = bent_square();
+        let direction = Vec3::new(0.0, -0.95, -0.3).normalize();
+        let hit = mesh.ray_intersect(&Ray::new(Vec3::new(0.0, -0.5, 0.0) - direction * 2.0, direction));
+        assert!(hit.is_intersecting);
+        let reflected = direction - 2.0 * direction.dot(&hit.normal) * hit.normal;
+        assert!(direction.dot(&hit.normal) < 0.0);
+        assert!(reflected.z > 0.0, "{:?}", reflected);
+    }
+
+    // La textura va derecha vista desde +Z y cada triángulo del cuadrado tiene su material
+    #[test]
+    fn reports_uvs_tangents_and_submesh_materials() {
+        let red = Arc::new(Material::new(Color::new(255.0, 0.0, 0.0), 10.0, [0.9, 0.1]));
+        let positions = vec![
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+        ];
+        let mesh = Mesh::new(positions, vec![(vec![[0, 1, 2]], red), (vec![[0, 2, 3]], material())])
+            .unwrap()
+            .with_uvs(vec![(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)])
+            .unwrap();
+
+        let hit = mesh.ray_intersect(&ray([0.5, -0.5, 5.0], [0.0, 0.0, -1.0]));
+        let (u, v) = hit.uv.unwrap();
+        assert!((u - 0.75).abs() < 1e-5 && (v - 0.75).abs() < 1e-5, "{:?}", hit.uv);
+        assert!((hit.tangent - Vec3::x()).norm() < 1e-5 && (hit.bitangent + Vec3::y()).norm() < 1e-5);
+        assert!((hit.uv_scale - 2.0).abs() < 1e-5);
+        assert_eq!(hit.material.diffuse, Color::new(255.0, 0.0, 0.0));
+
+        let other = mesh.ray_intersect(&ray([-0.5, 0.5, 5.0], [0.0, 0.0, -1.0]));
+        assert_eq!(other.material.diffuse, Color::new(200.0, 200.0, 200.0));
+        assert!(mesh.clone().with_uvs(vec![(0.0, 0.0); 3]).is_none());
+        // Sin UV no hay coordenadas que muestrear
+        assert!(square().ray_intersect(&ray([0.5, -0.5, 5.0], [0.0, 0.0, -1.0])).uv.is_none());
+    }
+
+    #[test]
+    fn moving_and_scaling_keep_the_bvh() {
+        let mut mesh = square();
+        mesh.set_center(Vec3::new(0.0, 0.0, -3.0));
+        mesh.set_size(4.0);
+        let hit = mesh.ray_intersect(&ray([1.8, 1.8, 5.0], [0.0, 0.0, -1.0]));
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 8.0).abs() < 1e-5);
+        // La copia de antes de editar sigue en su lugar
+        assert!((square().ray_intersect(&ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0])).distance - 5.0).abs() < 1e-5);
+    }
+}
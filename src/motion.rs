@@ -0,0 +1,65 @@
+// motion.rs
+
+use std::path::Path;
+
+use image::{ImageBuffer, ImageResult, Rgb};
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::object::Object;
+use crate::ray::Ray;
+use crate::scene_intersect;
+
+// Distancia a la que se proyectan los rayos que no golpean nada (el cielo)
+const SKY_DISTANCE: f32 = 1.0e4;
+
+// Velocidad en pantalla de cada pixel (en pixeles) entre el cuadro anterior y el actual, por
+// el giro de la cámara y por la velocidad de los objetos que se mueven
+pub struct MotionBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub vectors: Vec<(f32, f32)>,
+}
+
+impl MotionBuffer {
+    pub fn compute(objects: &[Object], camera: &Camera, previous: &Camera, width: usize, height: usize) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let mut vectors = vec![(0.0, 0.0); width * height];
+
+        vectors
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, vector) in row.iter_mut().enumerate() {
+                    let (px, py) = (x as f32, y as f32);
+                    let direction = camera.primary_ray_direction(px, py, w, h);
+                    let intersect = scene_intersect(&Ray::new(camera.position, direction), objects);
+                    // Un objeto que se mueve estaba un cuadro atrás en su recorrido
+                    let world_point = if intersect.is_intersecting {
+                        let velocity = intersect.object.map_or(Vec3::zeros(), |index| objects[index].velocity());
+                        intersect.point - velocity
+                    } else {
+                        camera.position + direction * SKY_DISTANCE
+                    };
+
+                    // Sin posición previa válida (detrás de la cámara) se reporta movimiento nulo
+                    if let Some((prev_x, prev_y)) = previous.project(&world_point, w, h) {
+                        *vector = (px - prev_x, py - prev_y);
+                    }
+                }
+            });
+
+        MotionBuffer { width, height, vectors }
+    }
+
+    // EXR en punto flotante: R = dx, G = dy (pixeles), B = 0
+    pub fn save_exr(&self, path: &Path) -> ImageResult<()> {
+        let image: ImageBuffer<Rgb<f32>, Vec<f32>> =
+            ImageBuffer::from_fn(self.width as u32, self.height as u32, |x, y| {
+                let (dx, dy) = self.vectors[y as usize * self.width + x as usize];
+                Rgb([dx, dy, 0.0])
+            });
+        image.save_with_format(path, image::ImageFormat::OpenExr)
+    }
+}
@@ -0,0 +1,84 @@
+// moving.rs
+
+use std::io::{self, Write};
+
+use nalgebra_glm::{Mat3, Vec3};
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::obj_export::ObjTransform;
+use crate::object::{Object, SceneObject};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Objeto que se desplaza `velocity` durante un cuadro: está en su posición al abrir el
+// obturador y en posición + velocity al cerrarlo. Cada rayo lo ve donde estaba en su
+// instante, así que al promediar muestras queda la estela del desenfoque de movimiento
+pub struct Moving {
+    pub object: Object,
+    pub velocity: Vec3,
+}
+
+impl RayIntersect for Moving {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        // En vez de mover el objeto se mueve el rayo en sentido contrario
+        let offset = self.velocity * ray.time;
+        let local_ray = Ray { origin: ray.origin - offset, ..*ray };
+        let mut intersect = self.object.ray_intersect(&local_ray);
+        intersect.point += offset;
+        intersect
+    }
+}
+
+impl SceneObject for Moving {
+    fn kind(&self) -> &'static str {
+        self.object.kind()
+    }
+
+    fn center(&self) -> Vec3 {
+        self.object.center()
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.object.set_center(center);
+    }
+
+    fn size(&self) -> f32 {
+        self.object.size()
+    }
+
+    fn set_size(&mut self, size: f32) {
+        self.object.set_size(size);
+    }
+
+    fn material(&self) -> &Material {
+        self.object.material()
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        self.object.material_mut()
+    }
+
+    // Todo el recorrido del cuadro, para que el BVH no lo descarte en ningún instante
+    fn bounds(&self) -> Option<Aabb> {
+        let start = self.object.bounds()?;
+        let end = Aabb::new(start.min + self.velocity, start.max + self.velocity);
+        Some(start.union(&end))
+    }
+
+    fn linear(&self) -> Mat3 {
+        self.object.linear()
+    }
+
+    fn velocity(&self) -> Vec3 {
+        self.velocity + self.object.velocity()
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(Moving { object: self.object.box_clone(), velocity: self.velocity })
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        self.object.write_obj(obj, vertex_offset, transform)
+    }
+}
@@ -0,0 +1,22 @@
+// normal_map.rs
+
+use image::{DynamicImage, GenericImageView};
+use nalgebra_glm::Vec3;
+
+use crate::ray_intersect::Intersect;
+
+// Normal del mapa en (u, v) llevada al mundo. El mapa está en espacio tangente al estilo
+// OpenGL: rojo = derecha de la imagen (+u), verde = arriba de la imagen y azul = afuera.
+// Las filas de la imagen crecen con v, así que "arriba" es la dirección opuesta a la bitangente
+pub fn perturb(normal_map: &DynamicImage, (u, v): (f32, f32), intersect: &Intersect) -> Vec3 {
+    let (w, h) = normal_map.dimensions();
+    let x = (u.clamp(0.0, 1.0) * (w - 1) as f32) as u32;
+    let y = (v.clamp(0.0, 1.0) * (h - 1) as f32) as u32;
+    let pixel = normal_map.get_pixel(x, y);
+    let [nx, ny, nz] = [pixel[0], pixel[1], pixel[2]].map(|channel| channel as f32 / 255.0 * 2.0 - 1.0);
+
+    let tangent = intersect.tangent.normalize();
+    let bitangent = intersect.bitangent.normalize();
+    let perturbed = tangent * nx - bitangent * ny + intersect.normal * nz.max(0.0);
+    if perturbed.norm() < 1e-6 { intersect.normal } else { perturbed.normalize() }
+}
@@ -0,0 +1,312 @@
+// obj_export.rs
+
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use nalgebra_glm::{Mat3, Vec3};
+
+use crate::block::Block;
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::object::Object;
+use crate::plane::Plane;
+use crate::sphere::Sphere;
+
+// Ejes (normal, u, v) de cada cara, con el mismo mapeo UV que usa `Cube::ray_intersect`
+const FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+];
+
+const FACE_UVS: [(f32, f32); 4] = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+// Resolución de la malla de las esferas (paralelos y meridianos)
+const SPHERE_STACKS: usize = 16;
+const SPHERE_SLICES: usize = 32;
+
+// Transformación afín de lo que se escribe: la de los `Transformed` que contienen al
+// objeto, ya compuestas; la identidad para los objetos sueltos
+#[derive(Debug, Clone, Copy)]
+pub struct ObjTransform {
+    linear: Mat3,
+    translation: Vec3,
+    // Inversa transpuesta de `linear`, para las normales
+    normal: Mat3,
+}
+
+impl ObjTransform {
+    pub fn identity() -> Self {
+        ObjTransform { linear: Mat3::identity(), translation: Vec3::zeros(), normal: Mat3::identity() }
+    }
+
+    // Esta transformación aplicada después de `linear · p + translation`; `inverse` es la
+    // inversa de `linear`, que el objeto transformado ya tiene
+    pub fn after(&self, linear: &Mat3, inverse: &Mat3, translation: &Vec3) -> Self {
+        ObjTransform {
+            linear: self.linear * linear,
+            translation: self.point(translation),
+            normal: self.normal * inverse.transpose(),
+        }
+    }
+
+    pub fn point(&self, point: &Vec3) -> Vec3 {
+        self.linear * point + self.translation
+    }
+
+    pub fn normal(&self, normal: &Vec3) -> Vec3 {
+        (self.normal * normal).normalize()
+    }
+}
+
+// Un vértice con su coordenada de textura y su normal, ya transformados
+fn write_vertex(obj: &mut dyn Write, transform: &ObjTransform, position: &Vec3, uv: (f32, f32), normal: &Vec3) -> io::Result<()> {
+    let (position, normal) = (transform.point(position), transform.normal(normal));
+    writeln!(obj, "v {} {} {}", position.x, position.y, position.z)?;
+    writeln!(obj, "vt {} {}", uv.0, uv.1)?;
+    writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z)
+}
+
+// Escribe la escena como OBJ + MTL (el .mtl queda junto al .obj con el mismo nombre)
+pub fn export_obj(objects: &[Object], path: &Path) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("scene.mtl")
+        .to_string();
+
+    let mut obj = BufWriter::new(File::create(path)?);
+    let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+
+    writeln!(obj, "# Exportado por cubito")?;
+    writeln!(obj, "mtllib {}", mtl_name)?;
+
+    // Los índices de OBJ empiezan en 1 y son globales al archivo
+    let mut vertex_offset = 1;
+    for (i, object) in objects.iter().enumerate() {
+        let material_name = format!("material_{}", i);
+        write_material(&mut mtl, &material_name, object.material())?;
+
+        writeln!(obj, "o {}_{}", object.kind(), i)?;
+        writeln!(obj, "usemtl {}", material_name)?;
+        vertex_offset = object.write_obj(&mut obj, vertex_offset, &ObjTransform::identity())?;
+    }
+
+    obj.flush()?;
+    mtl.flush()
+}
+
+pub fn write_cube(obj: &mut dyn Write, cube: &Cube, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+    let half_size = cube.size / 2.0;
+
+    for (normal, u_axis, v_axis) in FACES {
+        let normal = Vec3::from(normal);
+        let u_axis = Vec3::from(u_axis);
+        let v_axis = Vec3::from(v_axis);
+
+        for (u, v) in FACE_UVS {
+            let position = cube.center
+                + normal * half_size
+                + u_axis * ((u - 0.5) * cube.size)
+                + v_axis * ((v - 0.5) * cube.size);
+            // Una normal por vértice para que los índices v/vt/vn coincidan con los de las esferas
+            write_vertex(obj, transform, &position, (u, v), &normal)?;
+        }
+    }
+
+    write_box_faces(obj, vertex_offset)
+}
+
+// Caras de los 4 vértices por cara que dejan `write_cube` y `write_block`, en el orden de FACES
+fn write_box_faces(obj: &mut dyn Write, vertex_offset: usize) -> io::Result<usize> {
+    for (face, (normal, u_axis, v_axis)) in FACES.iter().enumerate() {
+        let base = vertex_offset + face * 4;
+        let mut corners = [base, base + 1, base + 2, base + 3];
+
+        // OBJ espera las caras en sentido antihorario vistas desde afuera
+        if Vec3::from(*u_axis).cross(&Vec3::from(*v_axis)).dot(&Vec3::from(*normal)) < 0.0 {
+            corners.reverse();
+        }
+
+        write!(obj, "f")?;
+        for corner in corners {
+            write!(obj, " {0}/{0}/{0}", corner)?;
+        }
+        writeln!(obj)?;
+    }
+
+    Ok(vertex_offset + FACES.len() * 4)
+}
+
+// Cada caja del bloque como un cubo aparte; las UV se miden sobre la celda completa,
+// igual que en `Block::ray_intersect`
+pub fn write_block(obj: &mut dyn Write, block: &Block, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+    let cell_min = block.center - Vec3::new(block.size, block.size, block.size) / 2.0;
+    let mut offset = vertex_offset;
+    for part in block.parts() {
+        let (center, size) = (part.center(), part.size());
+        for (normal, u_axis, v_axis) in FACES {
+            let normal = Vec3::from(normal);
+            let u_axis = Vec3::from(u_axis);
+            let v_axis = Vec3::from(v_axis);
+
+            for (u, v) in FACE_UVS {
+                let position = center
+                    + normal.component_mul(&size) / 2.0
+                    + u_axis.component_mul(&size) * (u - 0.5)
+                    + v_axis.component_mul(&size) * (v - 0.5);
+                let cell = position - cell_min;
+                let uv = (cell.dot(&u_axis) / block.size, cell.dot(&v_axis) / block.size);
+                write_vertex(obj, transform, &position, uv, &normal)?;
+            }
+        }
+        offset = write_box_faces(obj, offset)?;
+    }
+    Ok(offset)
+}
+
+// Malla de latitud y longitud con el mismo mapeo UV que `Sphere::ray_intersect`;
+// la costura y los polos repiten vértices para que las UV no se mezclen
+pub fn write_sphere(obj: &mut dyn Write, sphere: &Sphere, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+    for stack in 0..=SPHERE_STACKS {
+        let v = stack as f32 / SPHERE_STACKS as f32;
+        let latitude = (v - 0.5) * PI;
+        for slice in 0..=SPHERE_SLICES {
+            let u = slice as f32 / SPHERE_SLICES as f32;
+            let longitude = (u - 0.5) * 2.0 * PI;
+            let normal = Vec3::new(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                latitude.cos() * longitude.sin(),
+            );
+            let position = sphere.center + normal * sphere.radius;
+            write_vertex(obj, transform, &position, (u, v), &normal)?;
+        }
+    }
+
+    let row = SPHERE_SLICES + 1;
+    for stack in 0..SPHERE_STACKS {
+        for slice in 0..SPHERE_SLICES {
+            let a = vertex_offset + stack * row + slice;
+            let b = a + row;
+            // Antihorario visto desde afuera: u crece hacia +Z alrededor de Y
+            for corner in [[a, b, b + 1], [a, b + 1, a + 1]] {
+                writeln!(
+                    obj,
+                    "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}",
+                    corner[0], corner[1], corner[2]
+                )?;
+            }
+        }
+    }
+
+    Ok(vertex_offset + (SPHERE_STACKS + 1) * row)
+}
+
+// OBJ no admite superficies infinitas: el plano se exporta como un cuadrado
+// de PLANE_EXPORT_TILES baldosas por lado centrado en su punto
+const PLANE_EXPORT_TILES: f32 = 50.0;
+
+pub fn write_plane(obj: &mut dyn Write, plane: &Plane, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+    let half = plane.tile_size * PLANE_EXPORT_TILES / 2.0;
+    let (tangent, bitangent) = plane.tangents();
+    for (u, v) in FACE_UVS {
+        let position = plane.point + tangent * ((u * 2.0 - 1.0) * half) + bitangent * ((v * 2.0 - 1.0) * half);
+        write_vertex(obj, transform, &position, (u * PLANE_EXPORT_TILES, v * PLANE_EXPORT_TILES), &plane.normal)?;
+    }
+
+    // tangente × bitangente apunta contra la normal, así que se recorre al revés
+    let corners = [vertex_offset + 3, vertex_offset + 2, vertex_offset + 1, vertex_offset];
+    write!(obj, "f")?;
+    for corner in corners {
+        write!(obj, " {0}/{0}/{0}", corner)?;
+    }
+    writeln!(obj)?;
+
+    Ok(vertex_offset + 4)
+}
+
+// Cada triángulo con sus tres vértices, sus UV y sus normales (las de la cara si es plana).
+// Todas las submallas van con el material del objeto
+pub fn write_mesh(obj: &mut dyn Write, mesh: &Mesh, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+    let mut next = vertex_offset;
+    for ((corners, normals), uvs) in mesh.triangles().zip(mesh.corner_normals()).zip(mesh.corner_uvs()) {
+        for ((corner, normal), uv) in corners.iter().zip(&normals).zip(uvs) {
+            write_vertex(obj, transform, corner, uv, normal)?;
+        }
+        writeln!(obj, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", next, next + 1, next + 2)?;
+        next += 3;
+    }
+    Ok(next)
+}
+
+fn write_material(mtl: &mut impl Write, name: &str, material: &Material) -> io::Result<()> {
+    let diffuse = material.diffuse * (material.albedo[0] / 255.0);
+    let specular = material.albedo[1];
+
+    writeln!(mtl, "newmtl {}", name)?;
+    writeln!(mtl, "Ka {} {} {}", diffuse.r * 0.3, diffuse.g * 0.3, diffuse.b * 0.3)?;
+    writeln!(mtl, "Kd {} {} {}", diffuse.r, diffuse.g, diffuse.b)?;
+    writeln!(mtl, "Ks {} {} {}", specular, specular, specular)?;
+    writeln!(mtl, "Ns {}", material.specular)?;
+    // illum 3: reflexión trazada para los cristales, 2: Phong normal
+    writeln!(mtl, "illum {}", if material.is_crystal { 3 } else { 2 })?;
+    if let Some(texture_path) = &material.texture_path {
+        writeln!(mtl, "map_Kd {}", texture_path)?;
+    }
+    writeln!(mtl)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::color::Color;
+    use crate::console;
+    use crate::scene_graph::SceneGraph;
+    use crate::transformed::Transformed;
+
+    // Los vértices de un objeto transformado salen en el mundo, y `export` escribe lo que
+    // hay en la consola en ese momento
+    #[test]
+    fn console_export_writes_transformed_vertices() {
+        let cube = Cube { center: Vec3::zeros(), size: 1.0, material: Arc::new(Material::new(Color::black(), 0.0, [1.0, 0.0])) };
+        let quarter_turn = nalgebra_glm::rotation(std::f32::consts::FRAC_PI_2, &Vec3::y());
+        let linear = Mat3::from_fn(|row, column| quarter_turn[(row, column)]) * 2.0;
+        let mut objects: Vec<Object> = vec![Box::new(Transformed::new(Box::new(cube) as Object, Vec3::new(3.0, 0.0, 0.0), linear).unwrap())];
+
+        let path = std::env::temp_dir().join(format!("cubito-export-{}.obj", std::process::id()));
+        let command = format!("export {}", path.display());
+        let result = console::execute(&command, &mut objects, &mut [], &mut Vec::new(), &mut SceneGraph::default());
+        let text = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(path.with_extension("mtl")).ok();
+        result.unwrap();
+
+        let vectors = |tag: &str| -> Vec<Vec3> {
+            let lines = text.as_ref().unwrap().lines();
+            let values = lines.filter_map(|line| line.strip_prefix(tag)).map(|rest| {
+                let values: Vec<f32> = rest.split_whitespace().map(|value| value.parse().unwrap()).collect();
+                Vec3::new(values[0], values[1], values[2])
+            });
+            values.collect()
+        };
+        let (positions, normals) = (vectors("v "), vectors("vn "));
+        assert_eq!(positions.len(), 24);
+        for position in positions {
+            assert!((2.0 - 1e-4..=4.0 + 1e-4).contains(&position.x), "{:?}", position);
+            assert!(position.y.abs() <= 1.0 + 1e-4 && position.z.abs() <= 1.0 + 1e-4, "{:?}", position);
+        }
+        // La cara +X del cubo mira hacia -Z después del cuarto de vuelta
+        assert!((normals[0] - Vec3::new(0.0, 0.0, -1.0)).norm() < 1e-4, "{:?}", normals[0]);
+        assert!(normals.iter().all(|normal| (normal.norm() - 1.0).abs() < 1e-4));
+    }
+}
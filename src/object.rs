@@ -0,0 +1,58 @@
+// object.rs
+
+use std::io::{self, Write};
+
+use nalgebra_glm::{Mat3, Vec3};
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::obj_export::ObjTransform;
+use crate::ray_intersect::RayIntersect;
+
+// Lo que necesita la escena de cualquier primitiva además de intersectarla:
+// posición y tamaño editables, material y exportación. Para agregar una primitiva
+// basta implementar este trait; el renderer solo usa `ray_intersect`
+pub trait SceneObject: RayIntersect + Send + Sync {
+    // Nombre del tipo, para el OBJ exportado y los listados
+    fn kind(&self) -> &'static str;
+
+    fn center(&self) -> Vec3;
+    fn set_center(&mut self, center: Vec3);
+
+    // Lado de la caja que lo contiene (el diámetro en una esfera)
+    fn size(&self) -> f32;
+    fn set_size(&mut self, size: f32);
+
+    fn material(&self) -> &Material;
+    fn material_mut(&mut self) -> &mut Material;
+
+    // Caja que lo contiene, o None si es infinito (como un plano)
+    fn bounds(&self) -> Option<Aabb> {
+        let half = Vec3::new(self.size() / 2.0, self.size() / 2.0, self.size() / 2.0);
+        Some(Aabb::new(self.center() - half, self.center() + half))
+    }
+
+    // Centro y lado si es un cubo común, alineado a los ejes: el BVH los guarda aparte y los
+    // prueba sin armar el impacto (ver `Bvh::traverse`)
+    fn solid_cube(&self) -> Option<(Vec3, f32)> {
+        None
+    }
+
+    // Giro y escala respecto de los ejes del mundo; la identidad salvo en los transformados
+    fn linear(&self) -> Mat3 {
+        Mat3::identity()
+    }
+
+    // Desplazamiento durante un cuadro; cero salvo en los que se mueven
+    fn velocity(&self) -> Vec3 {
+        Vec3::zeros()
+    }
+
+    fn box_clone(&self) -> Object;
+
+    // Escribe vértices y caras en el OBJ, pasados por `transform`; devuelve el siguiente
+    // índice de vértice libre
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize>;
+}
+
+pub type Object = Box<dyn SceneObject>;
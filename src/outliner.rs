@@ -0,0 +1,122 @@
+// outliner.rs
+
+use minifb::{Key, KeyRepeat, Window};
+
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::group::Group;
+use crate::light::Light;
+use crate::object::Object;
+
+const PANEL_WIDTH: usize = 170;
+const ROW_HEIGHT: usize = font::GLYPH_HEIGHT + 3;
+const INDENT: usize = 8;
+const PANEL_COLOR: u32 = 0x202020;
+const SELECTED_COLOR: u32 = 0x3A5A8C;
+const HEADER_COLOR: u32 = 0xA0A0A0;
+const ROW_COLOR: u32 = 0xFFFFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Object(usize),
+    Light(usize),
+}
+
+struct Row {
+    depth: usize,
+    text: String,
+    selection: Option<Selection>,
+}
+
+// Lista de la escena: los grupos con sus miembros, los objetos sueltos y las luces.
+// Las flechas mueven la selección entre las filas seleccionables
+#[derive(Default)]
+pub struct Outliner {
+    pub open: bool,
+    cursor: usize,
+}
+
+fn rows(objects: &[Object], lights: &[Light], groups: &[Group]) -> Vec<Row> {
+    let object_row = |index: usize, depth: usize| {
+        let object = &objects[index];
+        let center = object.center();
+        Row {
+            depth,
+            text: format!("{} {} {:.1} {:.1} {:.1}", index, object.kind(), center.x, center.y, center.z),
+            selection: Some(Selection::Object(index)),
+        }
+    };
+
+    let mut rows = vec![Row { depth: 0, text: format!("objetos ({})", objects.len()), selection: None }];
+    for group in groups {
+        rows.push(Row { depth: 1, text: format!("grupo {}", group.name), selection: None });
+        rows.extend(group.members.iter().filter(|&&index| index < objects.len()).map(|&index| object_row(index, 2)));
+    }
+    let grouped = |index: &usize| groups.iter().any(|group| group.members.contains(index));
+    rows.extend((0..objects.len()).filter(|index| !grouped(index)).map(|index| object_row(index, 1)));
+
+    rows.push(Row { depth: 0, text: format!("luces ({})", lights.len()), selection: None });
+    rows.extend(lights.iter().enumerate().map(|(index, light)| Row {
+        depth: 1,
+        text: format!(
+            "{} luz {:.1} {:.1} {:.1}",
+            index, light.position.x, light.position.y, light.position.z
+        ),
+        selection: Some(Selection::Light(index)),
+    }));
+    rows
+}
+
+impl Outliner {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    // Arriba y abajo recorren las filas seleccionables
+    pub fn update(&mut self, window: &Window, objects: &[Object], lights: &[Light], groups: &[Group]) {
+        let selectable = rows(objects, lights, groups).iter().filter(|row| row.selection.is_some()).count();
+        if selectable == 0 {
+            return;
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            self.cursor += 1;
+        }
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            self.cursor = self.cursor.checked_sub(1).unwrap_or(selectable - 1);
+        }
+        self.cursor %= selectable;
+    }
+
+    pub fn selection(&self, objects: &[Object], lights: &[Light], groups: &[Group]) -> Option<Selection> {
+        rows(objects, lights, groups).iter().filter_map(|row| row.selection).nth(self.cursor)
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, objects: &[Object], lights: &[Light], groups: &[Group]) {
+        let rows = rows(objects, lights, groups);
+        let selected = self.selection(objects, lights, groups);
+
+        // Si no caben todas las filas, se desplaza la lista para que la seleccionada quede visible
+        let visible = (framebuffer.height.saturating_sub(4) / ROW_HEIGHT).max(1);
+        let selected_row = rows.iter().position(|row| row.selection.is_some() && row.selection == selected).unwrap_or(0);
+        let first = selected_row.saturating_sub(visible - 1).min(rows.len().saturating_sub(visible));
+
+        let height = (rows.len() - first).min(visible) * ROW_HEIGHT + 4;
+        font::fill_rect(framebuffer, 0, 0, PANEL_WIDTH, height, PANEL_COLOR);
+        for (i, row) in rows.iter().skip(first).take(visible).enumerate() {
+            let y = 2 + i * ROW_HEIGHT;
+            let color = match row.selection {
+                Some(_) if row.selection == selected => {
+                    font::fill_rect(framebuffer, 0, y, PANEL_WIDTH, ROW_HEIGHT, SELECTED_COLOR);
+                    ROW_COLOR
+                }
+                Some(_) => ROW_COLOR,
+                None => HEADER_COLOR,
+            };
+            // Recorta el texto al ancho del panel
+            let x = 3 + row.depth * INDENT;
+            let max_chars = PANEL_WIDTH.saturating_sub(x) / font::GLYPH_ADVANCE;
+            let text: String = row.text.chars().take(max_chars).collect();
+            font::draw_text(framebuffer, x, y + 1, &text, color);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// parallax.rs
+
+use image::{DynamicImage, GenericImageView};
+use nalgebra_glm::Vec3;
+
+use crate::ray_intersect::Intersect;
+
+const MIN_LAYERS: f32 = 8.0;
+const MAX_LAYERS: f32 = 32.0;
+const SHADOW_LAYERS: usize = 16;
+
+// Profundidad en [0, 1] (0 = superficie) a partir de la luminancia del mapa de alturas
+fn sample_depth(height_map: &DynamicImage, u: f32, v: f32) -> f32 {
+    let (w, h) = height_map.dimensions();
+    let x = (u.clamp(0.0, 1.0) * (w - 1) as f32) as u32;
+    let y = (v.clamp(0.0, 1.0) * (h - 1) as f32) as u32;
+    let pixel = height_map.get_pixel(x, y);
+    let height = (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32) / 255.0;
+    1.0 - height
+}
+
+fn to_tangent_space(vector: &Vec3, intersect: &Intersect) -> Vec3 {
+    Vec3::new(
+        vector.dot(&intersect.tangent),
+        vector.dot(&intersect.bitangent),
+        vector.dot(&intersect.normal),
+    )
+}
+
+// Parallax occlusion mapping: avanza desde la UV del impacto en dirección contraria a la vista
+// hasta cruzar el relieve. Devuelve la UV desplazada y la profundidad en ese punto.
+pub fn occlusion_uv(
+    height_map: &DynamicImage,
+    uv: (f32, f32),
+    view_dir: &Vec3,
+    intersect: &Intersect,
+    height_scale: f32,
+) -> ((f32, f32), f32) {
+    let view = to_tangent_space(view_dir, intersect);
+    if view.z <= 1e-4 {
+        return (uv, 0.0);
+    }
+
+    // Más capas cuanto más rasante es la mirada
+    let layers = MIN_LAYERS + (MAX_LAYERS - MIN_LAYERS) * (1.0 - view.z);
+    let layer_step = 1.0 / layers;
+    let shift_u = view.x / view.z * height_scale * layer_step;
+    let shift_v = view.y / view.z * height_scale * layer_step;
+
+    let (mut u, mut v) = uv;
+    let mut layer_depth = 0.0;
+    let mut depth = sample_depth(height_map, u, v);
+    let mut previous = (u, v, layer_depth, depth);
+
+    while layer_depth < depth && layer_depth < 1.0 {
+        previous = (u, v, layer_depth, depth);
+        u -= shift_u;
+        v -= shift_v;
+        layer_depth += layer_step;
+        depth = sample_depth(height_map, u, v);
+    }
+
+    // Interpolar entre la última capa sobre el relieve y la primera debajo
+    let (prev_u, prev_v, prev_layer, prev_depth) = previous;
+    let after = depth - layer_depth;
+    let before = prev_depth - prev_layer;
+    let weight = if (after - before).abs() > 1e-6 { after / (after - before) } else { 0.0 };
+
+    let final_u = u * (1.0 - weight) + prev_u * weight;
+    let final_v = v * (1.0 - weight) + prev_v * weight;
+    ((final_u, final_v), layer_depth * (1.0 - weight) + prev_layer * weight)
+}
+
+// Fracción de luz (0 a 1) que llega al punto del relieve sin chocar con el propio relieve
+pub fn self_shadow(
+    height_map: &DynamicImage,
+    uv: (f32, f32),
+    depth: f32,
+    light_dir: &Vec3,
+    intersect: &Intersect,
+    height_scale: f32,
+) -> f32 {
+    let light = to_tangent_space(light_dir, intersect);
+    if light.z <= 0.0 || depth <= 0.0 {
+        return 1.0;
+    }
+
+    let layer_step = depth / SHADOW_LAYERS as f32;
+    let shift_u = light.x / light.z * height_scale * layer_step;
+    let shift_v = light.y / light.z * height_scale * layer_step;
+
+    let mut blocked = 0;
+    for i in 1..=SHADOW_LAYERS {
+        let layer_depth = depth - layer_step * i as f32;
+        let u = uv.0 + shift_u * i as f32;
+        let v = uv.1 + shift_v * i as f32;
+        if sample_depth(height_map, u, v) < layer_depth {
+            blocked += 1;
+        }
+    }
+
+    1.0 - (blocked as f32 / SHADOW_LAYERS as f32).min(1.0)
+}
@@ -0,0 +1,226 @@
+// pathtrace.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::emitter::Emitter;
+use crate::frame_graph::FrameContext;
+use crate::integrator::Integrator;
+use crate::light_grid::LightGrid;
+use crate::portal::Portal;
+use crate::ray::Ray;
+use crate::restir::PixelRng;
+use crate::settings::RenderSettings;
+use crate::{Surface, background, crystal_fresnel, reflect, secondary_ray, light_filter, shadow_only_factor};
+
+// Rebotes antes de que la ruleta rusa empiece a cortar caminos
+const GUARANTEED_BOUNCES: u32 = 2;
+// Tope de rebotes aunque la ruleta no corte (evita caminos eternos entre espejos)
+const MAX_BOUNCES: u32 = 16;
+
+// Dirección al azar en el hemisferio de `normal`, con densidad proporcional al coseno
+pub fn cosine_sample(normal: &Vec3, rng: &mut PixelRng) -> Vec3 {
+    let (u1, u2) = (rng.next(), rng.next());
+    let radius = u1.sqrt();
+    let angle = 2.0 * PI * u2;
+    let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * (1.0 - u1).max(0.0).sqrt())
+        .normalize()
+}
+
+// Luz directa de los objetos emisivos: una dirección al azar dentro del cono que cubre la
+// esfera envolvente de cada uno, que cuenta solo si da en ese objeto
+fn emitted_light(surface: &Surface, emitters: &[Emitter], scene: &Bvh, settings: &RenderSettings, rng: &mut PixelRng) -> Color {
+    let (point, normal) = (surface.intersect.point, surface.intersect.normal);
+    let mut light = Color::black();
+    for emitter in emitters.iter().filter(|emitter| surface.intersect.object != Some(emitter.object)) {
+        let Some((axis, cos_max)) = emitter.cone(&point) else { continue };
+        let cos_theta = 1.0 - rng.next() * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let angle = 2.0 * PI * rng.next();
+        let helper = if axis.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let tangent = axis.cross(&helper).normalize();
+        let bitangent = axis.cross(&tangent);
+        let direction = (tangent * (sin_theta * angle.cos()) + bitangent * (sin_theta * angle.sin()) + axis * cos_theta).normalize();
+        let cosine = normal.dot(&direction);
+        if cosine <= 0.0 {
+            continue;
+        }
+
+        let hit = scene.intersect(&secondary_ray(&surface.intersect, &direction, f32::INFINITY, settings));
+        if hit.is_intersecting && !hit.inside && hit.object == Some(emitter.object) {
+            // Difuso lambertiano (albedo / π) por el coseno, dividido entre la densidad 1 / ángulo sólido
+            light = light + emitter.reflected(cosine, cos_max);
+        }
+    }
+    light.tinted(surface.diffuse_albedo())
+}
+
+// Luz del fondo que entra por los portales: un punto al azar de uno de ellos, que cuenta si
+// el rayo hacia él sale de la escena sin chocar con nada (un vidrio en la ventana la tapa)
+fn portal_light(surface: &Surface, portals: &[Portal], scene: &Bvh, settings: &RenderSettings, rng: &mut PixelRng) -> Color {
+    if portals.is_empty() {
+        return Color::black();
+    }
+    let portal = &portals[((rng.next() * portals.len() as f32) as usize).min(portals.len() - 1)];
+    let (point, normal) = (surface.intersect.point, surface.intersect.normal);
+    let to_portal = portal.sample(rng.next(), rng.next()) - point;
+    let distance = to_portal.norm();
+    let direction = to_portal / distance;
+    let cosine = normal.dot(&direction);
+    let portal_cosine = portal.normal().dot(&direction).abs();
+    if cosine <= 0.0 || portal_cosine <= 0.0 {
+        return Color::black();
+    }
+    if scene.intersect(&secondary_ray(&surface.intersect, &direction, f32::INFINITY, settings)).is_intersecting {
+        return Color::black();
+    }
+    // Densidad por ángulo sólido del punto elegido: distancia² / (área · coseno en el portal),
+    // entre la cantidad de portales. Difuso lambertiano: radiancia · coseno / π entre la densidad
+    let density = distance * distance / (portal.area() * portal_cosine * portals.len() as f32);
+    let transmittance = settings.fog.map_or(1.0, |fog| fog.transmittance(f32::INFINITY));
+    (background(&direction, settings) * (cosine * transmittance / (PI * density))).tinted(surface.diffuse_albedo())
+}
+
+// Un camino desde la cámara: en cada impacto se suma la luz directa (con sombra) y el
+// camino sigue en una dirección difusa al azar, con el peso (throughput) del albedo
+fn trace_path(ray: &Ray, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings, rng: &mut PixelRng) -> Color {
+    let emitters = lights.emitters.as_slice();
+    let mut ray = *ray;
+    let mut color = Color::black();
+    // Fracción de la luz que llega a la cámara desde el punto actual, en 0-255 como los colores
+    let mut throughput = Color::new(255.0, 255.0, 255.0);
+    // Tras un rebote difuso la luz de los emisivos ya se sumó al muestrearlos; golpearlos
+    // de nuevo la contaría dos veces
+    let mut after_diffuse = false;
+
+    for bounce in 0..MAX_BOUNCES {
+        let intersect = scene.intersect(&ray);
+        // Niebla del tramo: se suma su color y atenúa lo que venga detrás (sin haces de luz)
+        if let Some(fog) = settings.fog {
+            let distance = if intersect.is_intersecting { intersect.distance } else { f32::INFINITY };
+            let transmittance = fog.transmittance(distance);
+            color = color + fog.color.tinted(throughput) * (1.0 - transmittance);
+            throughput = throughput * transmittance;
+        }
+        if !intersect.is_intersecting {
+            // Tras un rebote difuso, el fondo que se ve por un portal ya se sumó al muestrearlo
+            if !(after_diffuse && settings.portals.iter().any(|portal| portal.crosses(&ray))) {
+                color = color + background(&ray.direction, settings).tinted(throughput);
+            }
+            break;
+        }
+
+        // Igual que en `cast_ray`: desde dentro de un objeto opaco se sigue de largo
+        if intersect.inside && intersect.material.opacity >= 1.0 && !intersect.material.is_crystal {
+            ray = secondary_ray(&intersect, &ray.direction, f32::INFINITY, settings);
+            continue;
+        }
+        // Cristal: se refleja con la probabilidad de Fresnel y si no lo atraviesa
+        if intersect.material.is_crystal {
+            let (reflectance, refracted) = crystal_fresnel(&ray, &intersect);
+            let direction = match refracted {
+                Some(refracted) if rng.next() >= reflectance => {
+                    if !intersect.inside {
+                        throughput = throughput.tinted(intersect.material.diffuse);
+                    }
+                    refracted
+                }
+                _ => reflect(&ray.direction, &intersect.normal).normalize(),
+            };
+            ray = secondary_ray(&intersect, &direction, f32::INFINITY, settings);
+            // El muestreo de emisivos no ve a través de espejos ni de translúcidos
+            after_diffuse = false;
+            continue;
+        }
+
+        let emissive = intersect.material.emissive;
+        let sampled = intersect.object.is_some_and(|object| emitters.iter().any(|emitter| emitter.object == object));
+        if !(after_diffuse && sampled) {
+            color = color + emissive.tinted(throughput);
+        }
+
+        // Espejo parcial: con probabilidad `reflectivity` el camino se refleja como en un cristal
+        if intersect.material.reflectivity > 0.0 && rng.next() < intersect.material.reflectivity {
+            let reflect_dir = reflect(&ray.direction, &intersect.normal).normalize();
+            ray = secondary_ray(&intersect, &reflect_dir, f32::INFINITY, settings);
+            after_diffuse = false;
+            continue;
+        }
+
+        let surface = Surface::new(&ray, intersect);
+        // Translúcido: con probabilidad 1 - opacidad el camino lo atraviesa filtrado por su color
+        if rng.next() >= surface.intersect.material.opacity {
+            throughput = throughput.tinted(surface.base_color);
+            ray = secondary_ray(&surface.intersect, &ray.direction, f32::INFINITY, settings);
+            after_diffuse = false;
+            continue;
+        }
+
+        let point = surface.intersect.point;
+        let mut direct = Color::black();
+        for light in lights.near(&point).filter(|light| !light.shadow_only) {
+            let filter = light_filter(&surface.intersect, light, scene, settings);
+            direct = direct + surface.light_contribution(light).tinted(filter);
+        }
+        direct = direct * shadow_only_factor(&surface.intersect, lights.lights, scene, settings);
+        direct = direct + emitted_light(&surface, emitters, scene, settings, rng);
+        direct = direct + portal_light(&surface, &settings.portals, scene, settings, rng);
+        color = color + direct.tinted(throughput);
+
+        // Materiales físicos: a veces el rebote sigue el lóbulo especular GGX, con su peso
+        // dividido por la probabilidad de elegirlo; si no, el difuso dividido por la contraria
+        let mut diffuse_weight = 1.0;
+        if let Some(pbr) = &surface.intersect.material.pbr {
+            let ior = surface.intersect.material.ior;
+            let chance = pbr.specular_chance(surface.base_color, ior, surface.intersect.normal.dot(&surface.view_dir));
+            if rng.next() < chance {
+                let Some((direction, weight)) =
+                    pbr.sample_specular(surface.base_color, ior, &surface.intersect.normal, &surface.view_dir, rng)
+                else {
+                    break;
+                };
+                throughput = throughput.tinted(weight * (1.0 / chance));
+                ray = secondary_ray(&surface.intersect, &direction, f32::INFINITY, settings);
+                // Como en los espejos, lo emisivo que se vea en el reflejo no se muestreó
+                after_diffuse = false;
+                continue;
+            }
+            diffuse_weight = 1.0 / (1.0 - chance).max(1e-3);
+        }
+
+        // Difuso lambertiano muestreado por coseno: la densidad cancela el coseno y el 1/π,
+        // así que el peso del rebote es solo el albedo
+        throughput = throughput.tinted(surface.diffuse_albedo() * diffuse_weight);
+        if bounce + 1 >= GUARANTEED_BOUNCES.max(settings.max_depth) {
+            let survival = (throughput.r.max(throughput.g).max(throughput.b) / 255.0).clamp(0.05, 0.95);
+            if rng.next() > survival {
+                break;
+            }
+            throughput = throughput * (1.0 / survival);
+        }
+        let direction = cosine_sample(&surface.intersect.normal, rng);
+        ray = secondary_ray(&surface.intersect, &direction, f32::INFINITY, settings);
+        after_diffuse = true;
+    }
+
+    color
+}
+
+// Trazado de caminos con luz indirecta; los objetos emisivos del cuadro se muestrean como luces
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, context: &FrameContext, rng: &mut PixelRng) -> Color {
+        trace_path(ray, context.scene, context.light_grid, context.settings, rng)
+    }
+
+    fn accumulates(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,129 @@
+// pbr.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+use crate::restir::PixelRng;
+
+// Rugosidad mínima: con 0 el lóbulo GGX es un punto y las luces puntuales no se ven
+const MIN_ROUGHNESS: f32 = 0.03;
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+// Material físico en lugar de Phong: especular GGX con Fresnel de Schlick sobre el color
+// base, por ejemplo pbr: Some((metallic: 1, roughness: 0.3)). La parte dieléctrica refleja
+// según el `ior` del material
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Pbr {
+    // 0 = dieléctrico (plástico, piedra); 1 = metal: sin difuso y el reflejo toma el color base
+    #[serde(default)]
+    pub metallic: f32,
+    // 0 = pulido como un espejo, 1 = mate
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+}
+
+impl Default for Pbr {
+    fn default() -> Self {
+        Pbr { metallic: 0.0, roughness: default_roughness() }
+    }
+}
+
+// Término de sombreado de Smith para GGX en una dirección con coseno `cos` respecto a la normal
+fn smith_g1(cos: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    2.0 * cos / (cos + (alpha2 + (1.0 - alpha2) * cos * cos).sqrt())
+}
+
+fn average(color: Color) -> f32 {
+    (color.r + color.g + color.b) / 3.0
+}
+
+impl Pbr {
+    fn alpha(&self) -> f32 {
+        let roughness = self.roughness.clamp(MIN_ROUGHNESS, 1.0);
+        roughness * roughness
+    }
+
+    // Difuso: el color base en los dieléctricos, nada en los metales
+    pub fn diffuse(&self, base_color: Color) -> Color {
+        base_color * (1.0 - self.metallic.clamp(0.0, 1.0))
+    }
+
+    // Fresnel de Schlick en 0-255 por canal: de lo que refleja de frente (según el IOR, o el
+    // color base en los metales) hacia blanco en los ángulos rasantes
+    pub fn fresnel(&self, base_color: Color, ior: f32, cos: f32) -> Color {
+        let dielectric = ((ior - 1.0) / (ior + 1.0)).powi(2) * 255.0;
+        let f0 = Color::new(dielectric, dielectric, dielectric).blend(base_color, self.metallic);
+        f0.blend(Color::new(255.0, 255.0, 255.0), (1.0 - cos.clamp(0.0, 1.0)).powi(5))
+    }
+
+    // Luz de una luz que sale hacia `view_dir`, en la escala de Phong: un difuso blanco de
+    // frente devuelve el color de la luz (sin el 1/π; el especular se multiplica por π para
+    // mantener la proporción)
+    pub fn shade(&self, base_color: Color, ior: f32, light_color: Color, normal: &Vec3, view_dir: &Vec3, light_dir: &Vec3) -> Color {
+        let n_dot_l = normal.dot(light_dir);
+        if n_dot_l <= 0.0 {
+            return Color::black();
+        }
+        let n_dot_v = normal.dot(view_dir).max(1e-4);
+        let half = (light_dir + view_dir).normalize();
+        let n_dot_h = normal.dot(&half).max(0.0);
+        let alpha = self.alpha();
+        let alpha2 = alpha * alpha;
+        let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+        let distribution = alpha2 / (PI * denominator * denominator);
+        let shadowing = smith_g1(n_dot_l, alpha) * smith_g1(n_dot_v, alpha);
+        let fresnel = self.fresnel(base_color, ior, view_dir.dot(&half));
+
+        // D·G·F / (4·n·l·n·v) por el coseno n·l
+        let specular = light_color.tinted(fresnel) * (distribution * shadowing / (4.0 * n_dot_v) * PI);
+        let diffuse = self.diffuse(base_color).tinted(light_color) * n_dot_l;
+        diffuse + specular
+    }
+
+    // Probabilidad de seguir el lóbulo especular en un rebote, según cuánto pesa cada lóbulo
+    pub fn specular_chance(&self, base_color: Color, ior: f32, n_dot_v: f32) -> f32 {
+        let specular = average(self.fresnel(base_color, ior, n_dot_v));
+        let diffuse = average(self.diffuse(base_color));
+        if specular + diffuse <= 0.0 { 1.0 } else { (specular / (specular + diffuse)).clamp(0.05, 1.0) }
+    }
+
+    // Rebote especular: la normal de la microfaceta se elige según GGX y la dirección es el
+    // reflejo en ella. Devuelve la dirección y el peso (BRDF·coseno / densidad, 0-255 por
+    // canal), o None si el reflejo queda bajo la superficie
+    pub fn sample_specular(
+        &self,
+        base_color: Color,
+        ior: f32,
+        normal: &Vec3,
+        view_dir: &Vec3,
+        rng: &mut PixelRng,
+    ) -> Option<(Vec3, Color)> {
+        let alpha = self.alpha();
+        let alpha2 = alpha * alpha;
+        let (u1, u2) = (rng.next(), rng.next());
+        let cos_theta = ((1.0 - u1) / (1.0 + (alpha2 - 1.0) * u1)).max(0.0).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let tangent = normal.cross(&helper).normalize();
+        let bitangent = normal.cross(&tangent);
+        let half = tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta;
+
+        let v_dot_h = view_dir.dot(&half);
+        let direction = half * (2.0 * v_dot_h) - view_dir;
+        let (n_dot_l, n_dot_v) = (normal.dot(&direction), normal.dot(view_dir));
+        if n_dot_l <= 0.0 || n_dot_v <= 0.0 || v_dot_h <= 0.0 {
+            return None;
+        }
+        let shadowing = smith_g1(n_dot_l, alpha) * smith_g1(n_dot_v, alpha);
+        let weight = shadowing * v_dot_h / (cos_theta.max(1e-4) * n_dot_v);
+        Some((direction, self.fresnel(base_color, ior, v_dot_h) * weight))
+    }
+}
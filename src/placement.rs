@@ -0,0 +1,43 @@
+// placement.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+use crate::object::{Object, SceneObject};
+
+// Mueve el objeto para que las caras de su caja queden sobre la cuadrícula de lado `cell_size`
+pub fn snap_to_grid(object: &mut dyn SceneObject, cell_size: f32) {
+    let half = Vec3::new(object.size() / 2.0, object.size() / 2.0, object.size() / 2.0);
+    let corner = object.center() - half;
+    object.set_center((corner / cell_size).map(|v| v.round()) * cell_size + half);
+}
+
+// Dirección de una cara escrita como +x, -x, +y, -y, +z o -z
+pub fn parse_face(face: &str) -> Option<Vec3> {
+    match face {
+        "+x" => Some(Vec3::new(1.0, 0.0, 0.0)),
+        "-x" => Some(Vec3::new(-1.0, 0.0, 0.0)),
+        "+y" => Some(Vec3::new(0.0, 1.0, 0.0)),
+        "-y" => Some(Vec3::new(0.0, -1.0, 0.0)),
+        "+z" => Some(Vec3::new(0.0, 0.0, 1.0)),
+        "-z" => Some(Vec3::new(0.0, 0.0, -1.0)),
+        _ => None,
+    }
+}
+
+// Apoya el objeto contra la cara `face` de la caja `anchor`, centrado sobre ella
+pub fn attach_to_face(object: &mut dyn SceneObject, anchor: &Aabb, face: &Vec3) {
+    let anchor_extent = face.dot(&anchor.size()).abs();
+    object.set_center(anchor.center() + face * ((anchor_extent + object.size()) / 2.0));
+}
+
+// Copias del objeto desplazadas 1, 2, ..., count veces `step`
+pub fn array(object: &dyn SceneObject, count: usize, step: &Vec3) -> Vec<Object> {
+    (1..=count)
+        .map(|i| {
+            let mut copy = object.box_clone();
+            copy.set_center(object.center() + step * i as f32);
+            copy
+        })
+        .collect()
+}
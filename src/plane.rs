@@ -0,0 +1,109 @@
+// plane.rs
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::object::{Object, SceneObject};
+use crate::obj_export::{self, ObjTransform};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Plano infinito que pasa por `point`; la textura se repite cada `tile_size` unidades
+#[derive(Debug, Clone)]
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub tile_size: f32,
+    pub material: Arc<Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, tile_size: f32, material: impl Into<Arc<Material>>) -> Self {
+        Plane { point, normal: normal.normalize(), tile_size, material: material.into() }
+    }
+
+    // Direcciones de u y v sobre el plano; con normal +Y coinciden con las de la cara
+    // superior de un cubo (u = x, v = z)
+    pub fn tangents(&self) -> (Vec3, Vec3) {
+        let reference = if self.normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 0.0, 1.0) };
+        let tangent = (reference - self.normal * self.normal.dot(&reference)).normalize();
+        let bitangent = tangent.cross(&self.normal);
+        (tangent, bitangent)
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let denominator = self.normal.dot(&ray.direction);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.point - ray.origin).dot(&self.normal) / denominator;
+        if !ray.contains(t) {
+            return Intersect::empty();
+        }
+
+        let point = ray.at(t);
+        // Se ve por los dos lados: la normal apunta hacia el origen del rayo
+        let normal = if denominator < 0.0 { self.normal } else { -self.normal };
+
+        let (tangent, bitangent) = self.tangents();
+        let local = point - self.point;
+        let u = (local.dot(&tangent) / self.tile_size).rem_euclid(1.0);
+        let v = (local.dot(&bitangent) / self.tile_size).rem_euclid(1.0);
+
+        Intersect::new(point, normal, t, self.material.clone(), Some((u, v)))
+            .with_tangents(tangent, bitangent)
+            .with_uv_scale(self.tile_size)
+    }
+}
+
+impl SceneObject for Plane {
+    fn kind(&self) -> &'static str {
+        "plane"
+    }
+
+    fn center(&self) -> Vec3 {
+        self.point
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.point = center;
+    }
+
+    // El tamaño de un plano es el de una baldosa de su textura
+    fn size(&self) -> f32 {
+        self.tile_size
+    }
+
+    fn set_size(&mut self, size: f32) {
+        self.tile_size = size;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    // Si otro objeto comparte el material, este pasa a tener su propia copia
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.material)
+    }
+
+    // Infinito: no tiene caja
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(self.clone())
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        obj_export::write_plane(obj, self, vertex_offset, transform)
+    }
+}
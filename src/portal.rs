@@ -0,0 +1,52 @@
+// portal.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::ray::Ray;
+
+// Portal de luz: un rectángulo sobre una ventana o una puerta de un interior iluminado por el
+// fondo. El trazado de caminos elige puntos del portal para mirar el fondo a través de él en
+// lugar de esperar a que un rebote al azar salga justo por la abertura
+#[derive(Debug, Clone, Copy)]
+pub struct Portal {
+    pub corner: Vec3,
+    // Los dos lados que salen de `corner`
+    pub edges: [Vec3; 2],
+}
+
+impl Portal {
+    // None si los lados no forman un rectángulo con área
+    pub fn new(corner: Vec3, edges: [Vec3; 2]) -> Option<Self> {
+        let portal = Portal { corner, edges };
+        (portal.area() > 0.0 && edges[0].dot(&edges[1]).abs() <= 1e-3 * portal.area()).then_some(portal)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.edges[0].cross(&self.edges[1]).norm()
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.edges[0].cross(&self.edges[1]).normalize()
+    }
+
+    // Un punto uniforme en el área a partir de dos números al azar en [0, 1)
+    pub fn sample(&self, u: f32, v: f32) -> Vec3 {
+        self.corner + self.edges[0] * u + self.edges[1] * v
+    }
+
+    // Si el rayo pasa por la abertura, hacia cualquiera de los dos lados
+    pub fn crosses(&self, ray: &Ray) -> bool {
+        let normal = self.normal();
+        let facing = ray.direction.dot(&normal);
+        if facing == 0.0 {
+            return false;
+        }
+        let t = (self.corner - ray.origin).dot(&normal) / facing;
+        if t <= 0.0 {
+            return false;
+        }
+        let offset = ray.origin + ray.direction * t - self.corner;
+        let [u, v] = self.edges.map(|edge| offset.dot(&edge) / edge.norm_squared());
+        (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)
+    }
+}
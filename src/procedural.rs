@@ -0,0 +1,167 @@
+// procedural.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+// Cuánto tuercen el ruido las vetas del mármol y los anillos de la madera
+const MARBLE_TURBULENCE: f32 = 4.0;
+const WOOD_TURBULENCE: f32 = 0.3;
+// Anillos de la madera por unidad del patrón
+const WOOD_RINGS: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum PatternKind {
+    // Casillas de lado 1 alternando los dos colores
+    Checker,
+    // Ruido de Perlin fractal (fBm), manchas de todos los tamaños
+    Noise,
+    // Anillos alrededor del eje Y del patrón; en UV se ven como vetas a lo largo de v
+    Wood,
+    // Franjas onduladas por el ruido
+    Marble,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_octaves() -> u32 {
+    5
+}
+
+// Patrón calculado en cada punto en lugar de leído de una imagen, por ejemplo
+// (kind: Marble, scale: 2, colors: ((235, 235, 230), (70, 70, 80)))
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    // Repeticiones por unidad de UV o, con `world`, por unidad del mundo
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    // Colores en 0 y en 1 del patrón; en el archivo en sRGB y, ya cargado, lineales
+    pub colors: [[f32; 3]; 2],
+    // Evaluar en la posición del mundo: el patrón sigue de un bloque al vecino como si
+    // estuvieran tallados en el mismo material. Sin esto, cada cara usa su UV
+    #[serde(default)]
+    pub world: bool,
+    // Capas de ruido, cada una del doble de frecuencia y la mitad de amplitud
+    #[serde(default = "default_octaves")]
+    pub octaves: u32,
+}
+
+// Hash entero de un vértice de la grilla
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841) ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h
+}
+
+// Una de las 12 direcciones a los bordes de un cubo, como en el ruido mejorado de Perlin
+fn gradient(x: i32, y: i32, z: i32) -> Vec3 {
+    const DIRECTIONS: [[f32; 3]; 12] = [
+        [1.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+        [1.0, -1.0, 0.0],
+        [-1.0, -1.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [-1.0, 0.0, 1.0],
+        [1.0, 0.0, -1.0],
+        [-1.0, 0.0, -1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+        [0.0, 1.0, -1.0],
+        [0.0, -1.0, -1.0],
+    ];
+    let [gx, gy, gz] = DIRECTIONS[hash(x, y, z) as usize % 12];
+    Vec3::new(gx, gy, gz)
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Ruido de Perlin en [-1, 1] (en la práctica rara vez pasa de ±0.7); 0 en los vértices de la grilla
+pub fn perlin(point: &Vec3) -> f32 {
+    let cell = point.map(f32::floor);
+    let local = point - cell;
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient(x + dx, y + dy, z + dz).dot(&(local - Vec3::new(dx as f32, dy as f32, dz as f32)))
+    };
+    let (u, v, w) = (fade(local.x), fade(local.y), fade(local.z));
+    lerp(
+        lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), u), lerp(corner(0, 1, 0), corner(1, 1, 0), u), v),
+        lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), u), lerp(corner(0, 1, 1), corner(1, 1, 1), u), v),
+        w,
+    )
+}
+
+// Suma de `octaves` capas de ruido. Las capas más finas que el pixel (`width`, en unidades
+// del patrón) se desvanecen: solo agregarían parpadeo
+fn fbm(point: &Vec3, octaves: u32, width: f32) -> f32 {
+    let (mut sum, mut frequency, mut amplitude) = (0.0, 1.0, 0.5);
+    for _ in 0..octaves {
+        let visible = (2.0 - 2.0 * frequency * width).clamp(0.0, 1.0);
+        if visible <= 0.0 {
+            break;
+        }
+        sum += perlin(&(point * frequency)) * amplitude * visible;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    sum
+}
+
+impl Pattern {
+    // El mismo patrón con los colores escritos en sRGB pasados a lineal
+    pub fn linearized(self) -> Self {
+        let colors = self.colors.map(|[r, g, b]| {
+            let color = Color::from_srgb(r, g, b);
+            [color.r, color.g, color.b]
+        });
+        Pattern { colors, ..self }
+    }
+
+    // Color en la UV de la cara o en `point` del mundo. `footprint` es lo que mide el pixel
+    // sobre la superficie en las mismas unidades (UV o mundo); None si el patrón va en UV y
+    // la superficie no tiene
+    pub fn sample(&self, uv: Option<(f32, f32)>, point: &Vec3, footprint: f32) -> Option<Color> {
+        let point = match uv {
+            _ if self.world => *point,
+            Some((u, v)) => Vec3::new(u, v, 0.0),
+            None => return None,
+        } * self.scale;
+        let width = footprint * self.scale;
+
+        let value = match self.kind {
+            PatternKind::Checker => {
+                let parity = (point.x.floor() + point.y.floor() + point.z.floor()).rem_euclid(2.0);
+                // Cuando las casillas son más chicas que el pixel queda el promedio
+                lerp(parity, 0.5, (width * 2.0 - 0.5).clamp(0.0, 1.0))
+            }
+            PatternKind::Noise => 0.5 + fbm(&point, self.octaves, width),
+            PatternKind::Wood => {
+                let radius = (point.x * point.x + point.z * point.z).sqrt();
+                let rings = (radius + WOOD_TURBULENCE * fbm(&point, self.octaves, width)) * WOOD_RINGS;
+                // Diente de sierra suavizado: madera clara que se oscurece hacia cada anillo
+                lerp(rings.fract().powi(3), 0.25, (width * WOOD_RINGS - 0.5).clamp(0.0, 1.0))
+            }
+            PatternKind::Marble => {
+                let turbulence = fbm(&point, self.octaves, width).abs() * 2.0;
+                0.5 + 0.5 * ((point.x + MARBLE_TURBULENCE * turbulence) * PI).sin()
+            }
+        };
+
+        let [[r0, g0, b0], [r1, g1, b1]] = self.colors;
+        Some(Color::new(r0, g0, b0).blend(Color::new(r1, g1, b1), value))
+    }
+}
@@ -0,0 +1,97 @@
+// projection.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::ray_intersect::Intersect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum ProjectionKind {
+    // Como un proyector a lo largo de `axis`: los lados paralelos al eje quedan estirados
+    Planar,
+    // Planar según el eje al que más mira cada punto; sirve para cualquier forma sin estirar
+    Box,
+    // Longitud y latitud alrededor del eje Y del objeto
+    Spherical,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_axis() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+// UV calculada desde la posición en el objeto en lugar de la propia de la primitiva, por
+// ejemplo projection: Some((kind: Box, scale: 2))
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    // Repeticiones por unidad del mundo; en la esférica, vueltas alrededor del objeto
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    // Dirección en que proyecta la planar (desde arriba si falta)
+    #[serde(default = "default_axis")]
+    pub axis: [f32; 3],
+}
+
+// Dos direcciones perpendiculares a `axis` que hacen de u y v; desde arriba, u = x y v = z
+fn frame(axis: &Vec3) -> (Vec3, Vec3) {
+    let helper = if axis.y.abs() > 0.9 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+    let tangent = axis.cross(&helper).normalize();
+    (tangent, tangent.cross(axis))
+}
+
+impl Projection {
+    // Reemplaza la UV, las tangentes y la escala de UV del impacto. La posición es relativa al
+    // centro del objeto, así que la textura se mueve con él
+    pub fn apply(&self, intersect: &mut Intersect) {
+        let scale = self.scale.max(1e-6);
+        let local = intersect.point - intersect.instance;
+        let planar = |intersect: &mut Intersect, axis: &Vec3| {
+            let (tangent, bitangent) = frame(axis);
+            let (u, v) = (local.dot(&tangent) * scale, local.dot(&bitangent) * scale);
+            intersect.uv = Some((u.rem_euclid(1.0), v.rem_euclid(1.0)));
+            intersect.tangent = tangent;
+            intersect.bitangent = bitangent;
+            intersect.uv_scale = 1.0 / scale;
+        };
+        match self.kind {
+            ProjectionKind::Planar => {
+                let [x, y, z] = self.axis;
+                let axis = Vec3::new(x, y, z);
+                planar(intersect, &if axis.norm() > 0.0 { axis.normalize() } else { Vec3::new(0.0, 1.0, 0.0) });
+            }
+            ProjectionKind::Box => {
+                let normal = intersect.normal.abs();
+                let axis = if normal.x >= normal.y && normal.x >= normal.z {
+                    Vec3::new(1.0, 0.0, 0.0)
+                } else if normal.y >= normal.z {
+                    Vec3::new(0.0, 1.0, 0.0)
+                } else {
+                    Vec3::new(0.0, 0.0, 1.0)
+                };
+                planar(intersect, &axis);
+            }
+            ProjectionKind::Spherical => {
+                let radius = local.norm();
+                if radius <= 0.0 {
+                    return;
+                }
+                let direction = local / radius;
+                let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+                let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+                intersect.uv = Some(((u * scale).rem_euclid(1.0), (v * scale).rem_euclid(1.0)));
+                // u crece alrededor del eje Y y v hacia abajo; en los polos queda cualquier tangente
+                let around = Vec3::new(-direction.z, 0.0, direction.x);
+                let tangent = if around.norm() > 1e-6 { around.normalize() } else { Vec3::new(1.0, 0.0, 0.0) };
+                intersect.tangent = tangent;
+                intersect.bitangent = direction.cross(&tangent).normalize();
+                intersect.uv_scale = PI * radius / scale;
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use nalgebra_glm::Vec3;
+
+// Rayo con intervalo paramétrico: solo cuentan los impactos con t en [t_min, t_max]
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub t_min: f32,
+    pub t_max: f32,
+    // Instante dentro del obturador, de 0 (abre) a 1 (cierra), para el desenfoque de movimiento
+    pub time: f32,
+    // Ángulo que cubre un pixel alrededor del rayo (0 = sin ancho); con la distancia da el
+    // tamaño del pixel sobre lo que golpea, para el filtrado de texturas
+    pub spread: f32,
+    // Superficies atravesadas sin rebotar para llegar hasta acá (ver `MAX_PASS_THROUGH`)
+    pub passes: u32,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Ray {
+            origin,
+            direction,
+            t_min: 0.0,
+            t_max: f32::INFINITY,
+            time: 0.0,
+            spread: 0.0,
+            passes: 0,
+        }
+    }
+
+    pub fn with_interval(origin: Vec3, direction: Vec3, t_min: f32, t_max: f32) -> Self {
+        Ray {
+            origin,
+            direction,
+            t_min,
+            t_max,
+            time: 0.0,
+            spread: 0.0,
+            passes: 0,
+        }
+    }
+
+    pub fn at_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    // Continuación de `previous` del otro lado de una superficie que atravesó
+    pub fn passed_through(mut self, previous: &Ray) -> Self {
+        self.passes = previous.passes + 1;
+        self
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    pub fn contains(&self, t: f32) -> bool {
+        t >= self.t_min && t <= self.t_max
+    }
+}
@@ -1,5 +1,12 @@
+use std::sync::{Arc, LazyLock};
+
 use nalgebra_glm::Vec3;
+use crate::cube::Face;
 use crate::material::Material;
+use crate::ray::Ray;
+
+// Material de los impactos vacíos, uno solo para no reservarlo en cada rayo que no golpea
+static NO_MATERIAL: LazyLock<Arc<Material>> = LazyLock::new(|| Arc::new(Material::black()));
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -8,12 +15,30 @@ pub struct Intersect {
     pub normal: Vec3,
     pub distance: f32,
     pub is_intersecting: bool,
-    pub material: Material,
+    // Compartido con el objeto: copiar un impacto no copia texturas ni mapas
+    pub material: Arc<Material>,
     pub uv: Option<(f32, f32)>,
+    // Direcciones en el mundo en que crecen u y v sobre la superficie (cero si no hay UV)
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    // Tamaño en el mundo de una unidad de UV (el lado de un cubo, una baldosa del plano);
+    // 0 = desconocido, la textura se muestrea en su nivel más fino
+    pub uv_scale: f32,
+    // Cara golpeada, en cubos y bloques
+    pub face: Option<Face>,
+    // Centro del objeto golpeado; semilla de la variación por instancia del material
+    pub instance: Vec3,
+    // Instante del rayo que lo encontró; los rayos secundarios salen en el mismo
+    pub time: f32,
+    // El rayo empezó dentro del objeto y esta es la cara por la que sale; la normal
+    // apunta hacia adentro (hacia el origen del rayo), no hacia afuera
+    pub inside: bool,
+    // Índice del objeto golpeado en la escena, si se intersectó a través de ella
+    pub object: Option<usize>,
 }
 
 impl Intersect {
-    pub fn new(point: Vec3, normal: Vec3, distance: f32, material: Material, uv: Option<(f32, f32)>) -> Self {
+    pub fn new(point: Vec3, normal: Vec3, distance: f32, material: Arc<Material>, uv: Option<(f32, f32)>) -> Self {
         Intersect {
             point,
             normal,
@@ -21,23 +46,62 @@ impl Intersect {
             is_intersecting: true,
             material,
             uv,
+            tangent: Vec3::zeros(),
+            bitangent: Vec3::zeros(),
+            uv_scale: 0.0,
+            face: None,
+            instance: Vec3::zeros(),
+            time: 0.0,
+            inside: false,
+            object: None,
         }
     }
 
+    pub fn with_tangents(mut self, tangent: Vec3, bitangent: Vec3) -> Self {
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+        self
+    }
+
+    pub fn with_uv_scale(mut self, uv_scale: f32) -> Self {
+        self.uv_scale = uv_scale;
+        self
+    }
+
+    pub fn on_face(mut self, face: Face) -> Self {
+        self.face = Some(face);
+        self
+    }
+
+    // Marca un impacto de salida y da vuelta la normal hacia el interior
+    pub fn seen_from_inside(mut self) -> Self {
+        self.inside = true;
+        self.normal = -self.normal;
+        self
+    }
+
     pub fn empty() -> Self {
         Intersect {
             point: Vec3::zeros(),
             normal: Vec3::zeros(),
             distance: 0.0,
             is_intersecting: false,
-            material: Material::black(),
+            material: NO_MATERIAL.clone(),
             uv: None,
+            tangent: Vec3::zeros(),
+            bitangent: Vec3::zeros(),
+            uv_scale: 0.0,
+            face: None,
+            instance: Vec3::zeros(),
+            time: 0.0,
+            inside: false,
+            object: None,
         }
     }
 }
 
 pub trait RayIntersect {
-  fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+  fn ray_intersect(&self, ray: &Ray) -> Intersect;
 }
 
 
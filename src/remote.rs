@@ -0,0 +1,161 @@
+// remote.rs
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+// Protocolo: un objeto JSON por línea, por ejemplo
+// {"cmd": "orbit", "yaw": 0.1, "pitch": 0.0}
+// {"cmd": "set", "command": "set light.0.intensity 2.0"}
+// {"cmd": "frame"}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Orbit { yaw: f32, pitch: f32 },
+    Camera { position: [f32; 3], center: [f32; 3] },
+    Set { command: String },
+    Render,
+    Frame,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    // Cuadro actual codificado como PNG en base64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub png: Option<String>,
+}
+
+impl Response {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Response { ok: true, message: Some(message.into()), png: None }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Response { ok: false, message: Some(message.into()), png: None }
+    }
+}
+
+type Job = (Request, Sender<Response>);
+
+// Bytes máximos de una petición; un cliente que manda más sin cortar la línea se desconecta
+// en lugar de hacer crecer el búfer sin límite
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+pub struct RemoteServer {
+    jobs: Receiver<Job>,
+}
+
+impl RemoteServer {
+    pub fn start(address: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let (sender, jobs) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || handle_client(stream, sender));
+            }
+        });
+
+        Ok(RemoteServer { jobs })
+    }
+
+    // Atiende peticiones indefinidamente (modo sin ventana)
+    pub fn serve(&self, mut handle: impl FnMut(Request) -> Response) {
+        while let Ok((request, reply)) = self.jobs.recv() {
+            let _ = reply.send(handle(request));
+        }
+    }
+
+    // Atiende las peticiones pendientes sin bloquear; se llama una vez por cuadro
+    pub fn poll(&self, mut handle: impl FnMut(Request) -> Response) {
+        while let Ok((request, reply)) = self.jobs.try_recv() {
+            let _ = reply.send(handle(request));
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, jobs: Sender<Job>) {
+    let Ok(mut writer) = stream.try_clone() else { return };
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let mut line = String::new();
+        match (&mut reader).take(MAX_LINE_BYTES + 1).read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if line.len() as u64 > MAX_LINE_BYTES {
+            let response = Response::error(format!("petición de más de {} bytes", MAX_LINE_BYTES));
+            if let Ok(json) = serde_json::to_string(&response) {
+                let _ = writeln!(writer, "{}", json);
+            }
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let (reply, response) = mpsc::channel();
+                if jobs.send((request, reply)).is_err() {
+                    break;
+                }
+                response.recv().unwrap_or_else(|_| Response::error("el renderizador se cerró"))
+            }
+            Err(error) => Response::error(format!("petición inválida: {}", error)),
+        };
+
+        let Ok(json) = serde_json::to_string(&response) else { break };
+        if writeln!(writer, "{}", json).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Atiende una sola conexión con `handle_client` y devuelve el cliente conectado
+    fn connect() -> (TcpStream, thread::JoinHandle<()>, Receiver<Job>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (sender, jobs) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_client(stream, sender);
+        });
+        (TcpStream::connect(address).unwrap(), server, jobs)
+    }
+
+    #[test]
+    fn overlong_lines_close_the_connection() {
+        let (mut client, server, _jobs) = connect();
+        client.write_all(&vec![b'x'; MAX_LINE_BYTES as usize + 1]).unwrap();
+        let mut reply = String::new();
+        BufReader::new(&client).read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with(r#"{"ok":false"#), "{}", reply);
+        assert_eq!(reply.lines().count(), 1);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn invalid_requests_keep_the_connection() {
+        let (mut client, server, _jobs) = connect();
+        client.write_all(b"{\"cmd\": \"bailar\"}\n").unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut reply = String::new();
+        reader.read_line(&mut reply).unwrap();
+        assert!(reply.contains("petición inválida"), "{}", reply);
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+// restir.rs
+
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::emitter;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::light_grid::LightGrid;
+use crate::ray::Ray;
+use crate::settings::RenderSettings;
+use crate::watchdog;
+use crate::{
+    Surface, ambient_occlusion, apply_fog, background, cast_ray, light_filter, sample_offset, shade_crystal,
+    shadow_only_factor,
+};
+
+// Vecinos que se combinan por pixel y radio (en pixeles) en que se buscan
+const SPATIAL_NEIGHBORS: usize = 4;
+const SPATIAL_RADIUS: f32 = 8.0;
+// El historial cuenta como a lo más este múltiplo de las muestras del cuadro, para
+// que la imagen siga reaccionando a los cambios de la escena
+const TEMPORAL_HISTORY_LIMIT: f32 = 20.0;
+// Qué tan parecidas deben ser dos superficies para compartir muestras
+const MIN_NORMAL_SIMILARITY: f32 = 0.9;
+const MAX_DEPTH_DIFFERENCE: f32 = 0.1;
+
+// Reservorio de una sola muestra (weighted reservoir sampling): recorre candidatos
+// y se queda con uno con probabilidad proporcional a su peso
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reservoir {
+    light: usize,
+    weight_sum: f32,
+    // Número de candidatos vistos (M)
+    count: f32,
+    // Aporte estimado (luminancia sin sombra) de la luz elegida en la superficie dueña
+    target: f32,
+    // Superficie en que se armó, para decidir si se puede reutilizar
+    point: Vec3,
+    normal: Vec3,
+    distance: f32,
+}
+
+impl Reservoir {
+    fn update(&mut self, light: usize, weight: f32, target: f32, random: f32) {
+        self.weight_sum += weight;
+        self.count += 1.0;
+        if weight > 0.0 && random * self.weight_sum < weight {
+            self.light = light;
+            self.target = target;
+        }
+    }
+
+    // Peso por el que se multiplica el aporte de la luz elegida para estimar la suma de todas
+    fn contribution_weight(&self) -> f32 {
+        if self.target > 0.0 { self.weight_sum / (self.count * self.target) } else { 0.0 }
+    }
+
+    // Agrega otro reservorio reevaluando su luz en esta superficie
+    fn merge(&mut self, other: &Reservoir, count: f32, target_here: f32, random: f32) {
+        let total = self.count + count;
+        self.update(other.light, target_here * other.contribution_weight() * count, target_here, random);
+        self.count = total;
+    }
+
+    fn is_similar(&self, other: &Reservoir) -> bool {
+        other.count > 0.0
+            && self.normal.dot(&other.normal) > MIN_NORMAL_SIMILARITY
+            && (self.distance - other.distance).abs() < MAX_DEPTH_DIFFERENCE * self.distance
+    }
+}
+
+// Reservorios del cuadro anterior, uno por pixel; viven en el framebuffer
+#[derive(Default)]
+pub struct ReservoirHistory {
+    reservoirs: Vec<Reservoir>,
+    frame: u32,
+}
+
+// Generador pseudoaleatorio barato (hash PCG) con estado propio por pixel
+pub struct PixelRng(u32);
+
+impl PixelRng {
+    pub fn new(x: usize, y: usize, frame: u32) -> Self {
+        PixelRng((x as u32).wrapping_mul(1973) ^ (y as u32).wrapping_mul(9277) ^ frame.wrapping_mul(26699) | 1)
+    }
+
+    pub fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(747_796_405).wrapping_add(2_891_336_453);
+        let word = ((self.0 >> ((self.0 >> 28) + 4)) ^ self.0).wrapping_mul(277_803_737);
+        ((word >> 22) ^ word) as f32 / u32::MAX as f32
+    }
+}
+
+fn luminance(color: Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+fn target(surface: &Surface, light: &Light) -> f32 {
+    luminance(surface.light_contribution(light))
+}
+
+// Lo que deja la primera pasada en cada pixel
+enum PixelState {
+    Color(Color),
+    Surface(Box<Surface>, Reservoir),
+}
+
+// Render con muestreo de luces por reservorios (ReSTIR simplificado): en vez de evaluar
+// todas las luces, cada pixel elige `candidates` al azar, se queda con una según su aporte,
+// la combina con su reservorio del cuadro anterior y con los de pixeles vecinos, y
+// solo lanza un rayo de sombra hacia la luz elegida. Con pocas muestras queda ruido y
+// sesgo leve en los bordes, a cambio de que el costo casi no crezca con el número de luces.
+// Con `settings.samples` se repite todo por cada muestra del pixel (en otro estrato, punto
+// de la lente e instante del obturador, como `integrator::render_stratified`) y se promedia
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    scene: &Bvh,
+    camera: &Camera,
+    light_grid: &LightGrid,
+    settings: &RenderSettings,
+    candidates: usize,
+) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let samples = settings.samples.max(1);
+    let mut sum = vec![Color::black(); width * height];
+    let mut invalid = vec![false; width * height];
+    for sample in 0..samples {
+        let pass = render_sample(framebuffer, scene, camera, light_grid, settings, candidates, sample);
+        for (index, (ray, color)) in pass.into_iter().enumerate() {
+            if settings.watchdog && watchdog::is_invalid(&color) {
+                watchdog::report(color, index % width, index / width, &ray, scene);
+                invalid[index] = true;
+            }
+            sum[index] = sum[index] + color;
+        }
+    }
+    // Un NaN asegura que `resolve` lo pinte aunque el promedio haya quedado válido
+    let scale = 1.0 / samples as f32;
+    for ((pixel, color), invalid) in framebuffer.hdr.iter_mut().zip(sum).zip(invalid) {
+        *pixel = if invalid { Color::new(f32::NAN, f32::NAN, f32::NAN) } else { color * scale };
+    }
+}
+
+// Una muestra por pixel de `render`: devuelve el rayo de cámara y el color de cada pixel y
+// deja los reservorios en el historial para la muestra o el cuadro siguiente
+fn render_sample(
+    framebuffer: &mut Framebuffer,
+    scene: &Bvh,
+    camera: &Camera,
+    light_grid: &LightGrid,
+    settings: &RenderSettings,
+    candidates: usize,
+    sample: u32,
+) -> Vec<(Ray, Color)> {
+    // Los candidatos se eligen entre todas las luces; la cuadrícula solo se usa en los reflejos
+    let lights = light_grid.lights;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let samples = settings.samples.max(1);
+    let sampled: Vec<usize> = (0..lights.len()).filter(|&index| !lights[index].shadow_only).collect();
+    let history = &mut framebuffer.reservoirs;
+    if history.reservoirs.len() != width * height {
+        history.reservoirs = vec![Reservoir::default(); width * height];
+    }
+    history.frame = history.frame.wrapping_add(1);
+    let frame = history.frame;
+    let previous = &history.reservoirs;
+
+    // Primera pasada: candidatos iniciales y reutilización temporal
+    let states: Vec<(Ray, PixelState)> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let mut rng = PixelRng::new(x, y, frame);
+            let (dx, dy) = sample_offset(sample, samples, &mut rng);
+            let lens = if camera.aperture > 0.0 { (rng.next(), rng.next()) } else { (0.0, 0.0) };
+            let time = if samples > 1 { (sample as f32 + rng.next()) / samples as f32 } else { 0.0 };
+            let ray = camera.lens_ray(x as f32 + dx, y as f32 + dy, width as f32, height as f32, lens).at_time(time);
+            let intersect = scene.intersect(&ray);
+            if !intersect.is_intersecting {
+                let color = background(&ray.direction, settings);
+                return (ray, PixelState::Color(apply_fog(color, &ray, f32::INFINITY, scene, light_grid, settings, 0)));
+            }
+            if intersect.material.is_crystal {
+                let color = shade_crystal(&ray, &intersect, scene, light_grid, settings, 0);
+                return (ray, PixelState::Color(apply_fog(color, &ray, intersect.distance, scene, light_grid, settings, 0)));
+            }
+            // Lo translúcido mezcla lo que hay detrás, lo reflectante lo que refleja y desde
+            // adentro de un objeto se sigue hasta salir; se sombrea con todas las luces
+            if intersect.material.opacity < 1.0 || intersect.material.reflectivity > 0.0 || intersect.inside {
+                return (ray, PixelState::Color(cast_ray(&ray, scene, light_grid, settings, 0)));
+            }
+
+            let surface = Surface::new(&ray, intersect);
+            let mut reservoir = Reservoir {
+                point: surface.intersect.point,
+                normal: surface.intersect.normal,
+                distance: surface.intersect.distance,
+                ..Reservoir::default()
+            };
+            if sampled.is_empty() {
+                return (ray, PixelState::Surface(Box::new(surface), reservoir));
+            }
+
+            for _ in 0..candidates {
+                let light = sampled[((rng.next() * sampled.len() as f32) as usize).min(sampled.len() - 1)];
+                let target = target(&surface, &lights[light]);
+                // Candidatos uniformes: peso = aporte / probabilidad de elegirlo
+                reservoir.update(light, target * sampled.len() as f32, target, rng.next());
+            }
+
+            let old = &previous[index];
+            if reservoir.is_similar(old) && (old.point - reservoir.point).norm() < MAX_DEPTH_DIFFERENCE * reservoir.distance
+                && old.light < lights.len() && !lights[old.light].shadow_only
+            {
+                let count = old.count.min(TEMPORAL_HISTORY_LIMIT * candidates as f32);
+                reservoir.merge(old, count, target(&surface, &lights[old.light]), rng.next());
+            }
+
+            (ray, PixelState::Surface(Box::new(surface), reservoir))
+        })
+        .collect();
+
+    // Segunda pasada: reutilización espacial y sombreado con la luz elegida
+    let reservoirs: Vec<(Ray, Color, Reservoir)> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (ray, state) = &states[index];
+            let (surface, own) = match state {
+                PixelState::Color(color) => return (*ray, *color, Reservoir::default()),
+                PixelState::Surface(surface, reservoir) => (surface, reservoir),
+            };
+            let (x, y) = (index % width, index / width);
+            let mut rng = PixelRng::new(y, x, frame.wrapping_add(0x9e37));
+            let mut reservoir = *own;
+
+            for _ in 0..SPATIAL_NEIGHBORS {
+                let nx = x as f32 + (rng.next() * 2.0 - 1.0) * SPATIAL_RADIUS;
+                let ny = y as f32 + (rng.next() * 2.0 - 1.0) * SPATIAL_RADIUS;
+                if nx < 0.0 || ny < 0.0 || nx >= width as f32 || ny >= height as f32 {
+                    continue;
+                }
+                let neighbor_index = ny as usize * width + nx as usize;
+                if neighbor_index == index {
+                    continue;
+                }
+                if let (_, PixelState::Surface(_, neighbor)) = &states[neighbor_index]
+                    && own.is_similar(neighbor)
+                {
+                    let target = target(surface, &lights[neighbor.light]);
+                    reservoir.merge(neighbor, neighbor.count, target, rng.next());
+                }
+            }
+
+            let intersect = &surface.intersect;
+            let mut color = surface.ambient();
+            if settings.ao_samples > 0 {
+                color = color * ambient_occlusion(intersect, scene, settings);
+            }
+            if reservoir.count > 0.0 && reservoir.target > 0.0 {
+                let light = &lights[reservoir.light];
+                let filter = light_filter(intersect, light, scene, settings);
+                color = color + surface.light_contribution(light).tinted(filter) * reservoir.contribution_weight();
+            }
+            color = color * shadow_only_factor(intersect, lights, scene, settings)
+                + emitter::direct_light(intersect, &light_grid.emitters, scene, settings).tinted(surface.diffuse_albedo())
+                + intersect.material.emissive;
+            if settings.fog.is_some() {
+                color = apply_fog(color, ray, intersect.distance, scene, light_grid, settings, 0);
+            }
+
+            // Al historial va el reservorio propio (sin los vecinos) para no propagar correlación
+            (*ray, color, *own)
+        })
+        .collect();
+
+    framebuffer.reservoirs.reservoirs = reservoirs.iter().map(|(_, _, reservoir)| *reservoir).collect();
+    reservoirs.into_iter().map(|(ray, color, _)| (ray, color)).collect()
+}
@@ -0,0 +1,1210 @@
+// scene.rs
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use nalgebra_glm::{self as glm, Mat3, Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::background::{Background, BackgroundDescription};
+use crate::block::{Axis, Block, BlockShape, Facing};
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::console;
+use crate::cube::Cube;
+use crate::gltf;
+use crate::group::Group;
+use crate::ies::IesProfile;
+use crate::light::{AreaShape, Light, LightKind, LightLinks};
+use crate::material::{Material, MaterialDescription, load_material};
+use crate::moving::Moving;
+use crate::object::{Object, SceneObject};
+use crate::plane::Plane;
+use crate::portal::Portal;
+use crate::scene_error::{self, SceneError};
+use crate::scene_graph::{Node, SceneGraph};
+use crate::sphere::Sphere;
+use crate::transformed::{self, Transformed};
+
+// Niveles de escenas dentro de escenas; más que esto es casi seguro un ciclo
+const MAX_INCLUDE_DEPTH: u32 = 16;
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_light_color() -> [f32; 3] {
+    [255.0, 255.0, 255.0]
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn default_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CameraDescription {
+    pub eye: [f32; 3],
+    pub center: [f32; 3],
+    #[serde(default = "default_up")]
+    pub up: [f32; 3],
+    // Campo de visión vertical en grados
+    #[serde(default)]
+    pub fov: Option<f32>,
+    // Profundidad de campo: diámetro de la lente y distancia al plano nítido
+    #[serde(default)]
+    pub aperture: f32,
+    #[serde(default)]
+    pub focus_distance: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LightDescription {
+    pub position: [f32; 3],
+    #[serde(default = "default_light_color")]
+    pub color: [f32; 3],
+    #[serde(default = "default_one")]
+    pub intensity: f32,
+    #[serde(default = "default_true")]
+    pub casts_shadows: bool,
+    #[serde(default)]
+    pub shadow_only: bool,
+    #[serde(default)]
+    pub ies: Option<String>,
+    #[serde(default)]
+    pub attenuation: f32,
+    // Luz de área: esfera de este radio o panel horizontal (ancho en X, profundidad en Z)
+    #[serde(default)]
+    pub radius: f32,
+    #[serde(default)]
+    pub panel: Option<[f32; 2]>,
+    // Hacia dónde apunta: solo, una luz direccional como el sol (sin posición); con `cone`
+    // (medios ángulos interior y exterior en grados), un foco (hacia abajo si falta)
+    #[serde(default)]
+    pub direction: Option<[f32; 3]>,
+    #[serde(default)]
+    pub cone: Option<[f32; 2]>,
+    // Enlace de luz por índice de objeto: solo ilumina `include` (todos si no está) y nunca `exclude`
+    #[serde(default)]
+    pub include: Option<Vec<usize>>,
+    #[serde(default)]
+    pub exclude: Vec<usize>,
+}
+
+impl LightDescription {
+    // Lo inverso de lo que hace `contents` al armar la luz, en una escena de `units` metros
+    // por unidad. El perfil IES solo se puede quitar: la luz no guarda de qué archivo salió
+    fn update(&mut self, light: &Light, units: f32) {
+        self.position = (light.position / units).into();
+        self.color = light.color.to_srgb();
+        self.intensity = light.intensity;
+        self.casts_shadows = light.casts_shadows;
+        self.shadow_only = light.shadow_only;
+        if light.profile.is_none() {
+            self.ies = None;
+        }
+        self.attenuation = light.attenuation * units * units;
+        (self.radius, self.panel) = match light.area {
+            Some(AreaShape::Sphere { radius }) => (radius / units, None),
+            Some(AreaShape::Rectangle { width, depth }) => (0.0, Some([width / units, depth / units])),
+            None => (0.0, None),
+        };
+        (self.direction, self.cone) = match light.kind {
+            LightKind::Point => (None, None),
+            LightKind::Directional { direction } => (Some(direction.into()), None),
+            LightKind::Spot { direction, inner, outer } => {
+                (Some(direction.into()), Some([inner.to_degrees(), outer.to_degrees()]))
+            }
+        };
+        self.include = light.links.include.clone();
+        self.exclude = light.links.exclude.clone();
+    }
+}
+
+// El material es el nombre de uno de `materials` o la ruta de un archivo .ron
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum ObjectDescription {
+    Cube {
+        center: [f32; 3],
+        size: f32,
+        material: String,
+    },
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    // Losa, escalera o tronco, por ejemplo Block(center: (0, 0, 0), size: 1, shape: Stairs,
+    // facing: East, material: "piedra") o Block(center: (0, 0, 0), size: 1, shape: Log, axis: X,
+    // material: "roble")
+    Block {
+        center: [f32; 3],
+        size: f32,
+        shape: BlockShape,
+        #[serde(default)]
+        facing: Facing,
+        #[serde(default)]
+        upside_down: bool,
+        #[serde(default)]
+        axis: Axis,
+        material: String,
+    },
+    Plane {
+        point: [f32; 3],
+        #[serde(default = "default_up")]
+        normal: [f32; 3],
+        #[serde(default = "default_one")]
+        tile_size: f32,
+        material: String,
+    },
+    // Objeto que se desplaza `velocity` durante el cuadro, por ejemplo
+    // Moving(velocity: (1, 0, 0), object: Cube(center: (0, 0, 0), size: 1, material: "piedra"))
+    Moving {
+        velocity: [f32; 3],
+        object: Box<ObjectDescription>,
+    },
+    // Objeto escalado por eje, girado (grados alrededor de X, Y y Z, en ese orden) y
+    // desplazado, todo alrededor de su centro, por ejemplo
+    // Transformed(rotation: (0, 45, 30), scale: (2, 0.5, 1), object: Cube(center: (0, 0, 0), size: 1, material: "piedra"))
+    Transformed {
+        #[serde(default)]
+        translation: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+        #[serde(default = "default_scale")]
+        scale: [f32; 3],
+        object: Box<ObjectDescription>,
+    },
+    // Nodo de la jerarquía (ver `scene_graph`): los hijos, objetos u otros nodos, van en su
+    // espacio, así que moverlo o girarlo mueve todo el conjunto, por ejemplo
+    // Node(name: "muñeco", translation: (2, 0, 0), rotation: (0, 30, 0), children: [Cube(...), Cube(...)])
+    Node {
+        name: String,
+        #[serde(default)]
+        translation: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+        #[serde(default = "default_scale")]
+        scale: [f32; 3],
+        children: Vec<ObjectDescription>,
+    },
+    // Copia de uno de `prototypes` transformada como `Transformed`. Todas las copias comparten
+    // la geometría y el material del prototipo, por ejemplo
+    // Instance(prototype: "árbol", translation: (4, 0, 2), rotation: (0, 90, 0))
+    Instance {
+        prototype: String,
+        #[serde(default)]
+        translation: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+        #[serde(default = "default_scale")]
+        scale: [f32; 3],
+    },
+}
+
+// Portal de luz sobre una ventana o una puerta (ver `portal`): una esquina y los dos lados que
+// salen de ella, por ejemplo (corner: (-1, 1, -3), edges: ((2, 0, 0), (0, 1.5, 0)))
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PortalDescription {
+    pub corner: [f32; 3],
+    pub edges: [[f32; 3]; 2],
+}
+
+// Objetos que se editan juntos (ver `group`), por ejemplo
+// (name: "sillas", members: [3, 4, 5], material: Some("madera")). Los miembros son índices de
+// `objects`, como en la consola; el material y el tinte reemplazan a los de cada miembro
+// mientras el grupo los tenga
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GroupDescription {
+    pub name: String,
+    pub members: Vec<usize>,
+    #[serde(default)]
+    pub material: Option<String>,
+    #[serde(default)]
+    pub tint: Option<[f32; 3]>,
+}
+
+// Otra escena insertada en esta, movida y girada, por ejemplo
+// (path: "casa.ron", at: (10, 0, 4), rotation: 90). La ruta es relativa al archivo que la
+// incluye; se toman sus objetos, luces y portales (no la cámara ni el fondo). También puede ser
+// un modelo .gltf o .glb (ver `gltf`), en metros, del que se toman sus mallas y sus luces
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct IncludeDescription {
+    pub path: String,
+    #[serde(default)]
+    pub at: [f32; 3],
+    // Giro alrededor del eje Y en grados, antihorario visto desde arriba. Solo cuartos de
+    // vuelta: los cubos y los bloques van alineados a los ejes
+    #[serde(default)]
+    pub rotation: f32,
+    // Solo en los modelos glTF: sombreado por caras aunque traigan normales por vértice
+    #[serde(default)]
+    pub flat: bool,
+}
+
+// Cambio de unidades, giro en cuartos de vuelta alrededor de Y y desplazamiento, en ese orden
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    quarter_turns: u32,
+    offset: Vec3,
+    // Metros por unidad de la escena que se ubica
+    scale: f32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement::scaled(1.0)
+    }
+}
+
+impl Placement {
+    // `scale` es la relación entre las unidades de la escena incluida y las de la que la incluye
+    fn new(include: &IncludeDescription, scale: f32) -> Result<Self, String> {
+        let turns = include.rotation / 90.0;
+        if (turns - turns.round()).abs() > 1e-3 {
+            return Err(format!("{}: rotation debe ser un múltiplo de 90 grados", include.path));
+        }
+        Ok(Placement { quarter_turns: (turns.round() as i32).rem_euclid(4) as u32, offset: vec3(include.at), scale })
+    }
+
+    // Solo el cambio de unidades
+    fn scaled(scale: f32) -> Self {
+        Placement { quarter_turns: 0, offset: Vec3::zeros(), scale }
+    }
+
+    fn direction(&self, direction: &Vec3) -> Vec3 {
+        (0..self.quarter_turns).fold(*direction, |d, _| Vec3::new(d.z, d.y, -d.x))
+    }
+
+    fn point(&self, point: &Vec3) -> Vec3 {
+        self.vector(point) + self.offset
+    }
+
+    // Un desplazamiento: se gira y se pasa a metros, pero no se mueve
+    fn vector(&self, vector: &Vec3) -> Vec3 {
+        self.direction(vector) * self.scale
+    }
+
+    fn length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    fn facing(&self, facing: Facing) -> Facing {
+        // Cada cuarto de vuelta antihorario lleva -Z a -X
+        const ORDER: [Facing; 4] = [Facing::North, Facing::West, Facing::South, Facing::East];
+        let index = ORDER.iter().position(|other| *other == facing).unwrap_or(0);
+        ORDER[(index + self.quarter_turns as usize) % 4]
+    }
+
+    // Un cuarto de vuelta alrededor de Y acuesta los troncos X sobre Z y viceversa
+    fn axis(&self, axis: Axis) -> Axis {
+        match axis {
+            Axis::X if self.quarter_turns % 2 == 1 => Axis::Z,
+            Axis::Z if self.quarter_turns % 2 == 1 => Axis::X,
+            _ => axis,
+        }
+    }
+
+    // Una transformación del objeto sin ubicar, vista después de ubicarlo: el objeto de adentro
+    // ya quedó girado, así que se gira antes de aplicarla y se deshace después
+    fn linear(&self, linear: &Mat3) -> Mat3 {
+        let turn = self.turn();
+        turn * linear * turn.transpose()
+    }
+
+    // El giro solo, como matriz
+    fn turn(&self) -> Mat3 {
+        Mat3::from_columns(&[self.direction(&Vec3::x()), self.direction(&Vec3::y()), self.direction(&Vec3::z())])
+    }
+
+    // Toda la ubicación como una matriz, la misma transformación que `point`
+    fn matrix(&self) -> Mat4 {
+        glm::translation(&self.offset) * glm::mat3_to_mat4(&(self.turn() * self.scale))
+    }
+
+    // Un nodo `name` con esta ubicación, padre de los nodos sueltos de `graph`. Los hijos de
+    // los nodos ya van en metros, así que solo se desplaza y se gira
+    fn wrap(&self, name: &str, graph: SceneGraph) -> SceneGraph {
+        let mut node = Node::new(name, None);
+        node.translation = self.offset;
+        node.rotation = [0.0, self.quarter_turns as f32 * 90.0, 0.0];
+        let mut wrapped = SceneGraph { nodes: vec![node] };
+        wrapped.extend(graph, Some(0));
+        wrapped
+    }
+
+    // `inner` aplicado primero y después este
+    fn then(&self, inner: &Placement) -> Placement {
+        Placement {
+            quarter_turns: (self.quarter_turns + inner.quarter_turns) % 4,
+            offset: self.point(&inner.offset),
+            scale: self.scale * inner.scale,
+        }
+    }
+}
+
+// Escena en archivo RON, por ejemplo:
+// (
+//     camera: Some((eye: (0, 1, 5), center: (0, 0, 0))),
+//     materials: { "rojo": (diffuse: (200, 40, 40), specular: 50, albedo: (0.8, 0.2)) },
+//     lights: [(position: (0, 4, 4), intensity: 0.9)],
+//     objects: [Cube(center: (0, 0, 0), size: 1.5, material: "rojo")],
+//     include: [(path: "casa.ron", at: (10, 0, 4), rotation: 90)],
+//     max_depth: Some(4),
+//     prototypes: { "poste": Cube(center: (0, 0.5, 0), size: 1, material: "rojo") },
+//     units: 0.01,
+//     portals: [(corner: (-1, 1, -3), edges: ((2, 0, 0), (0, 1.5, 0)))],
+//     groups: [(name: "postes", members: [0], tint: Some((255, 220, 180)))],
+// )
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub camera: Option<CameraDescription>,
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDescription>,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub background: Option<BackgroundDescription>,
+    #[serde(default)]
+    pub include: Vec<IncludeDescription>,
+    // Rebotes de los reflejos que necesita la escena (cristales frente a cristales)
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    // Objetos que se repiten con `Instance`, por nombre
+    #[serde(default)]
+    pub prototypes: BTreeMap<String, ObjectDescription>,
+    // Metros por unidad de la escena, por ejemplo 0.01 si está en centímetros. Al cargarla
+    // todo pasa a metros, así que la niebla, el sesgo de las sombras y demás ajustes se miden
+    // en metros sea cual sea la escena; la atenuación de las luces y la lente se convierten
+    #[serde(default = "default_one")]
+    pub units: f32,
+    // Ventanas y puertas por donde entra la luz del fondo, para el trazado de caminos
+    #[serde(default)]
+    pub portals: Vec<PortalDescription>,
+    // Solo los de este archivo; los de las escenas incluidas no se toman
+    #[serde(default)]
+    pub groups: Vec<GroupDescription>,
+}
+
+// Objetos, luces, nodos y portales que aporta una escena
+type Contents = (Vec<Object>, Vec<Light>, SceneGraph, Vec<Portal>);
+
+pub struct Scene {
+    pub objects: Vec<Object>,
+    pub lights: Vec<Light>,
+    // Sin cámara en el archivo se usa la de siempre
+    pub camera: Option<Camera>,
+    // Sin fondo en el archivo se usa el de los ajustes (el azul de siempre o --background)
+    pub background: Option<Background>,
+    // Sin rebotes en el archivo se usan los de los ajustes
+    pub max_depth: Option<u32>,
+    // Nodos de los objetos; `objects` ya tiene los hijos en el mundo
+    pub graph: SceneGraph,
+    pub portals: Vec<Portal>,
+    // Ya aplicados a `objects`
+    pub groups: Vec<Group>,
+}
+
+// Lo que se va armando al construir una escena desde su descripción: cada material y cada
+// prototipo se construye una sola vez y los objetos que lo usan lo comparten
+struct SceneBuilder<'a> {
+    description: &'a SceneDescription,
+    // Carpeta del archivo de la escena, de donde salen las rutas de `include`
+    dir: &'a Path,
+    // El archivo y su texto, para ubicar los errores (sin archivo, la carpeta y sin texto)
+    file: &'a Path,
+    source: Option<&'a str>,
+    materials: HashMap<String, Arc<Material>>,
+    prototypes: HashMap<String, Arc<dyn SceneObject>>,
+    building_prototype: bool,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn new(description: &'a SceneDescription, dir: &'a Path, file: &'a Path, source: Option<&'a str>) -> Self {
+        let (materials, prototypes) = (HashMap::new(), HashMap::new());
+        SceneBuilder { description, dir, file, source, materials, prototypes, building_prototype: false }
+    }
+
+    // Error de esta escena en su campo `key` o, con `index`, en un elemento de esa lista
+    fn invalid(&self, key: &str, index: Option<usize>, error: impl fmt::Display) -> SceneError {
+        SceneError::Invalid {
+            path: self.file.to_path_buf(),
+            line: self.source.and_then(|source| scene_error::locate(source, key, index)),
+            message: error.to_string(),
+        }
+    }
+
+    fn material(&mut self, name: &str) -> Result<Arc<Material>, Box<dyn Error>> {
+        if let Some(material) = self.materials.get(name) {
+            return Ok(material.clone());
+        }
+        let material = if let Some(description) = self.description.materials.get(name) {
+            let mut material = description.build(self.dir)?;
+            material.name = Some(name.to_string());
+            material
+        } else if name.ends_with(".ron") {
+            load_material(&self.dir.join(name))?
+        } else {
+            return Err(format!("material desconocido: {}", name).into());
+        };
+        let material = Arc::new(material);
+        self.materials.insert(name.to_string(), material.clone());
+        Ok(material)
+    }
+
+    // El prototipo sin ubicar, solo pasado a metros; cada instancia lo ubica con su transformación
+    fn prototype(&mut self, name: &str, scale: f32) -> Result<Arc<dyn SceneObject>, Box<dyn Error>> {
+        if let Some(prototype) = self.prototypes.get(name) {
+            return Ok(prototype.clone());
+        }
+        let description = self.description.prototypes.get(name).ok_or_else(|| format!("prototipo desconocido: {}", name))?;
+        if std::mem::replace(&mut self.building_prototype, true) {
+            return Err(format!("el prototipo {} no puede usarse dentro de otro prototipo", name).into());
+        }
+        let prototype = self.object(description, &Placement::scaled(scale));
+        self.building_prototype = false;
+        let prototype: Arc<dyn SceneObject> = Arc::from(prototype?);
+        self.prototypes.insert(name.to_string(), prototype.clone());
+        Ok(prototype)
+    }
+
+    fn object(&mut self, object: &ObjectDescription, placement: &Placement) -> Result<Object, Box<dyn Error>> {
+        Ok(match object {
+            ObjectDescription::Cube { center, size, material } => Box::new(Cube {
+                center: placement.point(&vec3(*center)),
+                size: placement.length(*size),
+                material: self.material(material)?,
+            }),
+            ObjectDescription::Sphere { center, radius, material } => Box::new(Sphere {
+                center: placement.point(&vec3(*center)),
+                radius: placement.length(*radius),
+                material: self.material(material)?,
+            }),
+            ObjectDescription::Block { center, size, shape, facing, upside_down, axis, material } => Box::new(Block {
+                center: placement.point(&vec3(*center)),
+                size: placement.length(*size),
+                shape: *shape,
+                facing: placement.facing(*facing),
+                upside_down: *upside_down,
+                axis: placement.axis(*axis),
+                material: self.material(material)?,
+            }),
+            ObjectDescription::Plane { point, normal, tile_size, material } => Box::new(Plane::new(
+                placement.point(&vec3(*point)),
+                placement.direction(&vec3(*normal)),
+                placement.length(*tile_size),
+                self.material(material)?,
+            )),
+            ObjectDescription::Moving { velocity, object } => Box::new(Moving {
+                object: self.object(object, placement)?,
+                velocity: placement.vector(&vec3(*velocity)),
+            }),
+            ObjectDescription::Transformed { translation, rotation, scale, object } => {
+                if scale.iter().any(|&value| value <= 0.0) {
+                    return Err("la escala de un objeto transformado debe ser positiva".into());
+                }
+                let linear = placement.linear(&transformed::linear(*rotation, vec3(*scale)));
+                let object = self.object(object, placement)?;
+                Box::new(
+                    Transformed::new(object, placement.vector(&vec3(*translation)), linear)
+                        .ok_or("la transformación aplasta el objeto")?,
+                )
+            }
+            // El prototipo queda en su lugar original; la instancia lo transforma alrededor de su
+            // centro y después la ubicación de la escena lo lleva, girado, a donde corresponde
+            ObjectDescription::Instance { prototype, translation, rotation, scale } => {
+                if scale.iter().any(|&value| value <= 0.0) {
+                    return Err(format!("la escala de una instancia de {} debe ser positiva", prototype).into());
+                }
+                let object = self.prototype(prototype, placement.scale)?;
+                let center = object.center();
+                let linear = placement.turn() * transformed::linear(*rotation, vec3(*scale));
+                let translation =
+                    placement.direction(&center) + placement.offset - center + placement.vector(&vec3(*translation));
+                Box::new(Transformed::new(object, translation, linear).ok_or("la transformación aplasta el objeto")?)
+            }
+            ObjectDescription::Node { name, .. } => {
+                return Err(format!("el nodo {} solo puede ir en objects o entre los hijos de otro nodo", name).into());
+            }
+        })
+    }
+
+    // Agrega `object` a `objects`, o sus hijos si es un nodo. Los hijos de un nodo quedan en
+    // su espacio, sin `placement` salvo las unidades: los ubica el nodo
+    fn add_object(
+        &mut self,
+        object: &ObjectDescription,
+        placement: &Placement,
+        parent: Option<usize>,
+        first_object: usize,
+        objects: &mut Vec<Object>,
+        graph: &mut SceneGraph,
+    ) -> Result<(), Box<dyn Error>> {
+        let ObjectDescription::Node { name, translation, rotation, scale, children } = object else {
+            let placement = if parent.is_some() { Placement::scaled(placement.scale) } else { *placement };
+            let object = self.object(object, &placement)?;
+            if let Some(parent) = parent {
+                graph.nodes[parent].members.push((first_object + objects.len(), object.box_clone()));
+            }
+            objects.push(object);
+            return Ok(());
+        };
+        if scale.iter().any(|&value| value <= 0.0) {
+            return Err(format!("la escala del nodo {} debe ser positiva", name).into());
+        }
+        let mut node = Node::new(name, parent);
+        node.translation = vec3(*translation) * placement.scale;
+        node.rotation = *rotation;
+        node.scale = vec3(*scale);
+        graph.nodes.push(node);
+        let index = graph.nodes.len() - 1;
+        for child in children {
+            self.add_object(child, placement, Some(index), first_object, objects, graph)?;
+        }
+        Ok(())
+    }
+
+    // Objetos, luces y nodos de esta escena y de las que incluye, ya ubicados con `placement`.
+    // Los nodos de una escena incluida quedan bajo un nodo llamado `name` con esa ubicación.
+    // `first_object` es el índice que tendrá el primer objeto, para corregir los enlaces de luz
+    fn contents(
+        &mut self,
+        placement: &Placement,
+        name: Option<&str>,
+        first_object: usize,
+        depth: u32,
+    ) -> Result<Contents, SceneError> {
+        let mut objects = Vec::new();
+        let mut graph = SceneGraph::default();
+        let (description, dir) = (self.description, self.dir);
+        if description.units <= 0.0 {
+            return Err(self.invalid("units", None, "units debe ser positivo"));
+        }
+        for (index, object) in description.objects.iter().enumerate() {
+            self.add_object(object, placement, None, first_object, &mut objects, &mut graph)
+                .map_err(|error| self.invalid("objects", Some(index), error))?;
+        }
+        if let Some(name) = name
+            && !graph.nodes.is_empty()
+        {
+            graph = placement.wrap(name, graph);
+        }
+
+        let light = |description: &LightDescription| -> Result<Light, Box<dyn Error>> {
+            let [r, g, b] = description.color;
+            let position = placement.point(&vec3(description.position));
+            let mut light = Light::new(position, Color::from_srgb(r, g, b), description.intensity);
+            light.casts_shadows = description.casts_shadows;
+            light.shadow_only = description.shadow_only;
+            // La atenuación va con la distancia al cuadrado
+            light.attenuation = description.attenuation.max(0.0) / (placement.scale * placement.scale);
+            let direction = description.direction.map(|direction| placement.direction(&vec3(direction)));
+            if direction.is_some_and(|direction| direction.norm() == 0.0) {
+                return Err("la dirección de una luz no puede ser (0, 0, 0)".into());
+            }
+            light.kind = match (direction, description.cone) {
+                (_, Some([inner, outer])) => LightKind::Spot {
+                    direction: direction.unwrap_or(Vec3::new(0.0, -1.0, 0.0)),
+                    inner: inner.to_radians(),
+                    outer: outer.to_radians(),
+                },
+                (Some(direction), None) => LightKind::Directional { direction },
+                (None, None) => LightKind::Point,
+            };
+            if description.radius > 0.0 {
+                light.area = Some(AreaShape::Sphere { radius: placement.length(description.radius) });
+            }
+            if let Some([width, depth]) = description.panel {
+                let (width, depth) = if placement.quarter_turns % 2 == 1 { (depth, width) } else { (width, depth) };
+                light.area =
+                    Some(AreaShape::Rectangle { width: placement.length(width), depth: placement.length(depth) });
+            }
+            let shifted = |indices: &[usize]| indices.iter().map(|index| index + first_object).collect::<Vec<_>>();
+            light.links = LightLinks {
+                include: description.include.as_deref().map(shifted),
+                exclude: shifted(&description.exclude),
+            };
+            if let Some(path) = &description.ies {
+                light.profile = Some(IesProfile::load(&dir.join(path))?);
+            }
+            Ok(light)
+        };
+        let mut lights = description
+            .lights
+            .iter()
+            .enumerate()
+            .map(|(index, description)| light(description).map_err(|error| self.invalid("lights", Some(index), error)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut portals = description
+            .portals
+            .iter()
+            .enumerate()
+            .map(|(index, portal)| {
+                let edges = portal.edges.map(|edge| placement.vector(&vec3(edge)));
+                Portal::new(placement.point(&vec3(portal.corner)), edges)
+                    .ok_or_else(|| self.invalid("portals", Some(index), "los lados de un portal deben formar un rectángulo"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (index, include) in description.include.iter().enumerate() {
+            if depth >= MAX_INCLUDE_DEPTH {
+                let message = format!("{}: demasiadas escenas anidadas (¿se incluye a sí misma?)", include.path);
+                return Err(self.invalid("include", Some(index), message));
+            }
+            let path = dir.join(&include.path);
+            // Un modelo glTF aporta sus mallas y sus luces, ya en metros
+            if gltf::is_gltf(&path) {
+                let scale = 1.0 / self.description.units;
+                let included = placement.then(&Placement::new(include, scale).map_err(|error| self.invalid("include", Some(index), error))?);
+                let imported = gltf::import(&path, &included.matrix(), include.flat)
+                    .map_err(|error| SceneError::Invalid { path: path.clone(), line: None, message: error.to_string() })?;
+                objects.extend(imported.objects);
+                lights.extend(imported.lights);
+                continue;
+            }
+            let (source, description) = read_scene(&path)?;
+            let scale = description.units / self.description.units;
+            let included = placement.then(&Placement::new(include, scale).map_err(|error| self.invalid("include", Some(index), error))?);
+            let dir = path.parent().unwrap_or(Path::new(""));
+            let (included_objects, included_lights, included_graph, included_portals) = SceneBuilder::new(&description, dir, &path, Some(&source))
+                .contents(&included, Some(&include.path), first_object + objects.len(), depth + 1)?;
+            objects.extend(included_objects);
+            lights.extend(included_lights);
+            portals.extend(included_portals);
+            // Sus nodos ya vienen ubicados en el mundo
+            graph.extend(included_graph, None);
+        }
+        Ok((objects, lights, graph, portals))
+    }
+}
+
+impl SceneDescription {
+    // Escena vacía, en metros, que incluye el archivo `path`
+    fn including(path: &str) -> Self {
+        SceneDescription {
+            camera: None,
+            materials: BTreeMap::new(),
+            lights: Vec::new(),
+            objects: Vec::new(),
+            background: None,
+            include: vec![IncludeDescription { path: path.to_string(), at: [0.0; 3], rotation: 0.0, flat: false }],
+            max_depth: None,
+            prototypes: BTreeMap::new(),
+            units: 1.0,
+            portals: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    // Las rutas de `include`, materiales, texturas, perfiles IES y fondos se resuelven desde
+    // la carpeta del archivo de la escena (`builder.dir`)
+    fn build(&self, mut builder: SceneBuilder) -> Result<Scene, SceneError> {
+        let placement = Placement::scaled(self.units);
+        let (mut objects, lights, graph, portals) = builder.contents(&placement, None, 0, 0)?;
+        graph.flatten(&mut objects);
+
+        let camera = self.camera.as_ref().map(|description| {
+            let eye = placement.point(&vec3(description.eye));
+            let mut camera = Camera::new(eye, placement.point(&vec3(description.center)), vec3(description.up));
+            if let Some(fov) = description.fov {
+                camera.fov = fov.to_radians();
+            }
+            camera.aperture = placement.length(description.aperture.max(0.0));
+            camera.focus_distance = description.focus_distance.map(|distance| placement.length(distance));
+            camera
+        });
+
+        let background = self.background.as_ref().map(|background| background.build(builder.dir));
+        let background = background.transpose().map_err(|error| builder.invalid("background", None, error))?;
+
+        let mut groups = Vec::new();
+        for (index, description) in self.groups.iter().enumerate() {
+            if let Some(member) = description.members.iter().find(|&&member| member >= objects.len()) {
+                let message = format!("el grupo {} usa object.{}, pero hay {} objetos", description.name, member, objects.len());
+                return Err(builder.invalid("groups", Some(index), message));
+            }
+            let mut group = Group::new(&description.name, description.members.clone());
+            if let Some(name) = &description.material {
+                let material = builder.material(name).map_err(|error| builder.invalid("groups", Some(index), error))?;
+                group.material = Some(material.as_ref().clone());
+            }
+            group.tint = description.tint.map(|[r, g, b]| Color::from_srgb(r, g, b));
+            group.apply(&mut objects);
+            groups.push(group);
+        }
+
+        Ok(Scene { objects, lights, camera, background, max_depth: self.max_depth, graph, portals, groups })
+    }
+}
+
+// Cambia un parámetro ya cargado, por ejemplo `camera.fov=45` o `lights[0].position=0,4,5`.
+// Cámara aparte, las rutas son las del `set` de la consola (light, object, material, node)
+pub fn apply_override(
+    assignment: &str,
+    objects: &mut Vec<Object>,
+    lights: &mut [Light],
+    graph: &mut SceneGraph,
+    camera: &mut Camera,
+) -> Result<(), String> {
+    let (path, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("se esperaba ruta=valor: {}", assignment))?;
+    let values: Vec<&str> = value.split(',').map(str::trim).collect();
+    let parse_vec3 = || -> Result<Vec3, String> {
+        let numbers = values
+            .iter()
+            .map(|value| value.parse::<f32>().map_err(|_| format!("número inválido: {}", value)))
+            .collect::<Result<Vec<_>, _>>()?;
+        match numbers.as_slice() {
+            [x, y, z] => Ok(Vec3::new(*x, *y, *z)),
+            _ => Err("se esperaban 3 valores".to_string()),
+        }
+    };
+
+    match path.trim() {
+        "camera.fov" => {
+            let fov: f32 = value.trim().parse().map_err(|_| format!("número inválido: {}", value))?;
+            camera.fov = fov.to_radians();
+        }
+        "camera.aperture" => {
+            camera.aperture = value.trim().parse::<f32>().map_err(|_| format!("número inválido: {}", value))?.max(0.0);
+        }
+        "camera.focus_distance" => {
+            let distance: f32 = value.trim().parse().map_err(|_| format!("número inválido: {}", value))?;
+            camera.focus_distance = (distance > 0.0).then_some(distance);
+        }
+        "camera.eye" | "camera.position" => camera.position = parse_vec3()?,
+        "camera.center" => camera.center = parse_vec3()?,
+        "camera.up" => camera.up = parse_vec3()?,
+        path => {
+            // lights[0].intensity -> light.0.intensity
+            let path = path.replace('[', ".").replace(']', "");
+            let path = match path.split_once('.') {
+                Some((collection, rest)) => format!("{}.{}", collection.trim_end_matches('s'), rest),
+                None => path,
+            };
+            let command = format!("set {} {}", path, values.join(" "));
+            console::execute(&command, objects, lights, &mut Vec::new(), graph)?;
+        }
+    }
+    Ok(())
+}
+
+// El texto del archivo y la descripción que sale de él
+fn read_scene(path: &Path) -> Result<(String, SceneDescription), SceneError> {
+    let source = std::fs::read_to_string(path).map_err(|error| SceneError::Io { path: path.to_path_buf(), error })?;
+    let description = ron::from_str(&source).map_err(|error: ron::error::SpannedError| SceneError::Syntax {
+        path: path.to_path_buf(),
+        line: error.span.start.line,
+        column: error.span.start.col,
+        message: error.code.to_string(),
+    })?;
+    Ok((source, description))
+}
+
+pub fn parse_scene(path: &Path) -> Result<SceneDescription, SceneError> {
+    read_scene(path).map(|(_, description)| description)
+}
+
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    // Un modelo glTF suelto es una escena que solo lo incluye a él
+    if gltf::is_gltf(path) {
+        let description = SceneDescription::including(&path.file_name().unwrap_or_default().to_string_lossy());
+        return description.build(SceneBuilder::new(&description, dir, path, None));
+    }
+    let (source, description) = read_scene(path)?;
+    description.build(SceneBuilder::new(&description, dir, path, Some(&source)))
+}
+
+// Guarda en el archivo de la escena cómo quedaron sus luces después de editarlas y devuelve
+// cuántas guardó. Son las primeras de `lights`; las de las escenas incluidas y las que agrega
+// el cielo no se tocan. El archivo se escribe entero de nuevo, sin sus comentarios
+pub fn save_lights(path: &Path, lights: &[Light]) -> Result<usize, SceneError> {
+    let (_, mut description) = read_scene(path)?;
+    let units = description.units;
+    for (saved, light) in description.lights.iter_mut().zip(lights) {
+        saved.update(light, units);
+    }
+    let invalid = |error: ron::Error| SceneError::Invalid { path: path.to_path_buf(), line: None, message: error.to_string() };
+    let text = ron::ser::to_string_pretty(&description, ron::ser::PrettyConfig::default()).map_err(invalid)?;
+    std::fs::write(path, text).map_err(|error| SceneError::Io { path: path.to_path_buf(), error })?;
+    Ok(description.lights.len().min(lights.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::ray::Ray;
+
+    // Una escena con un poco de todo lo que se puede describir, sin archivos externos
+    const SCENE: &str = r#"(
+        camera: Some((eye: (0, 1, 5), center: (0, 0, 0), fov: Some(50), aperture: 0.1, focus_distance: Some(5))),
+        materials: {
+            "rojo": (diffuse: (200, 40, 40), specular: 50, albedo: (0.8, 0.2)),
+            "metal": (pbr: Some((metallic: 1, roughness: 0.3)), reflectivity: 0.5, variation: 0.1),
+            "vidrio": (crystal: true, ior: 1.5, opacity: 0.4, emissive: (10, 10, 10), max_depth: Some(4)),
+        },
+        lights: [
+            (position: (0, 4, 4), intensity: 0.9, attenuation: 0.1, radius: 0.5),
+            (position: (0, 0, 0), direction: Some((0, -1, 0))),
+            (position: (1, 3, 0), cone: Some((20, 30)), panel: Some((1, 2)), include: Some([0, 1]), exclude: [2]),
+        ],
+        objects: [
+            Cube(center: (0, 0, 0), size: 1.5, material: "rojo"),
+            Sphere(center: (2, 0, 0), radius: 0.5, material: "metal"),
+            Block(center: (0, 0, -2), size: 1, shape: Stairs, facing: East, upside_down: true, material: "rojo"),
+            Block(center: (1, 0, -2), size: 1, shape: Log, axis: X, material: "vidrio"),
+            Plane(point: (0, -1, 0), normal: (0, 1, 0), tile_size: 2, material: "rojo"),
+            Moving(velocity: (1, 0, 0), object: Sphere(center: (0, 2, 0), radius: 0.3, material: "vidrio")),
+            Transformed(translation: (1, 0, 0), rotation: (0, 45, 30), scale: (2, 0.5, 1), object: Cube(center: (0, 0, 0), size: 1, material: "metal")),
+            Node(name: "grupo", translation: (2, 0, 0), rotation: (0, 30, 0), children: [
+                Cube(center: (0, 0, 0), size: 1, material: "rojo"),
+                Instance(prototype: "poste", translation: (0, 1, 0)),
+            ]),
+            Instance(prototype: "poste", translation: (4, 0, 2), rotation: (0, 90, 0), scale: (1, 2, 1)),
+        ],
+        background: Some(Gradient(horizon: (200, 220, 240), zenith: (60, 110, 200))),
+        max_depth: Some(3),
+        prototypes: { "poste": Cube(center: (0, 0.5, 0), size: 1, material: "rojo") },
+        units: 0.5,
+        portals: [(corner: (-1, 1, -3), edges: ((2, 0, 0), (0, 1.5, 0)))],
+    )"#;
+
+    const NUMBERS: [&str; 8] = ["0", "-1", "-0", "1e30", "-1e30", "NaN", "inf", "0.000001"];
+    const PUNCTUATION: &[u8] = b"(){}[],:\"-. 0123456789eSomeNn";
+
+    fn parse_and_build(text: &str) -> Result<Scene, Box<dyn Error>> {
+        let description: SceneDescription = ron::from_str(text)?;
+        let here = Path::new("");
+        Ok(description.build(SceneBuilder::new(&description, here, here, Some(text)))?)
+    }
+
+    // Borra un tramo, mete un carácter, cambia un número por uno extremo o corta el texto
+    fn mutate(text: &mut String, rng: &mut StdRng) {
+        let at = rng.gen_range(0..text.len());
+        match rng.gen_range(0..4) {
+            0 => {
+                let end = (at + rng.gen_range(1..16)).min(text.len());
+                text.replace_range(at..end, "");
+            }
+            1 => text.insert(at, PUNCTUATION[rng.gen_range(0..PUNCTUATION.len())] as char),
+            2 => {
+                let Some(start) = text[at..].find(|c: char| c.is_ascii_digit()).map(|start| at + start) else { return };
+                let end = text[start..].find(|c: char| !c.is_ascii_digit() && c != '.').map_or(text.len(), |end| start + end);
+                text.replace_range(start..end, NUMBERS[rng.gen_range(0..NUMBERS.len())]);
+            }
+            _ => text.truncate(at),
+        }
+    }
+
+    // Una carpeta propia para los archivos de cada prueba, que se borra al terminar la prueba
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_scenes(test: &str, files: &[(&str, &str)]) -> TestDir {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let unique = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("cubito-{}-{}-{}", test, std::process::id(), unique));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, text) in files {
+            std::fs::write(dir.join(name), text).unwrap();
+        }
+        TestDir(dir)
+    }
+
+    fn load_error(path: &Path) -> SceneError {
+        match load_scene(path) {
+            Ok(_) => panic!("{} cargó sin error", path.display()),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn load_errors_name_the_file() {
+        let dir = write_scenes("errores", &[("rota.ron", "(\n    objects: [Cube(center: (0, 0, 0), size: )],\n)")]);
+        let missing = dir.join("no_existe.ron");
+        assert!(matches!(load_error(&missing), SceneError::Io { path, .. } if path == missing));
+        let broken = dir.join("rota.ron");
+        let error = load_error(&broken);
+        // La posición del error de sintaxis
+        assert!(matches!(&error, SceneError::Syntax { path, line: 2, .. } if *path == broken), "{}", error);
+        assert!(error.to_string().starts_with(&format!("{}:2:", broken.display())), "{}", error);
+    }
+
+    // Lo que falla al armar la escena lleva la línea del objeto, la luz o la inclusión, también
+    // dentro de una escena incluida
+    #[test]
+    fn invalid_scenes_name_the_file_and_line() {
+        let dir = write_scenes(
+            "lineas",
+            &[
+                (
+                    "casa.ron",
+                    r#"(
+                        materials: { "gris": () },
+                        objects: [
+                            Cube(center: (0, 0, 0), size: 1, material: "gris"),
+                            Cube(center: (0, 0, 0), size: 1, material: "azul"),
+                        ],
+                    )"#,
+                ),
+                (
+                    "barrio.ron",
+                    r#"(
+                        lights: [
+                            (position: (0, 4, 0)),
+                            (position: (0, 4, 0), direction: Some((0, 0, 0))),
+                        ],
+                        include: [(path: "casa.ron")],
+                    )"#,
+                ),
+                ("calle.ron", "(\n    include: [\n        (path: \"barrio.ron\", rotation: 45),\n    ],\n)"),
+            ],
+        );
+        let cases = [("casa.ron", "casa.ron", 5), ("barrio.ron", "barrio.ron", 4), ("calle.ron", "calle.ron", 3)];
+        for (file, failing, expected) in cases {
+            match load_error(&dir.join(file)) {
+                SceneError::Invalid { path, line, .. } => {
+                    assert_eq!((path, line), (dir.join(failing), Some(expected)), "{}", file);
+                }
+                error => panic!("{}: {}", file, error),
+            }
+        }
+    }
+
+    // Los grupos del archivo llegan aplicados y se pueden deshacer con su material original
+    #[test]
+    fn groups_load_applied_and_can_be_undone() {
+        let dir = write_scenes(
+            "grupos",
+            &[
+                (
+                    "sillas.ron",
+                    r#"(
+                        materials: { "gris": (diffuse: (100, 100, 100)), "madera": (diffuse: (120, 80, 40)) },
+                        objects: [
+                            Cube(center: (0, 0, 0), size: 1, material: "gris"),
+                            Cube(center: (2, 0, 0), size: 1, material: "gris"),
+                        ],
+                        groups: [(name: "sillas", members: [1], material: Some("madera"))],
+                    )"#,
+                ),
+                (
+                    "fuera.ron",
+                    r#"(
+                        materials: { "gris": () },
+                        objects: [Cube(center: (0, 0, 0), size: 1, material: "gris")],
+                        groups: [
+                            (name: "sillas", members: [0]),
+                            (name: "mesas", members: [1]),
+                        ],
+                    )"#,
+                ),
+            ],
+        );
+        let mut scene = load_scene(&dir.join("sillas.ron")).unwrap();
+        let gray = scene.objects[0].material().diffuse;
+        assert_ne!(scene.objects[1].material().diffuse, gray);
+        assert_eq!(scene.groups[0].name, "sillas");
+
+        scene.groups[0].material = None;
+        scene.groups[0].apply(&mut scene.objects);
+        assert_eq!(scene.objects[1].material().diffuse, gray);
+
+        match load_error(&dir.join("fuera.ron")) {
+            SceneError::Invalid { line, message, .. } => {
+                assert_eq!(line, Some(6));
+                assert!(message.contains("object.1"), "{}", message);
+            }
+            error => panic!("{}", error),
+        }
+    }
+
+    // Un .gltf se incluye como otra escena, o se carga solo
+    #[test]
+    fn includes_gltf_models() {
+        let gltf = crate::gltf::tests::triangle_gltf();
+        let dir = write_scenes(
+            "gltf",
+            &[
+                ("triangulo.gltf", gltf.as_str()),
+                ("sala.ron", r#"(units: 0.5, include: [(path: "triangulo.gltf", at: (10, 0, 0), rotation: 90)])"#),
+                ("plana.ron", r#"(units: 0.5, include: [(path: "triangulo.gltf", at: (10, 0, 0), rotation: 90, flat: true)])"#),
+            ],
+        );
+        let alone = load_scene(&dir.join("triangulo.gltf")).unwrap();
+        assert_eq!((alone.objects.len(), alone.lights.len()), (1, 1));
+        assert_eq!(alone.lights[0].position, Vec3::new(0.0, 3.0, 0.0));
+
+        // El modelo está en metros y la sala en medios metros: queda a 5 m, girado
+        let room = load_scene(&dir.join("sala.ron")).unwrap();
+        assert_eq!(room.objects.len(), 1);
+        assert!((room.lights[0].position - Vec3::new(5.0, 3.0, 0.0)).norm() < 1e-5);
+        let bounds = room.objects[0].bounds().unwrap();
+        assert!((bounds.min - Vec3::new(2.0, 0.0, 0.0)).norm() < 1e-4, "{:?}", bounds);
+        assert!((bounds.max - Vec3::new(3.0, 1.0, 0.0)).norm() < 1e-4, "{:?}", bounds);
+
+        // Con `flat` se ve la cara y no las normales del archivo
+        let normal = |scene: &Scene| scene.objects[0].ray_intersect(&Ray::new(Vec3::new(2.8, 0.2, -5.0), Vec3::z())).normal;
+        assert!((normal(&room) - Vec3::new(0.0, 0.6, -0.8)).norm() < 1e-4, "{:?}", normal(&room));
+        let flat = load_scene(&dir.join("plana.ron")).unwrap();
+        assert!((normal(&flat) + Vec3::z()).norm() < 1e-4, "{:?}", normal(&flat));
+    }
+
+    #[test]
+    fn load_errors_explain_what_is_wrong() {
+        let dir = write_scenes(
+            "contenido",
+            &[
+                ("material.ron", r#"(objects: [Cube(center: (0, 0, 0), size: 1, material: "azul")])"#),
+                ("prototipo.ron", r#"(objects: [Instance(prototype: "árbol")])"#),
+                ("unidades.ron", "(units: 0)"),
+                ("incluye.ron", r#"(include: [(path: "falta.ron")])"#),
+                ("ciclo.ron", r#"(include: [(path: "ciclo.ron")])"#),
+                ("giro.ron", r#"(include: [(path: "ciclo.ron", rotation: 45)])"#),
+            ],
+        );
+        let cases = [
+            ("material.ron", "material desconocido: azul"),
+            ("prototipo.ron", "prototipo desconocido: árbol"),
+            ("unidades.ron", "units debe ser positivo"),
+            ("incluye.ron", "falta.ron"),
+            ("ciclo.ron", "demasiadas escenas anidadas"),
+            ("giro.ron", "múltiplo de 90"),
+        ];
+        for (file, expected) in cases {
+            let error = load_error(&dir.join(file)).to_string();
+            assert!(error.contains(expected), "{}: {}", file, error);
+        }
+    }
+
+    // Las rutas de `include` son relativas al archivo que incluye
+    #[test]
+    fn includes_resolve_next_to_the_scene() {
+        let dir = write_scenes(
+            "incluidas",
+            &[
+                ("casa.ron", r#"(materials: { "gris": () }, objects: [Cube(center: (0, 0, 0), size: 1, material: "gris")])"#),
+                ("barrio.ron", r#"(include: [(path: "casa.ron", at: (10, 0, 0)), (path: "casa.ron", at: (-10, 0, 0), rotation: 90)])"#),
+            ],
+        );
+        let scene = load_scene(&dir.join("barrio.ron")).unwrap();
+        let centers: Vec<f32> = scene.objects.iter().map(|object| object.center().x).collect();
+        assert_eq!(centers, [10.0, -10.0]);
+    }
+
+    // Un nodo de una escena incluida, también dentro de otra inclusión, queda donde queda el
+    // mismo cubo fuera del nodo
+    #[test]
+    fn included_nodes_follow_the_include_placement() {
+        let dir = write_scenes(
+            "nodos-incluidos",
+            &[
+                (
+                    "casa.ron",
+                    r#"(materials: { "gris": () }, objects: [
+                        Cube(center: (1, 0, 2), size: 1, material: "gris"),
+                        Node(name: "techo", translation: (1, 0, 2), children: [Cube(center: (0, 0, 0), size: 1, material: "gris")]),
+                    ])"#,
+                ),
+                ("barrio.ron", r#"(include: [(path: "casa.ron", at: (10, 0, 0), rotation: 90)])"#),
+                ("ciudad.ron", r#"(include: [(path: "barrio.ron", at: (0, 3, 5), rotation: 180)])"#),
+            ],
+        );
+        for (file, expected) in [("barrio.ron", Vec3::new(12.0, 0.0, -1.0)), ("ciudad.ron", Vec3::new(-12.0, 3.0, 6.0))] {
+            let scene = load_scene(&dir.join(file)).unwrap();
+            for object in &scene.objects {
+                assert!((object.center() - expected).norm() < 1e-4, "{}: {:?}", file, object.center());
+            }
+        }
+    }
+
+    // Lo editado en las luces vuelve al archivo en las unidades de la escena, y las luces
+    // que no son del archivo se ignoran
+    #[test]
+    fn saved_lights_load_back_as_edited() {
+        let dir = write_scenes(
+            "guardar",
+            &[(
+                "luces.ron",
+                "(units: 0.5, lights: [(position: (0, 4, 0)), (position: (0, 0, 0), direction: Some((0, -1, 0)))])",
+            )],
+        );
+        let path = dir.join("luces.ron");
+        let mut lights = load_scene(&path).unwrap().lights;
+        lights[0].position = Vec3::new(1.0, 2.0, 3.0);
+        lights[0].area = Some(AreaShape::Sphere { radius: 0.25 });
+        lights[1].kind = LightKind::Directional { direction: Vec3::new(1.0, 0.0, 0.0) };
+        lights.push(Light::new(Vec3::zeros(), Color::black(), 1.0));
+        assert_eq!(save_lights(&path, &lights).unwrap(), 2);
+
+        let saved = load_scene(&path).unwrap().lights;
+        assert_eq!(saved.len(), 2);
+        assert!((saved[0].position - lights[0].position).norm() < 1e-5, "{:?}", saved[0].position);
+        assert!(matches!(saved[0].area, Some(AreaShape::Sphere { radius }) if (radius - 0.25).abs() < 1e-5));
+        assert!(matches!(saved[1].kind, LightKind::Directional { direction } if direction == Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(parse_scene(&path).unwrap().lights[0].position, [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn builds_every_kind_of_description() {
+        let scene = parse_and_build(SCENE).unwrap();
+        // El nodo aporta sus dos hijos
+        assert_eq!(scene.objects.len(), 10);
+        assert_eq!(scene.lights.len(), 3);
+        assert_eq!(scene.portals.len(), 1);
+        assert_eq!(scene.graph.nodes.len(), 1);
+        assert!(scene.camera.is_some() && scene.background.is_some());
+    }
+
+    // Un archivo roto, o bien formado pero con valores absurdos, da un error y nunca un pánico
+    #[test]
+    fn mutated_scenes_fail_without_panicking() {
+        let mut rng = StdRng::seed_from_u64(464);
+        for _ in 0..5000 {
+            let mut text = SCENE.to_string();
+            for _ in 0..rng.gen_range(1..4) {
+                if !text.is_empty() {
+                    mutate(&mut text, &mut rng);
+                }
+            }
+            let _ = parse_and_build(&text);
+        }
+    }
+}
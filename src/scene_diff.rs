@@ -0,0 +1,225 @@
+// scene_diff.rs
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::scene::{ObjectDescription, SceneDescription};
+
+fn kind(object: &ObjectDescription) -> &'static str {
+    match object {
+        ObjectDescription::Cube { .. } => "Cube",
+        ObjectDescription::Sphere { .. } => "Sphere",
+        ObjectDescription::Block { .. } => "Block",
+        ObjectDescription::Plane { .. } => "Plane",
+        ObjectDescription::Moving { .. } => "Moving",
+        ObjectDescription::Transformed { .. } => "Transformed",
+        ObjectDescription::Node { .. } => "Node",
+        ObjectDescription::Instance { .. } => "Instance",
+    }
+}
+
+// Dónde está el objeto: su centro o su desplazamiento
+fn position(object: &ObjectDescription) -> [f32; 3] {
+    match object {
+        ObjectDescription::Cube { center, .. }
+        | ObjectDescription::Sphere { center, .. }
+        | ObjectDescription::Block { center, .. } => *center,
+        ObjectDescription::Plane { point, .. } => *point,
+        ObjectDescription::Moving { object, .. } => position(object),
+        ObjectDescription::Transformed { translation, .. }
+        | ObjectDescription::Node { translation, .. }
+        | ObjectDescription::Instance { translation, .. } => *translation,
+    }
+}
+
+fn moved_to(object: &ObjectDescription, to: [f32; 3]) -> ObjectDescription {
+    let mut object = object.clone();
+    match &mut object {
+        ObjectDescription::Cube { center, .. }
+        | ObjectDescription::Sphere { center, .. }
+        | ObjectDescription::Block { center, .. } => *center = to,
+        ObjectDescription::Plane { point, .. } => *point = to,
+        ObjectDescription::Moving { object, .. } => **object = moved_to(object, to),
+        ObjectDescription::Transformed { translation, .. }
+        | ObjectDescription::Node { translation, .. }
+        | ObjectDescription::Instance { translation, .. } => *translation = to,
+    }
+    object
+}
+
+// Iguales salvo por dónde están
+fn only_moved(old: &ObjectDescription, new: &ObjectDescription) -> bool {
+    moved_to(old, position(new)) == *new
+}
+
+fn format_position([x, y, z]: [f32; 3]) -> String {
+    format!("({}, {}, {})", x, y, z)
+}
+
+// Para cada objeto de `old`, el índice del mismo objeto en `new`, si sigue. Los objetos no
+// tienen nombre, así que se emparejan primero los idénticos, después los que solo se movieron
+// y por último los del mismo tipo que quedaron en el mismo lugar de la lista, que cambiaron
+fn match_objects(old: &[ObjectDescription], new: &[ObjectDescription]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; old.len()];
+    let mut taken = vec![false; new.len()];
+    let passes: [&dyn Fn(usize, usize) -> bool; 3] = [
+        &|i, j| old[i] == new[j],
+        &|i, j| only_moved(&old[i], &new[j]),
+        &|i, j| i == j && kind(&old[i]) == kind(&new[j]),
+    ];
+    for same in passes {
+        for (i, found) in matches.iter_mut().enumerate() {
+            if found.is_some() {
+                continue;
+            }
+            if let Some(j) = (0..new.len()).find(|&j| !taken[j] && same(i, j)) {
+                *found = Some(j);
+                taken[j] = true;
+            }
+        }
+    }
+    matches
+}
+
+// Los objetos de `new` que no vienen de ninguno de `old`
+fn added<'a>(matches: &[Option<usize>], new: &'a [ObjectDescription]) -> impl Iterator<Item = (usize, &'a ObjectDescription)> {
+    new.iter().enumerate().filter(move |(j, _)| !matches.contains(&Some(*j)))
+}
+
+fn diff_map<T: PartialEq>(name: &str, old: &BTreeMap<String, T>, new: &BTreeMap<String, T>, lines: &mut Vec<String>) {
+    for (key, value) in old {
+        match new.get(key) {
+            None => lines.push(format!("- {} \"{}\"", name, key)),
+            Some(other) if other != value => lines.push(format!("~ {} \"{}\"", name, key)),
+            Some(_) => {}
+        }
+    }
+    for key in new.keys().filter(|key| !old.contains_key(*key)) {
+        lines.push(format!("+ {} \"{}\"", name, key));
+    }
+}
+
+// Lo que cambió de `old` a `new`, una línea por cambio: + agregado, - quitado, ~ modificado
+pub fn diff(old: &SceneDescription, new: &SceneDescription) -> Vec<String> {
+    let mut lines = Vec::new();
+    let matches = match_objects(&old.objects, &new.objects);
+    for (i, (object, found)) in old.objects.iter().zip(&matches).enumerate() {
+        let Some(j) = *found else {
+            lines.push(format!("- objects[{}] {} en {}", i, kind(object), format_position(position(object))));
+            continue;
+        };
+        let other = &new.objects[j];
+        let index = if i == j { format!("objects[{}]", i) } else { format!("objects[{}] -> objects[{}]", i, j) };
+        if other == object {
+            continue;
+        }
+        if only_moved(object, other) {
+            let (from, to) = (format_position(position(object)), format_position(position(other)));
+            lines.push(format!("~ {} {} movido de {} a {}", index, kind(object), from, to));
+        } else {
+            lines.push(format!("~ {} {} modificado", index, kind(object)));
+        }
+    }
+    for (j, object) in added(&matches, &new.objects) {
+        lines.push(format!("+ objects[{}] {} en {}", j, kind(object), format_position(position(object))));
+    }
+
+    diff_map("material", &old.materials, &new.materials, &mut lines);
+    diff_map("prototype", &old.prototypes, &new.prototypes, &mut lines);
+    for i in 0..old.lights.len().max(new.lights.len()) {
+        match (old.lights.get(i), new.lights.get(i)) {
+            (Some(_), None) => lines.push(format!("- lights[{}]", i)),
+            (None, Some(_)) => lines.push(format!("+ lights[{}]", i)),
+            (Some(light), Some(other)) if light != other => lines.push(format!("~ lights[{}]", i)),
+            _ => {}
+        }
+    }
+    let fields = [
+        ("camera", old.camera != new.camera),
+        ("background", old.background != new.background),
+        ("include", old.include != new.include),
+        ("max_depth", old.max_depth != new.max_depth),
+        ("units", old.units != new.units),
+        ("portals", old.portals != new.portals),
+        ("groups", old.groups != new.groups),
+    ];
+    lines.extend(fields.iter().filter(|(_, changed)| *changed).map(|(name, _)| format!("~ {}", name)));
+    lines
+}
+
+// El valor que queda si cada lado cambió a lo sumo una vez: el cambio del que lo tocó. Si los
+// dos lo cambiaron distinto es un conflicto y, para seguir, queda el de `ours`
+fn merge_value<T: PartialEq + Clone>(name: &str, base: &T, ours: &T, theirs: &T, conflicts: &mut Vec<String>) -> T {
+    if ours == theirs || theirs == base {
+        ours.clone()
+    } else if ours == base {
+        theirs.clone()
+    } else {
+        conflicts.push(name.to_string());
+        ours.clone()
+    }
+}
+
+fn merge_map<T: PartialEq + Clone>(
+    name: &str,
+    base: &BTreeMap<String, T>,
+    ours: &BTreeMap<String, T>,
+    theirs: &BTreeMap<String, T>,
+    conflicts: &mut Vec<String>,
+) -> BTreeMap<String, T> {
+    let keys: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let [base, ours, theirs] = [base, ours, theirs].map(|map| map.get(key).cloned());
+            merge_value(&format!("{} \"{}\"", name, key), &base, &ours, &theirs, conflicts).map(|value| (key.clone(), value))
+        })
+        .collect()
+}
+
+// Mezcla de tres vías: los cambios de `ours` y de `theirs` respecto de `base`, su versión en
+// común. Los objetos se emparejan como en `diff` y los agregados de los dos lados van al final.
+// Los enlaces de luz usan índices de objetos y no se corrigen si la lista se reordena.
+// Devuelve las partes que los dos lados cambiaron de forma distinta si las hay
+pub fn merge(base: &SceneDescription, ours: &SceneDescription, theirs: &SceneDescription) -> Result<SceneDescription, Vec<String>> {
+    let mut conflicts = Vec::new();
+
+    let ours_matches = match_objects(&base.objects, &ours.objects);
+    let theirs_matches = match_objects(&base.objects, &theirs.objects);
+    let mut objects = Vec::new();
+    for (i, object) in base.objects.iter().enumerate() {
+        let [mine, other] = [(&ours_matches, ours), (&theirs_matches, theirs)]
+            .map(|(matches, scene)| matches[i].map(|j| scene.objects[j].clone()));
+        let name = format!("objects[{}]", i);
+        objects.extend(merge_value(&name, &Some(object.clone()), &mine, &other, &mut conflicts));
+    }
+    let ours_added: Vec<_> = added(&ours_matches, &ours.objects).map(|(_, object)| object.clone()).collect();
+    let theirs_added: Vec<_> = added(&theirs_matches, &theirs.objects)
+        .map(|(_, object)| object.clone())
+        .filter(|object| !ours_added.contains(object))
+        .collect();
+    objects.extend(ours_added);
+    objects.extend(theirs_added);
+
+    // Las luces, una por una si ningún lado agregó ni quitó
+    let lights = if ours.lights.len() == base.lights.len() && theirs.lights.len() == base.lights.len() {
+        (0..base.lights.len())
+            .map(|i| merge_value(&format!("lights[{}]", i), &base.lights[i], &ours.lights[i], &theirs.lights[i], &mut conflicts))
+            .collect()
+    } else {
+        merge_value("lights", &base.lights, &ours.lights, &theirs.lights, &mut conflicts)
+    };
+
+    let merged = SceneDescription {
+        camera: merge_value("camera", &base.camera, &ours.camera, &theirs.camera, &mut conflicts),
+        materials: merge_map("material", &base.materials, &ours.materials, &theirs.materials, &mut conflicts),
+        lights,
+        objects,
+        background: merge_value("background", &base.background, &ours.background, &theirs.background, &mut conflicts),
+        include: merge_value("include", &base.include, &ours.include, &theirs.include, &mut conflicts),
+        max_depth: merge_value("max_depth", &base.max_depth, &ours.max_depth, &theirs.max_depth, &mut conflicts),
+        prototypes: merge_map("prototype", &base.prototypes, &ours.prototypes, &theirs.prototypes, &mut conflicts),
+        units: merge_value("units", &base.units, &ours.units, &theirs.units, &mut conflicts),
+        portals: merge_value("portals", &base.portals, &ours.portals, &theirs.portals, &mut conflicts),
+        groups: merge_value("groups", &base.groups, &ours.groups, &theirs.groups, &mut conflicts),
+    };
+    if conflicts.is_empty() { Ok(merged) } else { Err(conflicts) }
+}
@@ -0,0 +1,192 @@
+// scene_error.rs
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+// Lo que puede salir mal al cargar una escena, siempre con el archivo donde pasó
+#[derive(Debug)]
+pub enum SceneError {
+    // No se pudo leer el archivo
+    Io { path: PathBuf, error: io::Error },
+    // RON mal formado, o un campo o una variante que no existe
+    Syntax { path: PathBuf, line: usize, column: usize, message: String },
+    // Bien formado pero imposible de armar: un material que no existe, una escala negativa...
+    // `line` es la del elemento que falló (el objeto, la luz, la inclusión), si se sabe
+    Invalid { path: PathBuf, line: Option<usize>, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            SceneError::Syntax { path, line, column, message } => {
+                write!(f, "{}:{}:{}: {}", path.display(), line, column, message)
+            }
+            SceneError::Invalid { path, line: Some(line), message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            SceneError::Invalid { path, line: None, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Comma,
+    Colon,
+    // Nombres, números y textos entre comillas
+    Word(String),
+}
+
+// Los tokens del texto RON con la línea de cada uno, sin comentarios
+fn tokenize(source: &str) -> Vec<(usize, Token)> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '(' | '[' | '{' => tokens.push((line, Token::Open)),
+            ')' | ']' | '}' => tokens.push((line, Token::Close)),
+            ',' => tokens.push((line, Token::Comma)),
+            ':' => tokens.push((line, Token::Colon)),
+            '"' => {
+                let start = line;
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => text.extend(chars.next()),
+                        '\n' => {
+                            line += 1;
+                            text.push(c);
+                        }
+                        _ => text.push(c),
+                    }
+                }
+                tokens.push((start, Token::Word(text)));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')) {
+                    word.push(c);
+                }
+                tokens.push((line, Token::Word(word)));
+            }
+        }
+    }
+    tokens
+}
+
+// Línea donde empieza el campo `key` de la escena o, con `index`, su elemento número
+// `index`. La descripción ya leída no guarda posiciones, así que se vuelve a recorrer el
+// texto contando paréntesis; None si el texto no tiene esa forma
+pub fn locate(source: &str, key: &str, index: Option<usize>) -> Option<usize> {
+    let tokens = tokenize(source);
+    let mut depth = 0;
+    let mut at = None;
+    for (position, (_, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            Token::Word(word) if depth == 1 && word == key && tokens.get(position + 1).map(|(_, token)| token) == Some(&Token::Colon) => {
+                at = Some(position);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let at = at?;
+    let Some(index) = index else { return Some(tokens[at].0) };
+
+    // Los elementos de la lista que sigue a `key:` son lo que hay entre comas a su profundidad
+    let list = tokens.iter().skip(at + 2);
+    let mut depth = 0;
+    let mut element = 0;
+    let mut starts = true;
+    for (line, token) in list {
+        match token {
+            Token::Open if depth == 0 => {
+                depth = 1;
+                continue;
+            }
+            Token::Close if depth == 1 => return None,
+            Token::Comma if depth == 1 => {
+                element += 1;
+                starts = true;
+                continue;
+            }
+            _ if depth == 0 => return None,
+            _ => {}
+        }
+        if starts && element == index {
+            return Some(*line);
+        }
+        starts = false;
+        match token {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = r#"(
+        // Un comentario con "comillas" y (paréntesis)
+        materials: { "gris": () },
+        objects: [
+            Cube(center: (0, 0, 0), size: 1, material: "gris"),
+            /* otro
+               comentario */ Sphere(center: (2, 0, 0),
+                radius: 0.5, material: "gris"),
+            Cube(center: (0, 0, 0), size: 1, material: "objects: [x]"),
+        ],
+        units: 0.5,
+    )"#;
+
+    #[test]
+    fn locates_fields_and_elements() {
+        assert_eq!(locate(SCENE, "materials", None), Some(3));
+        assert_eq!(locate(SCENE, "objects", Some(0)), Some(5));
+        assert_eq!(locate(SCENE, "objects", Some(1)), Some(7));
+        assert_eq!(locate(SCENE, "objects", Some(2)), Some(9));
+        assert_eq!(locate(SCENE, "objects", Some(3)), None);
+        assert_eq!(locate(SCENE, "units", None), Some(11));
+        // Solo los campos de la escena, no los de adentro de un objeto
+        assert_eq!(locate(SCENE, "center", None), None);
+        assert_eq!(locate(SCENE, "lights", Some(0)), None);
+    }
+}
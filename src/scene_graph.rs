@@ -0,0 +1,87 @@
+// scene_graph.rs
+
+use nalgebra_glm::{Mat3, Vec3};
+
+use crate::object::Object;
+use crate::transformed::{self, Transformed};
+
+// Nodo con su propia transformación, relativa a la del padre: escala por eje, giro en grados
+// alrededor de X, Y y Z (en ese orden) y desplazamiento
+pub struct Node {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub translation: Vec3,
+    pub rotation: [f32; 3],
+    pub scale: Vec3,
+    // Objetos hijos directos (por índice en la escena) con su forma en el espacio del nodo
+    pub members: Vec<(usize, Object)>,
+}
+
+impl Node {
+    pub fn new(name: &str, parent: Option<usize>) -> Self {
+        Node {
+            name: name.to_string(),
+            parent,
+            translation: Vec3::zeros(),
+            rotation: [0.0; 3],
+            scale: Vec3::new(1.0, 1.0, 1.0),
+            members: Vec::new(),
+        }
+    }
+
+    fn linear(&self) -> Mat3 {
+        transformed::linear(self.rotation, self.scale)
+    }
+}
+
+// Jerarquía de nodos sobre los objetos de la escena, por ejemplo un muñeco de nieve hecho de
+// cubos apilados: girar el nodo gira todos sus hijos. Los objetos de la escena son la versión
+// aplanada, ya en el mundo; `flatten` la rehace cuando cambia un nodo
+#[derive(Default)]
+pub struct SceneGraph {
+    pub nodes: Vec<Node>,
+}
+
+impl SceneGraph {
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    // Agrega los nodos de otra escena; los que no tenían padre quedan bajo `parent`
+    pub fn extend(&mut self, other: SceneGraph, parent: Option<usize>) {
+        let shift = self.nodes.len();
+        self.nodes.extend(other.nodes.into_iter().map(|mut node| {
+            node.parent = node.parent.map(|index| index + shift).or(parent);
+            node
+        }));
+    }
+
+    // Desplazamiento y giro por escala que llevan del espacio del nodo al mundo
+    fn world(&self, index: usize) -> (Vec3, Mat3) {
+        let node = &self.nodes[index];
+        let (translation, linear) = (node.translation, node.linear());
+        match node.parent {
+            Some(parent) => {
+                let (parent_translation, parent_linear) = self.world(parent);
+                (parent_translation + parent_linear * translation, parent_linear * linear)
+            }
+            None => (translation, linear),
+        }
+    }
+
+    // Escribe en `objects` cada hijo llevado al mundo. `Transformed` gira alrededor del centro
+    // del objeto; se corrige el desplazamiento para que gire alrededor del origen del nodo
+    pub fn flatten(&self, objects: &mut [Object]) {
+        for (index, node) in self.nodes.iter().enumerate() {
+            let (translation, linear) = self.world(index);
+            for (member, local) in &node.members {
+                let Some(object) = objects.get_mut(*member) else { continue };
+                let center = local.center();
+                let offset = translation + linear * center - center;
+                if let Some(world) = Transformed::new(local.box_clone(), offset, linear) {
+                    *object = Box::new(world);
+                }
+            }
+        }
+    }
+}
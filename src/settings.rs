@@ -0,0 +1,116 @@
+// settings.rs
+
+use std::sync::Arc;
+
+use crate::background::Background;
+use crate::color_management::OutputTransform;
+use crate::dither::Dither;
+use crate::flare::LensFlare;
+use crate::fog::Fog;
+use crate::guides::Guides;
+use crate::kernels::Kernel;
+use crate::portal::Portal;
+use crate::sky::PreethamSky;
+use crate::tonemap::ToneMap;
+
+// Tope de `max_depth`: cada nivel agrega otro rayo por espejo o cristal a la vista y a
+// partir de ahí los reflejos ya no cambian la imagen
+pub const MAX_RAY_DEPTH: u32 = 32;
+
+// Superficies que un rayo puede atravesar (translúcidas, cristales, caras interiores) sin
+// que cuente como rebote; pasado esto se ve el fondo
+pub const MAX_PASS_THROUGH: u32 = 64;
+
+// Cómo se calcula el color de cada rayo de cámara (ver `integrator::create`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntegratorKind {
+    // Luz directa, sombras, ambiente fijo y reflejos de espejo
+    Whitted,
+    // Trazado de caminos con luz indirecta, acumulado entre cuadros
+    PathTracing,
+    // Solo oclusión ambiental, acumulada entre cuadros
+    AmbientOcclusion,
+    // Normales como colores, para depurar
+    Normals,
+    // Rebotes de cada pixel como colores, para depurar
+    Depth,
+}
+
+// Parámetros del render que se pueden ajustar sin recompilar
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    // t_min base de los rayos secundarios; se escala con la distancia
+    // del impacto y lo rasante del rayo (ver `secondary_ray`)
+    pub shadow_bias: f32,
+    // Cielo físico; si no hay, los rayos que no golpean nada ven `background`
+    pub sky: Option<PreethamSky>,
+    pub background: Background,
+    // Aberturas por donde el fondo ilumina un interior (ver `portal`); las carga la escena
+    pub portals: Arc<[Portal]>,
+    // Niebla homogénea que atenúa con la distancia; None = aire limpio
+    pub fog: Option<Fog>,
+    // Destello sobre las luces visibles, compuesto después del trazado
+    pub lens_flare: Option<LensFlare>,
+    // Cuadrícula y ejes dibujados sobre el cuadro
+    pub guides: Option<Guides>,
+    // Candidatos por pixel del muestreo de luces por reservorios; None = evaluar todas las luces
+    pub light_samples: Option<usize>,
+    // Rayos primarios por pixel (antialiasing); 1 = un rayo por pixel, como siempre
+    pub samples: u32,
+    // Rebotes máximos de los rayos reflejados, hasta MAX_RAY_DEPTH; los materiales con
+    // `max_depth` usan el suyo
+    pub max_depth: u32,
+    // Tramado al convertir a 8 bits; None = truncar
+    pub dither: Option<Dither>,
+    // Curva HDR y codificación sRGB de los colores lineales; None = recortar en 255 como siempre
+    pub tone_map: Option<ToneMap>,
+    // Espacio de color en que se codifican los pixeles de salida
+    pub output_transform: OutputTransform,
+    pub integrator: IntegratorKind,
+    // Rayos de sombra por punto hacia cada luz de área
+    pub shadow_samples: u32,
+    // Antialiasing temporal: desplaza los rayos primarios en cada cuadro y promedia con los
+    // anteriores reproyectados (solo en el integrador Whitted sin reservorios)
+    pub taa: bool,
+    // Rayos de oclusión ambiental por punto, que oscurecen el ambiente en rincones y
+    // contactos; 0 = ambiente parejo
+    pub ao_samples: u32,
+    // Reflejos de cristal y oclusión ambiental a media resolución, reescalados guiados por
+    // la distancia y la normal (ver `half_res`); solo en el integrador Whitted sin reservorios
+    pub half_res_effects: bool,
+    // Guardar sombras y oclusión por texel entre cuadros mientras la escena no cambie (ver
+    // `shading_cache`); lo usa el sombreado de Whitted
+    pub shading_cache: bool,
+    // Prueba de cajas del BVH; la mejor del procesador salvo --kernel
+    pub kernel: Kernel,
+    // Depuración: pinta de magenta los pixeles con NaN, infinitos o negativos e informa el primero
+    pub watchdog: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            shadow_bias: 1e-4,
+            sky: None,
+            background: Background::default(),
+            portals: Arc::new([]),
+            fog: None,
+            lens_flare: None,
+            guides: None,
+            light_samples: None,
+            samples: 1,
+            max_depth: 1,
+            dither: None,
+            tone_map: None,
+            output_transform: OutputTransform::Srgb,
+            integrator: IntegratorKind::Whitted,
+            shadow_samples: 16,
+            taa: false,
+            ao_samples: 0,
+            half_res_effects: false,
+            shading_cache: false,
+            kernel: Kernel::detect(),
+            watchdog: false,
+        }
+    }
+}
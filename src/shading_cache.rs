@@ -0,0 +1,155 @@
+// shading_cache.rs
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use crate::buffer_pool;
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::emitter;
+use crate::light_grid::LightGrid;
+use crate::ray_intersect::Intersect;
+use crate::settings::RenderSettings;
+use crate::{ambient_occlusion, light_filter, shadow_only_factor};
+
+// Texeles por unidad de UV (por lado de cubo, por baldosa del plano)
+const TEXELS_PER_UV: f32 = 64.0;
+// Mapas separados para que los hilos no se esperen entre sí
+const SHARDS: usize = 64;
+// Texeles por mapa antes de vaciarlo, para no crecer sin límite recorriendo una escena grande
+const MAX_TEXELS_PER_SHARD: usize = 1 << 15;
+
+// Objeto, texel en el mundo y normal redondeada (las dos caras de una arista caen en el
+// mismo texel)
+type Key = (usize, [i32; 3], [i8; 3]);
+
+// Lo que no depende de la vista en un punto: sombras, oclusión y luz de los emisivos.
+// Con esto el sombreado de un texel ya visto no traza ningún rayo de sombra
+pub struct Visibility {
+    // Lo que llega de cada luz cercana (ver `light_filter`), por índice en la escena
+    lit: Vec<(usize, Color)>,
+    // Oclusión ambiental; 1 sin --ao-samples
+    pub occlusion: f32,
+    pub shadow_only: f32,
+    // Luz directa de los objetos emisivos, sin teñir
+    pub emitted: Color,
+}
+
+impl Visibility {
+    fn compute(intersect: &Intersect, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings, occlusion: bool) -> Self {
+        let mut lit = buffer_pool::take_lights();
+        lit.extend(
+            lights
+                .near_indices(&intersect.point)
+                .filter(|&index| !lights.lights[index].shadow_only)
+                .map(|index| (index, light_filter(intersect, &lights.lights[index], scene, settings))),
+        );
+        Visibility {
+            lit,
+            occlusion: if occlusion && settings.ao_samples > 0 { ambient_occlusion(intersect, scene, settings) } else { 1.0 },
+            shadow_only: shadow_only_factor(intersect, lights.lights, scene, settings),
+            emitted: emitter::direct_light(intersect, &lights.emitters, scene, settings),
+        }
+    }
+
+    // Luces cercanas con el tinte de su sombra
+    pub fn lit(&self) -> &[(usize, Color)] {
+        &self.lit
+    }
+}
+
+// La lista de luces vuelve a los búferes del hilo para el punto siguiente
+impl Drop for Visibility {
+    fn drop(&mut self) {
+        buffer_pool::return_lights(std::mem::take(&mut self.lit));
+    }
+}
+
+// La visibilidad del caché, compartida, o una recién calculada para un solo punto, que no
+// necesita reservar memoria para compartirse
+pub enum PointVisibility {
+    Cached(Arc<Visibility>),
+    Computed(Visibility),
+}
+
+impl Deref for PointVisibility {
+    type Target = Visibility;
+
+    fn deref(&self) -> &Visibility {
+        match self {
+            PointVisibility::Cached(visibility) => visibility,
+            PointVisibility::Computed(visibility) => visibility,
+        }
+    }
+}
+
+// Visibilidad por texel de la superficie, guardada entre cuadros mientras no cambien la
+// escena ni las luces: al mover la cámara solo se recalcula el sombreado (difuso,
+// especular, reflejos), no las sombras ni la oclusión
+pub struct ShadingCache {
+    shards: Vec<Mutex<HashMap<Key, Arc<Visibility>>>>,
+    signature: u64,
+}
+
+impl Default for ShadingCache {
+    fn default() -> Self {
+        ShadingCache { shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(), signature: 0 }
+    }
+}
+
+fn key(intersect: &Intersect) -> Option<Key> {
+    let object = intersect.object?;
+    if intersect.uv_scale <= 0.0 {
+        return None;
+    }
+    let texel = intersect.point * (TEXELS_PER_UV / intersect.uv_scale);
+    let normal = intersect.normal * 2.0;
+    Some((
+        object,
+        [texel.x.floor() as i32, texel.y.floor() as i32, texel.z.floor() as i32],
+        [normal.x.round() as i8, normal.y.round() as i8, normal.z.round() as i8],
+    ))
+}
+
+impl ShadingCache {
+    // Vacía lo guardado si cambió la escena (ver `integrator::scene_signature`)
+    pub fn prepare(&mut self, signature: u64) {
+        if self.signature != signature {
+            self.shards.iter_mut().for_each(|shard| shard.get_mut().unwrap().clear());
+            self.signature = signature;
+        }
+    }
+
+    fn visibility(&self, intersect: &Intersect, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings) -> PointVisibility {
+        let Some(key) = key(intersect) else {
+            return PointVisibility::Computed(Visibility::compute(intersect, scene, lights, settings, true));
+        };
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let shard = &self.shards[hasher.finish() as usize % SHARDS];
+        if let Some(visibility) = shard.lock().unwrap().get(&key) {
+            return PointVisibility::Cached(visibility.clone());
+        }
+        // Se calcula sin el candado; si otro hilo llegó antes, queda cualquiera de los dos
+        let visibility = Arc::new(Visibility::compute(intersect, scene, lights, settings, true));
+        let mut shard = shard.lock().unwrap();
+        if shard.len() >= MAX_TEXELS_PER_SHARD {
+            shard.clear();
+        }
+        shard.insert(key, visibility.clone());
+        PointVisibility::Cached(visibility)
+    }
+}
+
+// Visibilidad en el punto, del caché si está activo. `occlusion` = false evita la oclusión
+// cuando no se va a usar (la calcula la media resolución); con caché se calcula igual, porque
+// el mismo texel puede servir a otro rayo que sí la use
+pub fn visibility(intersect: &Intersect, scene: &Bvh, lights: &LightGrid, settings: &RenderSettings, occlusion: bool) -> PointVisibility {
+    match lights.shading_cache {
+        Some(cache) => cache.visibility(intersect, scene, lights, settings),
+        None => PointVisibility::Computed(Visibility::compute(intersect, scene, lights, settings, occlusion)),
+    }
+}
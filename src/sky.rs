@@ -0,0 +1,248 @@
+// sky.rs
+
+use std::f32::consts::PI;
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::light::Light;
+
+// Distancia a la que se coloca la luz del sol (suficiente para que sus rayos sean casi paralelos)
+const SUN_DISTANCE: f32 = 1000.0;
+// Escala de la luminancia relativa (1 = cenit) a la escala 0-255 de los colores
+const SKY_BRIGHTNESS: f32 = 190.0;
+// Tamaño de la cuadrícula de estrellas sobre la esfera y fracción de celdas con estrella
+const STAR_GRID: f32 = 150.0;
+const STAR_DENSITY: f32 = 0.015;
+// Radio angular de la luna en radianes (exagerado respecto al real para que se vea)
+const MOON_RADIUS: f32 = 0.05;
+// Cielo nocturno base, azul muy oscuro
+const NIGHT_COLOR: Color = Color { r: 4.0, g: 6.0, b: 16.0 };
+// Cráteres de la luna: centro en coordenadas del disco (-1..1) y radio
+const MOON_CRATERS: [(f32, f32, f32); 6] = [
+    (-0.35, 0.30, 0.28),
+    (0.25, 0.45, 0.18),
+    (0.40, -0.20, 0.30),
+    (-0.20, -0.45, 0.20),
+    (0.05, 0.05, 0.12),
+    (-0.60, -0.05, 0.14),
+];
+
+// Modelo analítico de cielo de Preetham et al. (1999): el color depende de la
+// posición del sol y de la turbidez de la atmósfera (2 = muy limpio, 10 = brumoso)
+#[derive(Debug, Clone, Copy)]
+pub struct PreethamSky {
+    // Elevación y azimut del sol en grados
+    pub elevation: f32,
+    pub azimuth: f32,
+    pub turbidity: f32,
+    pub sun_direction: Vec3,
+    // Segundos transcurridos, para el parpadeo de las estrellas
+    pub time: f32,
+    // Coeficientes A..E de la función de Perez para Y, x, y
+    perez: [[f32; 5]; 3],
+    // Valores del cenit (Y relativa, x, y) divididos por la función de Perez en el cenit
+    zenith_over_perez: [f32; 3],
+    // Qué tan de día es: 1 con el sol alto, 0 con el sol en el horizonte o debajo
+    daylight: f32,
+}
+
+// Hash entero de una celda a un valor en [0, 1)
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f)
+        ^ seed.wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    (h & 0x00ff_ffff) as f32 / 0x0100_0000 as f32
+}
+
+fn perez(coefficients: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+impl PreethamSky {
+    // Elevación y azimut del sol en grados
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+        let elevation_rad = elevation.to_radians();
+        let azimuth_rad = azimuth.to_radians();
+        let sun_direction = Vec3::new(
+            elevation_rad.cos() * azimuth_rad.cos(),
+            elevation_rad.sin(),
+            elevation_rad.cos() * azimuth_rad.sin(),
+        );
+
+        let t = turbidity;
+        let coefficients = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        // El modelo solo es válido con el sol sobre el horizonte
+        let theta_s = (PI / 2.0 - elevation_rad).clamp(0.0, PI / 2.0 - 0.01);
+        let (t2, s, s2, s3) = (t * t, theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t2 * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let zenith_y = t2 * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+
+        // La luminancia se normaliza respecto al cenit (Y = 1 mirando hacia arriba);
+        // el brillo absoluto lo controla `daylight`
+        let zenith_over_perez = [
+            1.0 / perez(&coefficients[0], 1.0, theta_s),
+            zenith_x / perez(&coefficients[1], 1.0, theta_s),
+            zenith_y / perez(&coefficients[2], 1.0, theta_s),
+        ];
+
+        PreethamSky {
+            elevation,
+            azimuth,
+            turbidity,
+            sun_direction,
+            time: 0.0,
+            perez: coefficients,
+            zenith_over_perez,
+            daylight: (elevation_rad.sin() * 4.0).clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn color(&self, direction: &Vec3) -> Color {
+        let direction = direction.normalize();
+        // Debajo del horizonte se repite el color del horizonte
+        let cos_theta = direction.y.max(0.01);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let luminance = self.zenith_over_perez[0] * perez(&self.perez[0], cos_theta, gamma);
+        // Comprime el brillo alrededor del sol (hasta ~20 veces el del cenit) para que
+        // no sature todo el cielo; el cenit sigue valiendo 1
+        let luminance = luminance * 1.5 / (1.0 + 0.5 * luminance);
+        let x = self.zenith_over_perez[1] * perez(&self.perez[1], cos_theta, gamma);
+        let y = self.zenith_over_perez[2] * perez(&self.perez[2], cos_theta, gamma);
+
+        // Yxy -> XYZ -> sRGB lineal
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        let r = 3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z;
+        let g = -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z;
+        let b = 0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z;
+
+        let scale = SKY_BRIGHTNESS * self.daylight;
+        let day = Color::new(r.max(0.0) * scale, g.max(0.0) * scale, b.max(0.0) * scale);
+
+        // El cielo nocturno aparece a medida que el sol se pone
+        let night = 1.0 - self.daylight;
+        if night > 0.0 {
+            day + self.night_color(&direction) * night
+        } else {
+            day
+        }
+    }
+
+    // La luna está siempre opuesta al sol (luna llena)
+    pub fn moon_direction(&self) -> Vec3 {
+        -self.sun_direction
+    }
+
+    fn night_color(&self, direction: &Vec3) -> Color {
+        if direction.y < 0.0 {
+            return NIGHT_COLOR;
+        }
+
+        let moon_direction = self.moon_direction();
+        let moon_angle = direction.dot(&moon_direction).clamp(-1.0, 1.0).acos();
+        if moon_angle < MOON_RADIUS {
+            return self.moon_color(direction, &moon_direction);
+        }
+
+        NIGHT_COLOR + self.star_color(direction)
+    }
+
+    fn star_color(&self, direction: &Vec3) -> Color {
+        let grid = direction * STAR_GRID;
+        let cell = grid.map(|v| v.floor());
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        if hash(x, y, z, 0) > STAR_DENSITY {
+            return Color::black();
+        }
+
+        // Posición de la estrella dentro de la celda
+        let star = cell + Vec3::new(hash(x, y, z, 1), hash(x, y, z, 2), hash(x, y, z, 3));
+        let distance = (grid - star).norm();
+        if distance > 0.35 {
+            return Color::black();
+        }
+
+        let brightness = 0.3 + 0.7 * hash(x, y, z, 4).powi(3);
+        let phase = hash(x, y, z, 5) * 2.0 * PI;
+        let twinkle = 0.7 + 0.3 * (self.time * (2.0 + 3.0 * hash(x, y, z, 6)) + phase).sin();
+        let falloff = 1.0 - distance / 0.35;
+        let value = 255.0 * brightness * twinkle * falloff;
+        Color::new(value * 0.9, value * 0.95, value)
+    }
+
+    fn moon_color(&self, direction: &Vec3, moon_direction: &Vec3) -> Color {
+        // Coordenadas sobre el disco de la luna, de -1 a 1
+        let right = moon_direction.cross(&Vec3::new(0.0, 1.0, 0.0));
+        let right = if right.norm() < 1e-4 { Vec3::new(1.0, 0.0, 0.0) } else { right.normalize() };
+        let up = right.cross(moon_direction);
+        let offset = direction - moon_direction;
+        let u = offset.dot(&right) / MOON_RADIUS;
+        let v = offset.dot(&up) / MOON_RADIUS;
+
+        let mut shade: f32 = 0.9;
+        for (cx, cy, radius) in MOON_CRATERS {
+            let d = ((u - cx).powi(2) + (v - cy).powi(2)).sqrt();
+            if d < radius {
+                // Fondo del cráter más oscuro con el borde un poco más claro
+                shade = shade.min(0.6 + 0.3 * (d / radius).powi(4));
+            }
+        }
+        // Oscurecimiento hacia el borde del disco
+        let limb = (1.0 - (u * u + v * v)).max(0.0).sqrt();
+        let value = 235.0 * shade * (0.6 + 0.4 * limb);
+        Color::new(value, value, value * 1.05)
+    }
+
+    // El mismo cielo con el sol desplazado `degrees` a lo largo de su arco diario
+    // y el reloj de las estrellas avanzado `seconds`
+    pub fn advanced(&self, degrees: f32, seconds: f32) -> Self {
+        let elevation = (self.elevation + degrees + 180.0).rem_euclid(360.0) - 180.0;
+        PreethamSky {
+            time: self.time + seconds,
+            ..PreethamSky::new(elevation, self.azimuth, self.turbidity)
+        }
+    }
+
+    // El mismo cielo con el sol girado esos grados; la elevación se queda entre -90° y 90°
+    pub fn turned(&self, elevation: f32, azimuth: f32) -> Self {
+        PreethamSky {
+            time: self.time,
+            ..PreethamSky::new(
+                (self.elevation + elevation).clamp(-90.0, 90.0),
+                (self.azimuth + azimuth).rem_euclid(360.0),
+                self.turbidity,
+            )
+        }
+    }
+
+    // Luz del sol acoplada al cielo: más cálida y tenue cerca del horizonte
+    pub fn sun_light(&self) -> Light {
+        let warmth = 1.0 - self.sun_direction.y.clamp(0.0, 1.0);
+        let color = Color::from_srgb(255.0, 245.0 - 80.0 * warmth, 230.0 - 150.0 * warmth);
+        Light::new(self.sun_direction * SUN_DISTANCE, color, self.daylight)
+    }
+
+    // Luz tenue y azulada de la luna, solo de noche y con la luna sobre el horizonte
+    pub fn moon_light(&self) -> Light {
+        let night = 1.0 - self.daylight;
+        let height = self.moon_direction().y.clamp(0.0, 1.0).sqrt();
+        let color = Color::from_srgb(170.0, 190.0, 255.0);
+        Light::new(self.moon_direction() * SUN_DISTANCE, color, 0.15 * night * height)
+    }
+}
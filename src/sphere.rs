@@ -0,0 +1,97 @@
+// sphere.rs
+
+use std::f32::consts::PI;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use nalgebra_glm::Vec3;
+
+use crate::material::Material;
+use crate::object::{Object, SceneObject};
+use crate::obj_export::{self, ObjTransform};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+#[derive(Debug, Clone)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+    pub material: Arc<Material>,
+}
+
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        let oc = ray.origin - self.center;
+        let a = ray.direction.dot(&ray.direction);
+        let half_b = oc.dot(&ray.direction);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return Intersect::empty();
+        }
+
+        // Entrada si está dentro del intervalo; si no, la salida (rayo que empieza dentro)
+        let root = discriminant.sqrt();
+        let t_near = (-half_b - root) / a;
+        let t_far = (-half_b + root) / a;
+        let inside = !ray.contains(t_near);
+        let t = if inside { t_far } else { t_near };
+        if !ray.contains(t) {
+            return Intersect::empty();
+        }
+
+        let point = ray.at(t);
+        let normal = (point - self.center) / self.radius;
+
+        // UV de latitud y longitud; u crece alrededor de Y desde +X hacia +Z y v hacia arriba
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+        let v = 0.5 + normal.y.clamp(-1.0, 1.0).asin() / PI;
+        let around = Vec3::new(-normal.z, 0.0, normal.x);
+        let tangent = if around.norm() > 1e-6 { around.normalize() } else { Vec3::new(1.0, 0.0, 0.0) };
+        let bitangent = tangent.cross(&normal);
+
+        let intersect = Intersect::new(point, normal, t, self.material.clone(), Some((u, v)))
+            .with_tangents(tangent, bitangent)
+            .with_uv_scale(PI * self.radius);
+        if inside { intersect.seen_from_inside() } else { intersect }
+    }
+}
+
+impl SceneObject for Sphere {
+    fn kind(&self) -> &'static str {
+        "sphere"
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.center = center;
+    }
+
+    fn size(&self) -> f32 {
+        self.radius * 2.0
+    }
+
+    fn set_size(&mut self, size: f32) {
+        self.radius = size / 2.0;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    // Si otro objeto comparte el material, este pasa a tener su propia copia
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.material)
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(self.clone())
+    }
+
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        obj_export::write_sphere(obj, self, vertex_offset, transform)
+    }
+}
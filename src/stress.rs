@@ -0,0 +1,99 @@
+// stress.rs
+
+use std::time::Instant;
+
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::material::Material;
+use crate::object::Object;
+use crate::render;
+use crate::settings::RenderSettings;
+use crate::sphere::Sphere;
+
+// Escena aleatoria reproducible: `count` cubos y esferas dentro de un volumen que crece con la raíz cúbica
+pub fn generate_scene(count: usize, light_count: Option<usize>, attenuation: f32, seed: u64) -> (Vec<Object>, Vec<Light>, Camera) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let extent = (count as f32).cbrt() * 1.5;
+
+    let objects = (0..count)
+        .map(|_| {
+            let center = Vec3::new(
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+            );
+            let diffuse = Color::from_srgb(
+                rng.gen_range(0.0..255.0),
+                rng.gen_range(0.0..255.0),
+                rng.gen_range(0.0..255.0),
+            );
+            let specular = rng.gen_range(5.0..200.0);
+            let albedo = [rng.gen_range(0.4..0.9), rng.gen_range(0.0..0.6)];
+            let material = if rng.gen_bool(0.1) {
+                Material::crystal(diffuse, specular, albedo)
+            } else {
+                Material::new(diffuse, specular, albedo)
+            };
+            let size = rng.gen_range(0.2..1.0);
+            if rng.gen_bool(0.3) {
+                Box::new(Sphere { center, radius: size / 2.0, material: material.into() }) as Object
+            } else {
+                Box::new(Cube { center, size, material: material.into() })
+            }
+        })
+        .collect();
+
+    // Sin cantidad pedida, de 2 a 4 luces; con muchas, la intensidad se reparte entre todas.
+    // Con atenuación cada luz alcanza poco, así que van entre los objetos y sin repartir
+    let light_count = light_count.unwrap_or_else(|| rng.gen_range(2..=4));
+    let attenuated = attenuation > 0.0;
+    let intensity_scale = if attenuated { 1.0 } else { (3.0 / light_count as f32).min(1.0) };
+    let lights = (0..light_count)
+        .map(|_| {
+            let height = if attenuated { rng.gen_range(-extent..extent) } else { extent * 1.5 };
+            let position = Vec3::new(rng.gen_range(-extent..extent), height, rng.gen_range(-extent..extent));
+            let mut light = Light::new(position, Color::from_srgb(255.0, 255.0, 255.0), rng.gen_range(0.4..1.0) * intensity_scale);
+            light.attenuation = attenuation;
+            light
+        })
+        .collect();
+
+    let camera = Camera::new(
+        Vec3::new(0.0, extent * 0.5, extent * 3.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    );
+
+    (objects, lights, camera)
+}
+
+// Genera y renderiza la escena sin ventana, reportando los tiempos
+pub fn run(count: usize, light_count: Option<usize>, attenuation: f32, seed: u64, width: usize, height: usize, settings: &RenderSettings) -> Framebuffer {
+    let start = Instant::now();
+    let (objects, lights, camera) = generate_scene(count, light_count, attenuation, seed);
+    let generation = start.elapsed();
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    let start = Instant::now();
+    render(&mut framebuffer, &objects, &camera, &lights, settings);
+    let rendering = start.elapsed();
+
+    let primary_rays = (width * height) as f64;
+    println!("objetos:     {}", count);
+    println!("semilla:     {}", seed);
+    println!("luces:       {}", lights.len());
+    println!("resolución:  {}x{}", width, height);
+    println!("kernel:      {}", settings.kernel);
+    println!("generación:  {:.1} ms", generation.as_secs_f64() * 1000.0);
+    println!("render:      {:.1} ms", rendering.as_secs_f64() * 1000.0);
+    println!("rayos/s:     {:.0} (primarios)", primary_rays / rendering.as_secs_f64());
+
+    framebuffer
+}
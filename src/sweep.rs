@@ -0,0 +1,146 @@
+// sweep.rs
+
+use std::error::Error;
+use std::path::Path;
+
+use image::{imageops, RgbImage};
+
+use crate::camera::Camera;
+use crate::color_management::OutputTransform;
+use crate::export::{self, OutputFormat, framebuffer_to_image};
+use crate::font;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::object::Object;
+use crate::render;
+use crate::scene;
+use crate::scene_graph::SceneGraph;
+use crate::settings::RenderSettings;
+use crate::sky::PreethamSky;
+
+// Un parámetro que va de `from` a `to` en `steps` cuadros, por ejemplo `material.0.specular=0:100`
+pub struct Sweep {
+    pub path: String,
+    pub from: f32,
+    pub to: f32,
+    pub steps: usize,
+}
+
+impl Sweep {
+    pub fn parse(text: &str, steps: usize) -> Result<Self, String> {
+        let (path, range) = text
+            .split_once('=')
+            .ok_or_else(|| format!("se esperaba ruta=desde:hasta: {}", text))?;
+        let (from, to) = range
+            .split_once(':')
+            .ok_or_else(|| format!("se esperaba desde:hasta: {}", range))?;
+        let parse = |value: &str| value.trim().parse::<f32>().map_err(|_| format!("número inválido: {}", value));
+        Ok(Sweep { path: path.trim().to_string(), from: parse(from)?, to: parse(to)?, steps: steps.max(1) })
+    }
+
+    // Valor del cuadro `index`; con un solo cuadro se usa `from`
+    pub fn value(&self, index: usize) -> f32 {
+        if self.steps == 1 {
+            return self.from;
+        }
+        self.from + (self.to - self.from) * index as f32 / (self.steps - 1) as f32
+    }
+}
+
+// Cambia un parámetro del cielo y las luces del sol y la luna que dependen de él
+// (son las dos últimas de la escena, ver `main`)
+fn set_sky(path: &str, value: f32, lights: &mut [Light], settings: &mut RenderSettings) -> Result<(), String> {
+    let Some(sky) = settings.sky else {
+        return Err("la escena no tiene cielo (falta --sun-elevation, --sun-azimuth o --turbidity)".to_string());
+    };
+    let sky = match path {
+        "sky.elevation" => PreethamSky::new(value, sky.azimuth, sky.turbidity),
+        "sky.azimuth" => PreethamSky::new(sky.elevation, value, sky.turbidity),
+        "sky.turbidity" => PreethamSky::new(sky.elevation, sky.azimuth, value),
+        _ => return Err(format!("propiedad de cielo desconocida: {}", path)),
+    };
+    let index = lights.len() - 2;
+    lights[index] = sky.sun_light();
+    lights[index + 1] = sky.moon_light();
+    settings.sky = Some(sky);
+    Ok(())
+}
+
+// Un cuadro por valor del parámetro, cada uno con el valor rotulado en la esquina
+pub fn render_sweep(
+    sweep: &Sweep,
+    objects: &mut Vec<Object>,
+    lights: &mut [Light],
+    camera: &mut Camera,
+    settings: &RenderSettings,
+    width: usize,
+    height: usize,
+) -> Result<Vec<Framebuffer>, String> {
+    let mut settings = settings.clone();
+    (0..sweep.steps)
+        .map(|index| {
+            let value = sweep.value(index);
+            if sweep.path.starts_with("sky.") {
+                set_sky(&sweep.path, value, lights, &mut settings)?;
+            } else {
+                // Los nodos se giran y mueven con tres valores, que un barrido no da
+                scene::apply_override(&format!("{}={}", sweep.path, value), objects, lights, &mut SceneGraph::default(), camera)?;
+            }
+
+            let mut framebuffer = Framebuffer::new(width, height);
+            render(&mut framebuffer, objects, camera, lights, &settings);
+            font::draw_label(&mut framebuffer, 4, 4, &format!("{} = {:.3}", sweep.path, value));
+            Ok(framebuffer)
+        })
+        .collect()
+}
+
+// Guarda los cuadros en `dir` (01_ruta_valor.png...) y una tira con todos lado a lado (tira.png)
+pub fn save_sweep(
+    sweep: &Sweep,
+    frames: &[Framebuffer],
+    dir: &Path,
+    settings: &RenderSettings,
+    color_space: Option<OutputTransform>,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let Some(first) = frames.first() else { return Ok(()) };
+    let mut strip = RgbImage::new((first.width * frames.len()) as u32, first.height as u32);
+    for (index, framebuffer) in frames.iter().enumerate() {
+        let path = dir.join(format!("{:02}_{}_{:.3}.png", index + 1, sweep.path, sweep.value(index)));
+        export::save_framebuffer(framebuffer, &path, OutputFormat::Png, settings, color_space)?;
+        imageops::replace(&mut strip, &framebuffer_to_image(framebuffer), (index * first.width) as i64, 0);
+    }
+    strip.save(dir.join("tira.png"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_path_and_range() {
+        let sweep = Sweep::parse(" material.0.specular = 0 : 100 ", 5).unwrap();
+        assert_eq!(sweep.path, "material.0.specular");
+        assert_eq!((sweep.from, sweep.to, sweep.steps), (0.0, 100.0, 5));
+        assert_eq!((0..5).map(|index| sweep.value(index)).collect::<Vec<_>>(), [0.0, 25.0, 50.0, 75.0, 100.0]);
+    }
+
+    #[test]
+    fn descending_and_single_step() {
+        let sweep = Sweep::parse("camera.fov=90:30", 3).unwrap();
+        assert_eq!((0..3).map(|index| sweep.value(index)).collect::<Vec<_>>(), [90.0, 60.0, 30.0]);
+        let sweep = Sweep::parse("camera.fov=90:30", 0).unwrap();
+        assert_eq!(sweep.steps, 1);
+        assert_eq!(sweep.value(0), 90.0);
+    }
+
+    #[test]
+    fn rejects_malformed_ranges() {
+        assert!(Sweep::parse("camera.fov", 3).is_err());
+        assert!(Sweep::parse("camera.fov=90", 3).is_err());
+        assert!(Sweep::parse("camera.fov=noventa:30", 3).is_err());
+        assert!(Sweep::parse("camera.fov=90:", 3).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// taa.rs
+
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::ray::Ray;
+use crate::watchdog;
+
+// Peso del cuadro nuevo en el promedio: más bajo suaviza más pero deja estelas
+const BLEND: f32 = 0.1;
+// Desplazamientos distintos antes de que la secuencia se repita
+const JITTER_PERIOD: u32 = 8;
+// Diferencia relativa de distancia a partir de la cual la historia es de otra superficie
+// (algo que recién se destapó) y se descarta
+const MAX_DEPTH_DIFFERENCE: f32 = 0.05;
+// Distancia a la que se ubica el cielo para reproyectarlo
+const SKY_DISTANCE: f32 = 1.0e4;
+
+// Cuadros ya resueltos, para el antialiasing temporal; vive en el framebuffer
+#[derive(Default)]
+pub struct TaaHistory {
+    color: Vec<Color>,
+    // Distancia de la cámara a lo que se veía en cada pixel
+    depth: Vec<f32>,
+    camera: Option<Camera>,
+    frame: u32,
+}
+
+// Secuencia de Halton: cubre el intervalo [0, 1) de forma pareja con pocos puntos
+fn halton(mut index: u32, base: u32) -> f32 {
+    let (mut result, mut fraction) = (0.0, 1.0);
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+impl TaaHistory {
+    // Desplazamiento de los rayos primarios dentro del pixel en este cuadro, en [-0.5, 0.5)
+    pub fn jitter(&self) -> (f32, f32) {
+        let index = self.frame % JITTER_PERIOD + 1;
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+}
+
+// Mezcla el cuadro recién trazado en `framebuffer.hdr` (con los rayos desplazados por
+// `jitter`) con la historia reproyectada a esta cámara. La historia se descarta donde la
+// distancia no coincide y se recorta al rango de colores de los vecinos actuales, que es
+// lo que evita las estelas cuando algo se mueve o cambia de color
+pub fn accumulate(framebuffer: &mut Framebuffer, scene: &Bvh, camera: &Camera) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let (w, h) = (width as f32, height as f32);
+    let (dx, dy) = framebuffer.taa.jitter();
+
+    // Lo que ve el rayo primario (desplazado igual que el del color) de cada pixel
+    let points: Vec<Vec3> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = ((index % width) as f32 + dx, (index / width) as f32 + dy);
+            let ray = Ray::new(camera.position, camera.primary_ray_direction(x, y, w, h));
+            let intersect = scene.intersect(&ray);
+            if intersect.is_intersecting { intersect.point } else { ray.at(SKY_DISTANCE) }
+        })
+        .collect();
+
+    let history = &framebuffer.taa;
+    let current = &framebuffer.hdr;
+    let previous = history.camera.as_ref().filter(|_| history.color.len() == width * height);
+    let resolved: Vec<Color> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let color = current[index];
+            // Un pixel inválido se deja tal cual para que el vigilante lo marque
+            if watchdog::is_invalid(&color) {
+                return color;
+            }
+            let Some(previous) = previous else { return color };
+            let Some((px, py)) = previous.project(&points[index], w, h) else { return color };
+            let (hx, hy) = (px.round(), py.round());
+            if hx < 0.0 || hy < 0.0 || hx >= w || hy >= h {
+                return color;
+            }
+            let history_index = hy as usize * width + hx as usize;
+            let expected = (points[index] - previous.position).norm();
+            if (history.depth[history_index] - expected).abs() > MAX_DEPTH_DIFFERENCE * expected {
+                return color;
+            }
+
+            let (x, y) = (index % width, index / width);
+            let (mut low, mut high) = (color, color);
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let neighbor = current[ny * width + nx];
+                    low = Color::new(low.r.min(neighbor.r), low.g.min(neighbor.g), low.b.min(neighbor.b));
+                    high = Color::new(high.r.max(neighbor.r), high.g.max(neighbor.g), high.b.max(neighbor.b));
+                }
+            }
+            let past = history.color[history_index];
+            let past = Color::new(past.r.clamp(low.r, high.r), past.g.clamp(low.g, high.g), past.b.clamp(low.b, high.b));
+            past.blend(color, BLEND)
+        })
+        .collect();
+
+    let history = &mut framebuffer.taa;
+    history.depth = points.iter().map(|point| (point - camera.position).norm()).collect();
+    history.color = resolved.clone();
+    history.camera = Some(camera.clone());
+    history.frame = history.frame.wrapping_add(1);
+    framebuffer.hdr = resolved;
+}
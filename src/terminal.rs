@@ -0,0 +1,51 @@
+// terminal.rs
+
+use std::io::{self, Write};
+
+use crate::framebuffer::Framebuffer;
+
+// Cada celda de la terminal muestra dos pixeles (medio bloque superior + fondo),
+// así que 80x48 pixeles llenan una terminal estándar de 80x24.
+pub const PREVIEW_WIDTH: usize = 80;
+pub const PREVIEW_HEIGHT: usize = 48;
+
+const UPPER_HALF_BLOCK: char = '▀';
+
+// Convierte un color 0xRRGGBB al cubo de 6x6x6 de la paleta de 256 colores
+fn ansi_256(color: u32) -> u8 {
+    let to_level = |channel: u32| ((channel as f32 / 255.0) * 5.0).round() as u8;
+    let r = to_level((color >> 16) & 0xFF);
+    let g = to_level((color >> 8) & 0xFF);
+    let b = to_level(color & 0xFF);
+    16 + 36 * r + 6 * g + b
+}
+
+pub fn framebuffer_to_ansi(framebuffer: &Framebuffer) -> String {
+    let mut output = String::new();
+
+    for y in (0..framebuffer.height).step_by(2) {
+        for x in 0..framebuffer.width {
+            let top = framebuffer.buffer[y * framebuffer.width + x];
+            let bottom = if y + 1 < framebuffer.height {
+                framebuffer.buffer[(y + 1) * framebuffer.width + x]
+            } else {
+                top
+            };
+            output.push_str(&format!(
+                "\x1b[38;5;{}m\x1b[48;5;{}m{}",
+                ansi_256(top),
+                ansi_256(bottom),
+                UPPER_HALF_BLOCK
+            ));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+
+    output
+}
+
+pub fn print_framebuffer(framebuffer: &Framebuffer) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(framebuffer_to_ansi(framebuffer).as_bytes())?;
+    stdout.flush()
+}
@@ -0,0 +1,152 @@
+// texture.rs
+
+use std::fmt;
+use std::sync::Arc;
+
+use image::DynamicImage;
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::color_management::srgb_to_linear;
+use crate::procedural::Pattern;
+
+// Un nivel de la cadena, en colores lineales 0-255
+struct Level {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+}
+
+impl Level {
+    fn texel(&self, x: isize, y: isize) -> Color {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    // Filtrado bilineal: mezcla los cuatro texeles cuyos centros rodean a (u, v)
+    fn bilinear(&self, u: f32, v: f32) -> Color {
+        let x = u.clamp(0.0, 1.0) * self.width as f32 - 0.5;
+        let y = v.clamp(0.0, 1.0) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let top = self.texel(x0, y0).blend(self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).blend(self.texel(x0 + 1, y0 + 1), fx);
+        top.blend(bottom, fy)
+    }
+
+    // Mitad de tamaño (redondeando hacia arriba), cada texel el promedio de hasta 2x2 del anterior
+    fn halved(&self) -> Level {
+        let (width, height) = (self.width.div_ceil(2), self.height.div_ceil(2));
+        let texels = (0..width * height)
+            .map(|index| {
+                let (x, y) = ((index % width) as isize * 2, (index / width) as isize * 2);
+                (self.texel(x, y) + self.texel(x + 1, y) + self.texel(x, y + 1) + self.texel(x + 1, y + 1)) * 0.25
+            })
+            .collect();
+        Level { width, height, texels }
+    }
+}
+
+// Textura con sus mipmaps precalculados: cada nivel es la mitad del anterior hasta 1x1.
+// De lejos un pixel cubre muchos texeles y el nivel fino parpadea al mover la cámara;
+// un nivel más chico ya trae el promedio
+pub struct Mipmap {
+    levels: Vec<Level>,
+}
+
+impl fmt::Debug for Mipmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mipmap({}x{}, {} niveles)", self.levels[0].width, self.levels[0].height, self.levels.len())
+    }
+}
+
+impl Mipmap {
+    pub fn size(&self) -> (usize, usize) {
+        (self.levels[0].width, self.levels[0].height)
+    }
+
+    // Los pixeles de la imagen vienen en sRGB; se pasan a lineal antes de promediar los niveles
+    pub fn new(image: &DynamicImage) -> Self {
+        let image = image.to_rgb8();
+        let linear: [f32; 256] = std::array::from_fn(|value| srgb_to_linear(value as f32 / 255.0) * 255.0);
+        let texels = image.pixels().map(|pixel| Color::new(linear[pixel[0] as usize], linear[pixel[1] as usize], linear[pixel[2] as usize])).collect();
+        let mut levels = vec![Level { width: image.width().max(1) as usize, height: image.height().max(1) as usize, texels }];
+        while let Some(last) = levels.last()
+            && (last.width > 1 || last.height > 1)
+        {
+            let next = last.halved();
+            levels.push(next);
+        }
+        Mipmap { levels }
+    }
+
+    // Color en (u, v) según cuánto de la textura cubre el pixel (`footprint`, en unidades de
+    // UV; 0 = el nivel más fino). Bilineal en los dos niveles más cercanos y mezcla entre ellos
+    pub fn sample(&self, (u, v): (f32, f32), footprint: f32) -> Color {
+        let base = &self.levels[0];
+        let texels = footprint * base.width.max(base.height) as f32;
+        let lod = if texels > 1.0 { texels.log2().min((self.levels.len() - 1) as f32) } else { 0.0 };
+        let fine = lod.floor() as usize;
+        let color = self.levels[fine].bilinear(u, v);
+        match self.levels.get(fine + 1) {
+            Some(coarse) if lod > fine as f32 => color.blend(coarse.bilinear(u, v), lod - fine as f32),
+            _ => color,
+        }
+    }
+}
+
+// Color base de un material: una imagen con sus mipmaps o un patrón calculado
+#[derive(Debug, Clone)]
+pub enum Texture {
+    Image(Arc<Mipmap>),
+    Procedural(Pattern),
+}
+
+impl Texture {
+    // `footprint` es lo que mide el pixel en UV y `pixel_size` en el mundo (para los patrones
+    // evaluados en la posición). None si la textura necesita UV y la superficie no tiene
+    pub fn sample(&self, uv: Option<(f32, f32)>, point: &Vec3, footprint: f32, pixel_size: f32) -> Option<Color> {
+        match self {
+            Texture::Image(mipmap) => uv.map(|uv| mipmap.sample(uv, footprint)),
+            Texture::Procedural(pattern) => pattern.sample(uv, point, if pattern.world { pixel_size } else { footprint }),
+        }
+    }
+}
+
+// Textura de una cara de cubo: una imagen entera o una región de un atlas
+#[derive(Debug, Clone)]
+pub struct FaceTexture {
+    pub mipmap: Arc<Mipmap>,
+    // Esquinas de la región en UV de la imagen: u0, v0, u1, v1
+    pub region: [f32; 4],
+}
+
+impl FaceTexture {
+    // Región en pixeles (x, y, ancho, alto) de la imagen; sin región, la imagen entera
+    pub fn new(mipmap: Arc<Mipmap>, region: Option<[u32; 4]>) -> Self {
+        let (width, height) = mipmap.size();
+        let region = match region {
+            Some([x, y, w, h]) => [
+                x as f32 / width as f32,
+                y as f32 / height as f32,
+                (x + w) as f32 / width as f32,
+                (y + h) as f32 / height as f32,
+            ],
+            None => [0.0, 0.0, 1.0, 1.0],
+        };
+        FaceTexture { mipmap, region }
+    }
+
+    // Como `Mipmap::sample` con (u, v) de la cara. Se queda medio texel adentro de la región
+    // para que el filtrado no tome pixeles de la región vecina del atlas
+    pub fn sample(&self, (u, v): (f32, f32), footprint: f32) -> Color {
+        let [u0, v0, u1, v1] = self.region;
+        let (width, height) = self.mipmap.size();
+        let (inset_u, inset_v) = (0.5 / width as f32, 0.5 / height as f32);
+        let u = (u0 + u.clamp(0.0, 1.0) * (u1 - u0)).clamp(u0 + inset_u, (u1 - inset_u).max(u0 + inset_u));
+        let v = (v0 + v.clamp(0.0, 1.0) * (v1 - v0)).clamp(v0 + inset_v, (v1 - inset_v).max(v0 + inset_v));
+        self.mipmap.sample((u, v), footprint * (u1 - u0).max(v1 - v0))
+    }
+}
@@ -0,0 +1,113 @@
+// tonemap.rs
+
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::dither;
+use crate::framebuffer::Framebuffer;
+use crate::settings::RenderSettings;
+use crate::watchdog;
+
+// Curva que lleva la radiancia lineal, que puede pasarse de 255 donde se suman luces,
+// al rango de pantalla sin recortar de golpe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    // x / (1 + x): suave, nunca llega al blanco
+    Reinhard,
+    // Ajuste de Narkowicz de la curva filmica de ACES: más contraste y blancos que se queman
+    Aces,
+}
+
+impl ToneMap {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "reinhard" => Some(ToneMap::Reinhard),
+            "aces" => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+
+    fn curve(self, value: f32) -> f32 {
+        let value = value.max(0.0);
+        match self {
+            ToneMap::Reinhard => value / (1.0 + value),
+            ToneMap::Aces => (value * (2.51 * value + 0.03) / (value * (2.43 * value + 0.59) + 0.14)).clamp(0.0, 1.0),
+        }
+    }
+
+    // Color lineal en 0-255 que puede pasarse de 255 a color lineal dentro de 0-255; la
+    // curva de salida se aplica después, en `dither::quantize`
+    pub fn apply(self, color: Color) -> Color {
+        let [r, g, b] = [color.r, color.g, color.b].map(|value| self.curve(value / 255.0) * 255.0);
+        Color::new(r, g, b)
+    }
+}
+
+// Pasa el cuadro HDR a los pixeles de 8 bits: exposición, mapeo de tonos (si hay; si no,
+// se recorta), curva del espacio de color de salida (sRGB por defecto) y tramado
+pub fn resolve(framebuffer: &mut Framebuffer, settings: &RenderSettings) {
+    let (width, exposure) = (framebuffer.width, framebuffer.exposure);
+    let hdr = &framebuffer.hdr;
+    framebuffer.buffer.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let color = hdr[y * width + x];
+            *pixel = if settings.watchdog && watchdog::is_invalid(&color) {
+                watchdog::INVALID_COLOR.to_hex()
+            } else {
+                dither::quantize(color * exposure, x, y, settings)
+            };
+        }
+    });
+}
+
+// Como `resolve`, pero a tres canales de 16 bits sacados directamente del cuadro HDR, para
+// los PNG de 16 bits. Lo que se dibuja sobre los pixeles de 8 bits después de `resolve`
+// (guías, destellos, rótulos) no aparece aquí
+pub fn resolve_16(framebuffer: &Framebuffer, settings: &RenderSettings) -> Vec<u16> {
+    let exposure = framebuffer.exposure;
+    framebuffer
+        .hdr
+        .par_iter()
+        .flat_map_iter(|&color| {
+            if settings.watchdog && watchdog::is_invalid(&color) {
+                let invalid = watchdog::INVALID_COLOR;
+                [invalid.r, invalid.g, invalid.b].map(|value| value as u16 * 257)
+            } else {
+                dither::quantize_16(color * exposure, settings)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ignores_case() {
+        assert_eq!(ToneMap::parse("Reinhard"), Some(ToneMap::Reinhard));
+        assert_eq!(ToneMap::parse("ACES"), Some(ToneMap::Aces));
+        assert_eq!(ToneMap::parse("filmic"), None);
+    }
+
+    #[test]
+    fn reinhard_halves_white_and_never_reaches_it() {
+        let white = ToneMap::Reinhard.apply(Color::new(255.0, 255.0, 255.0));
+        assert!((white.r - 127.5).abs() < 1e-3);
+        assert!(ToneMap::Reinhard.apply(Color::new(255_000.0, 0.0, 0.0)).r < 255.0);
+    }
+
+    #[test]
+    fn curves_start_at_black_and_keep_order() {
+        for tone_map in [ToneMap::Reinhard, ToneMap::Aces] {
+            assert_eq!(tone_map.apply(Color::black()), Color::black());
+            // Lo negativo (que no debería llegar) se trata como negro
+            assert_eq!(tone_map.apply(Color::new(-10.0, 0.0, 0.0)).r, 0.0);
+            let values: Vec<f32> = (0..200).map(|step| tone_map.apply(Color::new(step as f32 * 20.0, 0.0, 0.0)).r).collect();
+            assert!(values.windows(2).all(|pair| pair[0] <= pair[1]), "{:?} no es creciente", tone_map);
+            assert!(values.iter().all(|&value| (0.0..=255.0).contains(&value)));
+        }
+        // ACES quema los blancos muy brillantes
+        assert_eq!(ToneMap::Aces.apply(Color::new(255_000.0, 0.0, 0.0)).r, 255.0);
+    }
+}
@@ -0,0 +1,161 @@
+// transformed.rs
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use nalgebra_glm::{self as glm, Mat3, Vec3};
+
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::obj_export::ObjTransform;
+use crate::object::{Object, SceneObject};
+use crate::ray::Ray;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Escala por eje y después giro en grados alrededor de X, después Y, después Z
+pub fn linear([x, y, z]: [f32; 3], scale: Vec3) -> Mat3 {
+    let turn = |degrees: f32, axis: Vec3| glm::mat4_to_mat3(&glm::rotation(degrees.to_radians(), &axis));
+    turn(z, Vec3::z()) * turn(y, Vec3::y()) * turn(x, Vec3::x()) * Mat3::from_diagonal(&scale)
+}
+
+// Objeto escalado y girado alrededor de su centro y después desplazado `translation`, por
+// ejemplo un cubo de costado o aplastado. El objeto de adentro sigue alineado a los ejes:
+// en vez de transformarlo se lleva cada rayo a su espacio. Varios pueden compartir el mismo
+// objeto de adentro (las instancias de un prototipo): cada uno guarda solo su transformación
+pub struct Transformed {
+    pub object: Arc<dyn SceneObject>,
+    pub translation: Vec3,
+    // Giro por escala; `inverse` lleva del mundo al objeto
+    linear: Mat3,
+    inverse: Mat3,
+}
+
+impl Transformed {
+    // None si `linear` aplasta el objeto a nada
+    pub fn new(object: impl Into<Arc<dyn SceneObject>>, translation: Vec3, linear: Mat3) -> Option<Self> {
+        let inverse = linear.try_inverse()?;
+        Some(Transformed { object: object.into(), translation, linear, inverse })
+    }
+
+    // El objeto de adentro para editarlo; si otras instancias lo comparten, esta pasa a
+    // tener su propia copia
+    fn object_mut(&mut self) -> &mut dyn SceneObject {
+        if Arc::get_mut(&mut self.object).is_none() {
+            self.object = Arc::from(self.object.box_clone());
+        }
+        Arc::get_mut(&mut self.object).expect("la copia recién hecha no se comparte")
+    }
+
+    fn to_world(&self, point: &Vec3) -> Vec3 {
+        let pivot = self.object.center();
+        pivot + self.translation + self.linear * (point - pivot)
+    }
+
+    // Las normales se transforman con la inversa transpuesta para seguir perpendiculares
+    // a la superficie cuando la escala no es uniforme
+    fn normal_to_world(&self, normal: &Vec3) -> Vec3 {
+        (self.inverse.transpose() * normal).normalize()
+    }
+
+    fn direction_to_world(&self, direction: &Vec3) -> Vec3 {
+        let direction = self.linear * direction;
+        if direction.norm() > 0.0 { direction.normalize() } else { direction }
+    }
+}
+
+impl RayIntersect for Transformed {
+    fn ray_intersect(&self, ray: &Ray) -> Intersect {
+        // La dirección no se normaliza: así t es el mismo en los dos espacios y el intervalo
+        // del rayo sigue valiendo
+        let pivot = self.object.center();
+        let local_ray = Ray {
+            origin: pivot + self.inverse * (ray.origin - pivot - self.translation),
+            direction: self.inverse * ray.direction,
+            ..*ray
+        };
+        let mut intersect = self.object.ray_intersect(&local_ray);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+        intersect.point = self.to_world(&intersect.point);
+        intersect.normal = self.normal_to_world(&intersect.normal);
+        intersect.tangent = self.direction_to_world(&intersect.tangent);
+        intersect.bitangent = self.direction_to_world(&intersect.bitangent);
+        // Una unidad de UV se estira con la escala media
+        intersect.uv_scale *= (self.linear.determinant().abs()).cbrt();
+        intersect
+    }
+}
+
+impl SceneObject for Transformed {
+    fn kind(&self) -> &'static str {
+        self.object.kind()
+    }
+
+    fn center(&self) -> Vec3 {
+        self.object.center() + self.translation
+    }
+
+    // Se mueve la instancia, no el objeto compartido
+    fn set_center(&mut self, center: Vec3) {
+        self.translation = center - self.object.center();
+    }
+
+    // El de adentro por la escala mayor, para que la caja lo siga conteniendo al editarlo
+    fn size(&self) -> f32 {
+        let stretch = (0..3).map(|axis| self.linear.column(axis).norm()).fold(0.0, f32::max);
+        self.object.size() * stretch
+    }
+
+    fn set_size(&mut self, size: f32) {
+        let ratio = size / self.size().max(1e-6);
+        let size = self.object.size() * ratio;
+        self.object_mut().set_size(size);
+    }
+
+    fn material(&self) -> &Material {
+        self.object.material()
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        self.object_mut().material_mut()
+    }
+
+    // La caja de las 8 esquinas de la del objeto ya transformadas
+    fn bounds(&self) -> Option<Aabb> {
+        let local = self.object.bounds()?;
+        let corners = (0..8).map(|corner| {
+            let pick = |bit: usize, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+            self.to_world(&Vec3::new(
+                pick(1, local.min.x, local.max.x),
+                pick(2, local.min.y, local.max.y),
+                pick(4, local.min.z, local.max.z),
+            ))
+        });
+        Aabb::enclosing(corners.map(|corner| Aabb::new(corner, corner)))
+    }
+
+    fn linear(&self) -> Mat3 {
+        self.linear * self.object.linear()
+    }
+
+    fn velocity(&self) -> Vec3 {
+        self.linear * self.object.velocity()
+    }
+
+    fn box_clone(&self) -> Object {
+        Box::new(Transformed {
+            object: self.object.clone(),
+            translation: self.translation,
+            linear: self.linear,
+            inverse: self.inverse,
+        })
+    }
+
+    // to_world(p) = linear · p + (pivot + translation - linear · pivot)
+    fn write_obj(&self, obj: &mut dyn Write, vertex_offset: usize, transform: &ObjTransform) -> io::Result<usize> {
+        let pivot = self.object.center();
+        let transform = transform.after(&self.linear, &self.inverse, &(pivot + self.translation - self.linear * pivot));
+        self.object.write_obj(obj, vertex_offset, &transform)
+    }
+}
@@ -0,0 +1,40 @@
+// watchdog.rs
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::ray::Ray;
+
+// Color con que se pintan los pixeles inválidos, imposible de confundir con la escena
+pub const INVALID_COLOR: Color = Color { r: 255.0, g: 0.0, b: 255.0 };
+
+// Solo se informa el primer rayo: los siguientes suelen ser el mismo error repetido
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
+// NaN, infinito o negativo: casi siempre un vector cero normalizado o una normal degenerada
+pub fn is_invalid(color: &Color) -> bool {
+    [color.r, color.g, color.b].iter().any(|value| !value.is_finite() || *value < 0.0)
+}
+
+// Informa el contexto del rayo primario que dio un color inválido, solo la primera vez
+pub fn report(color: Color, x: usize, y: usize, ray: &Ray, scene: &Bvh) {
+    if REPORTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    eprintln!("color inválido en el pixel ({}, {}): {:?}", x, y, color);
+    eprintln!("  rayo: origen {:?}, dirección {:?}", ray.origin.as_slice(), ray.direction.as_slice());
+    let intersect = scene.intersect(ray);
+    if intersect.is_intersecting {
+        eprintln!(
+            "  impacto: punto {:?}, normal {:?}, distancia {}, uv {:?}, material {}",
+            intersect.point.as_slice(),
+            intersect.normal.as_slice(),
+            intersect.distance,
+            intersect.uv,
+            intersect.material.name.as_deref().unwrap_or("sin nombre"),
+        );
+    } else {
+        eprintln!("  sin impacto (fondo)");
+    }
+}